@@ -0,0 +1,505 @@
+use crate::polygon_with_holes::even_odd_contains;
+use crate::{line, Line, Polygon, PolygonWithHoles};
+use euclid::Point2D;
+use fart_aabb::ToAabb;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+
+/// A boolean operation between two polygons. Used with
+/// `PolygonWithHoles::boolean`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BooleanOp {
+    /// Everything that is in either polygon.
+    Union,
+
+    /// Everything that is in both polygons.
+    Intersection,
+
+    /// Everything that is in the first polygon, but not in the second.
+    Difference,
+
+    /// Everything that is in exactly one of the two polygons.
+    Xor,
+}
+
+impl<U> PolygonWithHoles<f64, U> {
+    /// Combine this polygon with another using the given boolean operation.
+    ///
+    /// Both polygons may be concave and have holes. The result is zero or more
+    /// disjoint polygons, which may have holes of their own.
+    ///
+    /// The boundaries of the two polygons are split wherever they cross, each
+    /// piece is kept or dropped depending on whether it lies inside or outside
+    /// of the other polygon, and the kept pieces are linked back together into
+    /// rings. Pieces of boundary that the polygons share are handled as well,
+    /// so polygons that exactly abut each other combine cleanly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::{BooleanOp, Polygon, PolygonWithHoles};
+    ///
+    /// let square = |x0: f64, y0: f64, x1: f64, y1: f64| -> PolygonWithHoles<f64, UnknownUnit> {
+    ///     Polygon::new(vec![
+    ///         point2(x0, y0),
+    ///         point2(x1, y0),
+    ///         point2(x1, y1),
+    ///         point2(x0, y1),
+    ///     ]).into()
+    /// };
+    ///
+    /// let big = square(0.0, 0.0, 10.0, 10.0);
+    /// let small = square(2.0, 2.0, 8.0, 8.0);
+    ///
+    /// // Cutting the small square out of the big one leaves a frame.
+    /// let frame = big.boolean(&small, BooleanOp::Difference);
+    /// assert_eq!(frame.len(), 1);
+    /// assert_eq!(frame[0].holes().len(), 1);
+    /// assert_eq!(frame[0].area(), 64.0);
+    ///
+    /// // Two overlapping squares merge into one shape.
+    /// let union = square(0.0, 0.0, 2.0, 2.0).union(&square(1.0, 1.0, 3.0, 3.0));
+    /// assert_eq!(union.len(), 1);
+    /// assert_eq!(union[0].area(), 7.0);
+    /// ```
+    pub fn boolean(
+        &self,
+        other: &PolygonWithHoles<f64, U>,
+        op: BooleanOp,
+    ) -> Vec<PolygonWithHoles<f64, U>> {
+        let mut graph = Graph::new(tolerance(self, other));
+        graph.insert_split_edges(self, other);
+
+        // Decide which pieces of boundary make it into the result, and which
+        // way around they go.
+        let a_edges: HashSet<_> = graph.edges_of(Owner::A).collect();
+        let b_edges: HashSet<_> = graph.edges_of(Owner::B).collect();
+        let mut kept = vec![];
+        for edge in &graph.edges {
+            let (others, other_polygon) = match edge.owner {
+                Owner::A => (&b_edges, other),
+                Owner::B => (&a_edges, self),
+            };
+            let location = if others.contains(&(edge.from, edge.to)) {
+                Location::SharedSameDirection
+            } else if others.contains(&(edge.to, edge.from)) {
+                Location::SharedOppositeDirection
+            } else {
+                let mid = graph.points[edge.from].lerp(graph.points[edge.to], 0.5);
+                if other_polygon.contains_point(mid) {
+                    Location::Inside
+                } else {
+                    Location::Outside
+                }
+            };
+            match keep(op, edge.owner, location) {
+                Keep::No => {}
+                Keep::Forwards => kept.push((edge.from, edge.to)),
+                Keep::Backwards => kept.push((edge.to, edge.from)),
+            }
+        }
+
+        graph.assemble(&kept)
+    }
+
+    /// Get the union of this polygon and the other polygon.
+    ///
+    /// See `PolygonWithHoles::boolean` for details.
+    pub fn union(&self, other: &PolygonWithHoles<f64, U>) -> Vec<PolygonWithHoles<f64, U>> {
+        self.boolean(other, BooleanOp::Union)
+    }
+
+    /// Get the intersection of this polygon and the other polygon.
+    ///
+    /// See `PolygonWithHoles::boolean` for details.
+    pub fn intersection(&self, other: &PolygonWithHoles<f64, U>) -> Vec<PolygonWithHoles<f64, U>> {
+        self.boolean(other, BooleanOp::Intersection)
+    }
+
+    /// Get this polygon with the other polygon cut out of it.
+    ///
+    /// See `PolygonWithHoles::boolean` for details.
+    pub fn difference(&self, other: &PolygonWithHoles<f64, U>) -> Vec<PolygonWithHoles<f64, U>> {
+        self.boolean(other, BooleanOp::Difference)
+    }
+
+    /// Get the parts that are in exactly one of this polygon and the other
+    /// polygon.
+    ///
+    /// See `PolygonWithHoles::boolean` for details.
+    pub fn xor(&self, other: &PolygonWithHoles<f64, U>) -> Vec<PolygonWithHoles<f64, U>> {
+        self.boolean(other, BooleanOp::Xor)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Owner {
+    A,
+    B,
+}
+
+/// Where a piece of one polygon's boundary lies relative to the other polygon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Location {
+    Inside,
+    Outside,
+    SharedSameDirection,
+    SharedOppositeDirection,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Keep {
+    No,
+    Forwards,
+    Backwards,
+}
+
+/// All boundary edges are directed so that their polygon's interior is on
+/// their left. A kept edge must still have the result's interior on its left.
+fn keep(op: BooleanOp, owner: Owner, location: Location) -> Keep {
+    use self::BooleanOp::*;
+    use self::Location::*;
+
+    match (op, owner, location) {
+        (Union, _, Outside) | (Intersection, _, Inside) => Keep::Forwards,
+        (Union, _, Inside) | (Intersection, _, Outside) => Keep::No,
+
+        (Difference, Owner::A, Outside) => Keep::Forwards,
+        (Difference, Owner::A, Inside) | (Difference, Owner::B, Outside) => Keep::No,
+        (Difference, Owner::B, Inside) => Keep::Backwards,
+
+        (Xor, _, Outside) => Keep::Forwards,
+        (Xor, _, Inside) => Keep::Backwards,
+
+        // Both interiors are on the same side of a shared edge: it bounds the
+        // union and intersection, but only one copy of it should be kept.
+        (Union, Owner::A, SharedSameDirection) | (Intersection, Owner::A, SharedSameDirection) => {
+            Keep::Forwards
+        }
+        (Union, Owner::B, SharedSameDirection)
+        | (Intersection, Owner::B, SharedSameDirection)
+        | (Difference, _, SharedSameDirection)
+        | (Xor, _, SharedSameDirection) => Keep::No,
+
+        // The interiors are on either side of a shared edge: only the
+        // difference has one side in and the other side out.
+        (Difference, Owner::A, SharedOppositeDirection) => Keep::Forwards,
+        (_, _, SharedOppositeDirection) => Keep::No,
+    }
+}
+
+/// How close two points have to be to be considered the same point.
+fn tolerance<U>(a: &PolygonWithHoles<f64, U>, b: &PolygonWithHoles<f64, U>) -> f64 {
+    let scale = a
+        .exterior()
+        .vertices()
+        .iter()
+        .chain(b.exterior().vertices())
+        .map(|p| p.x.abs().max(p.y.abs()))
+        .fold(1.0, f64::max);
+    scale * 1e-9
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    from: usize,
+    to: usize,
+    owner: Owner,
+}
+
+/// The split up boundaries of both polygons, sharing vertices wherever they
+/// meet.
+struct Graph<U> {
+    tolerance: f64,
+    points: Vec<Point2D<f64, U>>,
+    grid: HashMap<(i64, i64), Vec<usize>>,
+    edges: Vec<Edge>,
+}
+
+impl<U> Graph<U> {
+    fn new(tolerance: f64) -> Graph<U> {
+        Graph {
+            tolerance,
+            points: vec![],
+            grid: HashMap::new(),
+            edges: vec![],
+        }
+    }
+
+    fn cell(&self, p: Point2D<f64, U>) -> (i64, i64) {
+        let size = self.tolerance * 4.0;
+        ((p.x / size).floor() as i64, (p.y / size).floor() as i64)
+    }
+
+    /// Get the index of the vertex at `p`, snapping to any existing vertex
+    /// within the tolerance.
+    fn vertex(&mut self, p: Point2D<f64, U>) -> usize {
+        let (cx, cy) = self.cell(p);
+        for x in cx - 1..=cx + 1 {
+            for y in cy - 1..=cy + 1 {
+                if let Some(candidates) = self.grid.get(&(x, y)) {
+                    for &i in candidates {
+                        if (self.points[i] - p).length() <= self.tolerance {
+                            return i;
+                        }
+                    }
+                }
+            }
+        }
+        let i = self.points.len();
+        self.points.push(p);
+        self.grid.entry((cx, cy)).or_default().push(i);
+        i
+    }
+
+    fn edges_of<'a>(&'a self, owner: Owner) -> impl 'a + Iterator<Item = (usize, usize)> {
+        self.edges
+            .iter()
+            .filter(move |e| e.owner == owner)
+            .map(|e| (e.from, e.to))
+    }
+
+    /// Insert both polygons' boundaries, split everywhere they touch or cross
+    /// each other.
+    fn insert_split_edges(&mut self, a: &PolygonWithHoles<f64, U>, b: &PolygonWithHoles<f64, U>) {
+        let a_edges = directed_edges(a);
+        let b_edges = directed_edges(b);
+        let mut a_splits: Vec<_> = a_edges.iter().map(|e| vec![e.a, e.b]).collect();
+        let mut b_splits: Vec<_> = b_edges.iter().map(|e| vec![e.a, e.b]).collect();
+
+        let b_aabbs: Vec<_> = b_edges.iter().map(|e| e.to_aabb()).collect();
+        for (i, ea) in a_edges.iter().enumerate() {
+            let aabb = ea.to_aabb();
+            for (j, eb) in b_edges.iter().enumerate() {
+                if !overlaps(&aabb, &b_aabbs[j], self.tolerance) {
+                    continue;
+                }
+                for p in intersections(ea, eb, self.tolerance) {
+                    a_splits[i].push(p);
+                    b_splits[j].push(p);
+                }
+            }
+        }
+
+        self.insert_edges(&a_edges, a_splits, Owner::A);
+        self.insert_edges(&b_edges, b_splits, Owner::B);
+    }
+
+    fn insert_edges(
+        &mut self,
+        edges: &[Line<f64, U>],
+        splits: Vec<Vec<Point2D<f64, U>>>,
+        owner: Owner,
+    ) {
+        for (edge, mut points) in edges.iter().zip(splits) {
+            let d = edge.b - edge.a;
+            points.sort_by(|p, q| {
+                let s = (*p - edge.a).dot(d);
+                let t = (*q - edge.a).dot(d);
+                s.partial_cmp(&t).unwrap()
+            });
+            let mut vertices: Vec<_> = points.into_iter().map(|p| self.vertex(p)).collect();
+            vertices.dedup();
+            for w in vertices.windows(2) {
+                self.edges.push(Edge {
+                    from: w[0],
+                    to: w[1],
+                    owner,
+                });
+            }
+        }
+    }
+
+    /// Link the given directed edges together into rings, and turn those into
+    /// polygons with holes.
+    fn assemble(&self, edges: &[(usize, usize)]) -> Vec<PolygonWithHoles<f64, U>> {
+        let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, &(from, _)) in edges.iter().enumerate() {
+            outgoing.entry(from).or_default().push(i);
+        }
+
+        let mut used = vec![false; edges.len()];
+        let mut rings = vec![];
+        for start in 0..edges.len() {
+            if used[start] {
+                continue;
+            }
+            used[start] = true;
+
+            let mut ring = vec![self.points[edges[start].0]];
+            let mut current = start;
+            loop {
+                let (from, at) = edges[current];
+                let back = self.points[from] - self.points[at];
+
+                // Where several edges leave the same vertex, take the one
+                // closest clockwise from the edge we arrived on. That is the
+                // edge bounding the same piece of interior, so rings that only
+                // touch at a vertex are kept apart.
+                let next = outgoing
+                    .get(&at)
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .filter(|&i| !used[i] || i == start)
+                    .map(|i| {
+                        let out = self.points[edges[i].1] - self.points[at];
+                        let mut angle =
+                            (back.y.atan2(back.x) - out.y.atan2(out.x)).rem_euclid(2.0 * PI);
+                        if angle == 0.0 {
+                            angle = 2.0 * PI;
+                        }
+                        (i, angle)
+                    })
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                    .map(|(i, _)| i);
+
+                match next {
+                    Some(i) if i == start => {
+                        rings.push(ring);
+                        break;
+                    }
+                    Some(i) => {
+                        used[i] = true;
+                        ring.push(self.points[at]);
+                        current = i;
+                    }
+                    // Only possible with degenerate input; drop the ring.
+                    None => break,
+                }
+            }
+        }
+
+        self.rings_to_polygons(rings)
+    }
+
+    fn rings_to_polygons(&self, rings: Vec<Vec<Point2D<f64, U>>>) -> Vec<PolygonWithHoles<f64, U>> {
+        // Counter-clockwise rings are exteriors, and clockwise rings are holes.
+        let mut exteriors = vec![];
+        let mut holes = vec![];
+        for mut ring in rings {
+            remove_straight_vertices(&mut ring, self.tolerance);
+            if ring.len() < 3 {
+                continue;
+            }
+            let area = signed_area(&ring);
+            if area.abs() <= self.tolerance * self.tolerance {
+                continue;
+            }
+            if area > 0.0 {
+                exteriors.push((area, Polygon::new(ring), vec![]));
+            } else {
+                holes.push(ring);
+            }
+        }
+
+        // Each hole belongs to the smallest exterior that it is inside of.
+        for mut hole in holes {
+            // A point just inside the hole, which is to the right of its edges.
+            // It is the tolerance away from the edge, however long the edge is,
+            // so that it stays within thin holes, and doesn't depend on how
+            // big the coordinates are.
+            let (a, b) = (hole[0], hole[1]);
+            let d = b - a;
+            let inside = a.lerp(b, 0.5) + euclid::vec2(d.y, -d.x) * (self.tolerance / d.length());
+
+            let owner = exteriors
+                .iter_mut()
+                .filter(|(_, exterior, _)| even_odd_contains(exterior.edges(), inside))
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            if let Some((_, _, owned)) = owner {
+                hole.reverse();
+                owned.push(Polygon::new(hole));
+            }
+        }
+
+        exteriors
+            .into_iter()
+            .map(|(_, exterior, holes)| PolygonWithHoles::new(exterior, holes))
+            .collect()
+    }
+}
+
+/// Get the edges of all of the polygon's rings, directed such that the
+/// polygon's interior is on their left.
+fn directed_edges<U>(polygon: &PolygonWithHoles<f64, U>) -> Vec<Line<f64, U>> {
+    let exterior = polygon.exterior().edges();
+    let holes = polygon
+        .holes()
+        .iter()
+        .flat_map(|h| h.edges())
+        .map(|e| line(e.b, e.a));
+    exterior.chain(holes).filter(|e| e.a != e.b).collect()
+}
+
+fn overlaps<U>(a: &fart_aabb::Aabb<f64, U>, b: &fart_aabb::Aabb<f64, U>, tolerance: f64) -> bool {
+    a.min().x <= b.max().x + tolerance
+        && b.min().x <= a.max().x + tolerance
+        && a.min().y <= b.max().y + tolerance
+        && b.min().y <= a.max().y + tolerance
+}
+
+/// Get the points where the two line segments touch or cross. Collinear
+/// segments that overlap touch at the ends of the overlap.
+fn intersections<U>(e: &Line<f64, U>, f: &Line<f64, U>, tolerance: f64) -> Vec<Point2D<f64, U>> {
+    let r = e.b - e.a;
+    let s = f.b - f.a;
+    let (r_len, s_len) = (r.length(), s.length());
+    let qp = f.a - e.a;
+    let denominator = r.cross(s);
+
+    if denominator.abs() > 1e-12 * r_len * s_len {
+        let t = qp.cross(s) / denominator;
+        let u = qp.cross(r) / denominator;
+        let (t_tol, u_tol) = (tolerance / r_len, tolerance / s_len);
+        if -t_tol <= t && t <= 1.0 + t_tol && -u_tol <= u && u <= 1.0 + u_tol {
+            vec![e.a.lerp(e.b, t.clamp(0.0, 1.0))]
+        } else {
+            vec![]
+        }
+    } else if qp.cross(r).abs() / r_len > tolerance {
+        // Parallel, but not on the same line.
+        vec![]
+    } else {
+        let on = |l: &Line<f64, U>, p: Point2D<f64, U>| {
+            let d = l.b - l.a;
+            let t = (p - l.a).dot(d) / d.square_length();
+            let t_tol = tolerance / d.length();
+            -t_tol <= t && t <= 1.0 + t_tol
+        };
+        [(e, f.a), (e, f.b), (f, e.a), (f, e.b)]
+            .iter()
+            .filter(|(l, p)| on(l, *p))
+            .map(|&(_, p)| p)
+            .collect()
+    }
+}
+
+/// Remove vertices where the ring just continues straight on.
+fn remove_straight_vertices<U>(ring: &mut Vec<Point2D<f64, U>>, tolerance: f64) {
+    let mut i = 0;
+    while ring.len() >= 3 && i < ring.len() {
+        let n = ring.len();
+        let prev = ring[(i + n - 1) % n];
+        let next = ring[(i + 1) % n];
+        let (d1, d2) = (ring[i] - prev, next - ring[i]);
+        let straight =
+            d1.cross(d2).abs() <= tolerance * (d1.length() + d2.length()) && d1.dot(d2) >= 0.0;
+        if straight || ring[i] == prev {
+            ring.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+fn signed_area<U>(ring: &[Point2D<f64, U>]) -> f64 {
+    let mut sum = 0.0;
+    for (i, a) in ring.iter().enumerate() {
+        let b = ring[(i + 1) % ring.len()];
+        sum += a.x * b.y - b.x * a.y;
+    }
+    sum / 2.0
+}
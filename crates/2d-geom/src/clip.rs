@@ -0,0 +1,216 @@
+use crate::polygon_with_holes::even_odd_contains;
+use crate::{line, ConvexPolygon, Line, Polygon, PolygonWithHoles, Polyline};
+use euclid::{point2, Point2D};
+use fart_aabb::{Aabb, ToAabb};
+
+/// Which part of a polyline to keep when clipping it against a mask. Used with
+/// `Polyline::clip`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Keep {
+    /// Keep the parts inside of the mask.
+    Inside,
+
+    /// Keep the parts outside of the mask.
+    Outside,
+}
+
+/// A closed shape that polylines can be clipped against.
+///
+/// Implemented for `Polygon`, `ConvexPolygon`, `PolygonWithHoles`, and `Aabb`.
+pub trait ClipMask<U> {
+    /// Get the edges of this mask's boundary.
+    fn boundary(&self) -> Vec<Line<f64, U>>;
+
+    /// Does this mask contain the given point?
+    ///
+    /// It doesn't matter whether points exactly on the boundary are contained
+    /// or not.
+    fn contains(&self, point: Point2D<f64, U>) -> bool;
+}
+
+impl<M, U> ClipMask<U> for &M
+where
+    M: ClipMask<U>,
+{
+    fn boundary(&self) -> Vec<Line<f64, U>> {
+        (*self).boundary()
+    }
+
+    fn contains(&self, point: Point2D<f64, U>) -> bool {
+        (*self).contains(point)
+    }
+}
+
+impl<U> ClipMask<U> for Polygon<f64, U> {
+    fn boundary(&self) -> Vec<Line<f64, U>> {
+        self.edges().collect()
+    }
+
+    fn contains(&self, point: Point2D<f64, U>) -> bool {
+        even_odd_contains(self.edges(), point)
+    }
+}
+
+impl<U> ClipMask<U> for ConvexPolygon<f64, U> {
+    fn boundary(&self) -> Vec<Line<f64, U>> {
+        self.edges().collect()
+    }
+
+    fn contains(&self, point: Point2D<f64, U>) -> bool {
+        self.contains_point(point)
+    }
+}
+
+impl<U> ClipMask<U> for PolygonWithHoles<f64, U> {
+    fn boundary(&self) -> Vec<Line<f64, U>> {
+        self.edges().collect()
+    }
+
+    fn contains(&self, point: Point2D<f64, U>) -> bool {
+        self.contains_point(point)
+    }
+}
+
+impl<U> ClipMask<U> for Aabb<f64, U> {
+    fn boundary(&self) -> Vec<Line<f64, U>> {
+        let (min, max) = (self.min(), self.max());
+        let corners = [min, point2(max.x, min.y), max, point2(min.x, max.y)];
+        (0..4)
+            .map(|i| line(corners[i], corners[(i + 1) % 4]))
+            .collect()
+    }
+
+    fn contains(&self, point: Point2D<f64, U>) -> bool {
+        self.contains_point(point)
+    }
+}
+
+impl<U> Polyline<f64, U> {
+    /// Clip this polyline against the given mask, keeping either the parts
+    /// inside or the parts outside of it.
+    ///
+    /// Returns the kept parts as separate polylines, in order along this
+    /// polyline. If this polyline is closed (it ends where it starts) and the
+    /// cut runs across its start, the pieces on either side of the start are
+    /// joined back together. A polyline without any length is a dot, and is
+    /// kept whole if its point is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::{Keep, Polyline};
+    /// use fart_aabb::Aabb;
+    ///
+    /// let zigzag = Polyline::<f64, UnknownUnit>::new(vec![
+    ///     point2(0.0, 0.0),
+    ///     point2(4.0, 4.0),
+    ///     point2(8.0, 0.0),
+    /// ]);
+    /// let mask = Aabb::new(point2(0.0, 0.0), point2(8.0, 2.0));
+    ///
+    /// let inside = zigzag.clip(&mask, Keep::Inside);
+    /// assert_eq!(inside, vec![
+    ///     Polyline::new(vec![point2(0.0, 0.0), point2(2.0, 2.0)]),
+    ///     Polyline::new(vec![point2(6.0, 2.0), point2(8.0, 0.0)]),
+    /// ]);
+    ///
+    /// let outside = zigzag.clip(&mask, Keep::Outside);
+    /// assert_eq!(outside, vec![
+    ///     Polyline::new(vec![point2(2.0, 2.0), point2(4.0, 4.0), point2(6.0, 2.0)]),
+    /// ]);
+    /// ```
+    pub fn clip<M>(&self, mask: &M, keep: Keep) -> Vec<Polyline<f64, U>>
+    where
+        M: ClipMask<U>,
+    {
+        if self.vertices.windows(2).all(|w| w[0] == w[1]) {
+            return if mask.contains(self.vertices[0]) == (keep == Keep::Inside) {
+                vec![Polyline {
+                    vertices: self.vertices.clone(),
+                }]
+            } else {
+                vec![]
+            };
+        }
+
+        let boundary = mask.boundary();
+        let boundary_aabbs: Vec<_> = boundary.iter().map(|e| e.to_aabb()).collect();
+
+        let mut pieces = vec![];
+        let mut current: Vec<Point2D<f64, U>> = vec![];
+        let mut starts_kept = None;
+        let mut ts = vec![];
+        for w in self.vertices.windows(2) {
+            let segment = line(w[0], w[1]);
+            if segment.a == segment.b {
+                continue;
+            }
+
+            // Split this segment wherever it crosses the mask's boundary.
+            ts.clear();
+            ts.push((0.0, segment.a));
+            let aabb = segment.to_aabb();
+            for (edge, edge_aabb) in boundary.iter().zip(&boundary_aabbs) {
+                if aabb.intersects(edge_aabb) {
+                    crossings(&segment, edge, &mut ts);
+                }
+            }
+            ts.push((1.0, segment.b));
+            ts.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            ts.dedup_by(|a, b| a.0 == b.0);
+
+            // Keep or drop every piece between two crossings.
+            for t in ts.windows(2) {
+                let mid = t[0].1.lerp(t[1].1, 0.5);
+                let kept = mask.contains(mid) == (keep == Keep::Inside);
+                starts_kept.get_or_insert(kept);
+                if kept {
+                    if current.is_empty() {
+                        current.push(t[0].1);
+                    }
+                    current.push(t[1].1);
+                } else if !current.is_empty() {
+                    pieces.push(Polyline::new(std::mem::take(&mut current)));
+                }
+            }
+        }
+
+        let ends_kept = !current.is_empty();
+        if ends_kept {
+            pieces.push(Polyline::new(current));
+        }
+
+        // Rejoin a closed polyline that was cut somewhere other than at its
+        // start.
+        let is_closed = self.vertices.first() == self.vertices.last();
+        if is_closed && starts_kept == Some(true) && ends_kept && pieces.len() >= 2 {
+            let first = pieces.remove(0);
+            let last = pieces.last_mut().unwrap();
+            last.vertices.extend(first.vertices.into_iter().skip(1));
+        }
+
+        pieces
+    }
+}
+
+/// Push the parameters along `segment` where it crosses `edge`, along with the
+/// crossing points.
+fn crossings<U>(segment: &Line<f64, U>, edge: &Line<f64, U>, ts: &mut Vec<(f64, Point2D<f64, U>)>) {
+    let r = segment.b - segment.a;
+    let s = edge.b - edge.a;
+    let denominator = r.cross(s);
+    if denominator == 0.0 {
+        // Parallel segments don't cross. If they overlap, the pieces along the
+        // overlap are split where the neighbouring edges cross instead.
+        return;
+    }
+    let qp = edge.a - segment.a;
+    let t = qp.cross(s) / denominator;
+    let u = qp.cross(r) / denominator;
+    if 0.0 < t && t < 1.0 && (0.0..=1.0).contains(&u) {
+        // Take the point along the edge, so that it lies exactly on
+        // horizontal and vertical edges.
+        ts.push((t, edge.a.lerp(edge.b, u)));
+    }
+}
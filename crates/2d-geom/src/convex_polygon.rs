@@ -1,4 +1,4 @@
-use crate::{line, sort_around, Polygon};
+use crate::{is_counter_clockwise, line, sort_around, GeomError, Polygon};
 use euclid::{point2, Point2D};
 use fart_aabb::{Aabb, ToAabb};
 use fart_utils::NoMorePartial;
@@ -95,7 +95,26 @@ where
     /// assert!(ConvexPolygon::<i32, UnknownUnit>::hull(vec![point2(0, 0), point2(1, 1)]).is_none());
     /// assert!(ConvexPolygon::<i32, UnknownUnit>::hull(vec![point2(0, 0), point2(1, 1), point2(2, 2)]).is_none());
     /// ```
-    pub fn hull(mut vertices: Vec<Point2D<T, U>>) -> Option<ConvexPolygon<T, U>> {
+    pub fn hull(vertices: Vec<Point2D<T, U>>) -> Option<ConvexPolygon<T, U>> {
+        ConvexPolygon::try_hull(vertices).ok()
+    }
+
+    /// Compute the convex hull of the given vertices, or return an error if
+    /// the convex hull doesn't have any area.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::{ConvexPolygon, GeomError};
+    ///
+    /// let collinear = vec![point2(0, 0), point2(1, 1), point2(2, 2)];
+    /// assert_eq!(
+    ///     ConvexPolygon::<i32, UnknownUnit>::try_hull(collinear).unwrap_err(),
+    ///     GeomError::Degenerate,
+    /// );
+    /// ```
+    pub fn try_hull(mut vertices: Vec<Point2D<T, U>>) -> Result<ConvexPolygon<T, U>, GeomError> {
         let max = vertices
             .iter()
             .cloned()
@@ -111,7 +130,7 @@ where
         vertices.dedup();
 
         if vertices.len() < 3 {
-            return None;
+            return Err(GeomError::Degenerate);
         }
 
         debug_assert_eq!(max, vertices.last().cloned().unwrap());
@@ -139,11 +158,11 @@ where
         }
 
         if stack.len() < 3 {
-            return None;
+            return Err(GeomError::Degenerate);
         }
 
-        Some(ConvexPolygon {
-            inner: Polygon::new(stack),
+        Ok(ConvexPolygon {
+            inner: Polygon::try_new(stack)?,
         })
     }
 
@@ -196,6 +215,84 @@ where
     }
 }
 
+impl<U> ConvexPolygon<f64, U> {
+    /// Get the intersection of this convex polygon with another convex
+    /// polygon, if they overlap.
+    ///
+    /// The intersection of two convex polygons is always a single convex
+    /// polygon, which makes this much cheaper than the general
+    /// `PolygonWithHoles::intersection`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::ConvexPolygon;
+    ///
+    /// let a = ConvexPolygon::<f64, UnknownUnit>::hull(vec![
+    ///     point2(0.0, 0.0),
+    ///     point2(2.0, 0.0),
+    ///     point2(2.0, 2.0),
+    ///     point2(0.0, 2.0),
+    /// ]).unwrap();
+    /// let b = ConvexPolygon::<f64, UnknownUnit>::hull(vec![
+    ///     point2(1.0, 1.0),
+    ///     point2(3.0, 1.0),
+    ///     point2(3.0, 3.0),
+    ///     point2(1.0, 3.0),
+    /// ]).unwrap();
+    ///
+    /// let overlap = a.intersection(&b).unwrap();
+    /// assert_eq!(overlap.area(), 1.0);
+    ///
+    /// let far_away = ConvexPolygon::<f64, UnknownUnit>::hull(vec![
+    ///     point2(10.0, 10.0),
+    ///     point2(11.0, 10.0),
+    ///     point2(11.0, 11.0),
+    /// ]).unwrap();
+    /// assert!(a.intersection(&far_away).is_none());
+    /// ```
+    pub fn intersection(&self, other: &ConvexPolygon<f64, U>) -> Option<ConvexPolygon<f64, U>> {
+        // Sutherland-Hodgman: clip this polygon by each of the other polygon's
+        // edges in turn.
+        let mut vertices = self.vertices().to_vec();
+        for edge in other.edges() {
+            if vertices.is_empty() {
+                return None;
+            }
+            let side = |p: Point2D<f64, U>| (edge.b - edge.a).cross(p - edge.a);
+            let input = std::mem::take(&mut vertices);
+            let mut previous = input[input.len() - 1];
+            for &current in &input {
+                let (s, t) = (side(previous), side(current));
+                if (s >= 0.0) != (t >= 0.0) {
+                    vertices.push(previous.lerp(current, s / (s - t)));
+                }
+                if t >= 0.0 {
+                    vertices.push(current);
+                }
+                previous = current;
+            }
+        }
+
+        vertices.dedup();
+        while vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        // Rounding can leave a sliver of zero, or even slightly negative, area
+        // when the polygons only touch.
+        if vertices.len() < 3 || !is_counter_clockwise(&vertices) {
+            return None;
+        }
+        let inner = Polygon::new(vertices);
+        if inner.area() > 0.0 {
+            Some(ConvexPolygon { inner })
+        } else {
+            None
+        }
+    }
+}
+
 impl<T, U> ConvexPolygon<T, U>
 where
     T: Copy + NumCast,
@@ -228,7 +325,7 @@ where
 
 impl<T, U> ConvexPolygon<T, U>
 where
-    T: Copy + Num + PartialOrd
+    T: Copy + Num + PartialOrd,
 {
     /// Transform this convex polygon with the given linear transformation and
     /// return the new, transformed convex polygon.
@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::fmt;
+
+/// An error constructing a shape from invalid vertices.
+///
+/// Returned by the `try_` constructors, like `Polygon::try_new`, whose
+/// non-`try_` counterparts panic instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeomError {
+    /// There are not enough vertices for the shape.
+    TooFewVertices {
+        /// The smallest number of vertices that the shape can have.
+        required: usize,
+        /// The number of vertices given.
+        actual: usize,
+    },
+
+    /// A polygon's vertices are not in counter-clockwise order.
+    NotCounterClockwise,
+
+    /// The vertices don't enclose any area: they are all collinear, or there
+    /// are fewer than three distinct ones.
+    Degenerate,
+
+    /// The random distributions kept generating points that were already
+    /// used, so a random polygon could not be made.
+    NoUniqueRandomPoint,
+}
+
+impl fmt::Display for GeomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeomError::TooFewVertices { required, actual } => write!(
+                f,
+                "expected at least {} vertices, but got {}",
+                required, actual
+            ),
+            GeomError::NotCounterClockwise => write!(f, "vertices are not counter clockwise"),
+            GeomError::Degenerate => write!(f, "vertices do not enclose any area"),
+            GeomError::NoUniqueRandomPoint => write!(
+                f,
+                "failed to generate a new unique random point with the given distributions"
+            ),
+        }
+    }
+}
+
+impl Error for GeomError {}
@@ -2,12 +2,20 @@
 
 #![deny(missing_docs, missing_debug_implementations)]
 
+mod boolean;
+mod clip;
 mod convex_polygon;
+mod error;
 mod line;
 mod polygon;
+mod polygon_with_holes;
 mod polyline;
+mod simplify;
 
-pub use crate::{convex_polygon::*, line::*, polygon::*, polyline::*};
+pub use crate::{
+    boolean::*, clip::*, convex_polygon::*, error::*, line::*, polygon::*, polygon_with_holes::*,
+    polyline::*,
+};
 
 use euclid::{point2, Point2D};
 use num_traits::{Num, NumAssign, NumCast, Signed};
@@ -1,4 +1,4 @@
-use crate::{area2, is_counter_clockwise, line, Line};
+use crate::{area2, is_counter_clockwise, line, GeomError, Line};
 use euclid::{point2, Point2D};
 use fart_aabb::{Aabb, ToAabb};
 use fart_utils::NoMorePartial;
@@ -43,6 +43,12 @@ where
     T: Copy + NumAssign + PartialOrd + Signed + fmt::Debug,
 {
     /// Construct a new polygon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than three vertices, or if they are not in
+    /// counter-clockwise order. See `Polygon::try_new` for a version that
+    /// returns an error instead.
     pub fn new(vertices: Vec<Point2D<T, U>>) -> Polygon<T, U> {
         assert!(vertices.len() >= 3);
         assert!(
@@ -53,6 +59,32 @@ where
         Polygon { vertices }
     }
 
+    /// Construct a new polygon, or return an error if there are fewer than
+    /// three vertices, or if they are not in counter-clockwise order.
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::{GeomError, Polygon};
+    ///
+    /// let clockwise = vec![point2(0, 0), point2(0, 1), point2(1, 0)];
+    /// assert_eq!(
+    ///     Polygon::<i32, UnknownUnit>::try_new(clockwise).unwrap_err(),
+    ///     GeomError::NotCounterClockwise,
+    /// );
+    /// ```
+    pub fn try_new(vertices: Vec<Point2D<T, U>>) -> Result<Polygon<T, U>, GeomError> {
+        if vertices.len() < 3 {
+            return Err(GeomError::TooFewVertices {
+                required: 3,
+                actual: vertices.len(),
+            });
+        }
+        if !is_counter_clockwise(&vertices) {
+            return Err(GeomError::NotCounterClockwise);
+        }
+        Ok(Polygon { vertices })
+    }
+
     /// Generate a random `n`-gon with the given `x` an `y` point distributions.
     ///
     /// ```
@@ -69,6 +101,12 @@ where
     ///     5
     /// );
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is less than three, or if the distributions keep
+    /// generating points that were already used. See `Polygon::try_random`
+    /// for a version that returns an error instead.
     pub fn random(
         rng: &mut dyn RngCore,
         x_dist: &mut impl Distribution<T>,
@@ -78,7 +116,45 @@ where
     where
         T: NumCast,
     {
-        assert!(n >= 3);
+        match Polygon::try_random(rng, x_dist, y_dist, n) {
+            Ok(polygon) => polygon,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Generate a random `n`-gon with the given `x` an `y` point
+    /// distributions, or return an error if `n` is less than three, or if the
+    /// distributions keep generating points that were already used.
+    ///
+    /// ```
+    /// use euclid::UnknownUnit;
+    /// use fart_2d_geom::{GeomError, Polygon};
+    /// use rand::{thread_rng, distributions::Uniform};
+    ///
+    /// // There are only four distinct points to choose from.
+    /// let result = Polygon::<i32, UnknownUnit>::try_random(
+    ///     &mut thread_rng(),
+    ///     &mut Uniform::new(0, 2),
+    ///     &mut Uniform::new(0, 2),
+    ///     5
+    /// );
+    /// assert_eq!(result.unwrap_err(), GeomError::NoUniqueRandomPoint);
+    /// ```
+    pub fn try_random(
+        rng: &mut dyn RngCore,
+        x_dist: &mut impl Distribution<T>,
+        y_dist: &mut impl Distribution<T>,
+        n: usize,
+    ) -> Result<Polygon<T, U>, GeomError>
+    where
+        T: NumCast,
+    {
+        if n < 3 {
+            return Err(GeomError::TooFewVertices {
+                required: 3,
+                actual: n,
+            });
+        }
 
         let mut vertices_set = BTreeSet::new();
         let mut point = move |rng: &mut dyn RngCore| {
@@ -86,16 +162,16 @@ where
                 let x = x_dist.sample(rng);
                 let y = y_dist.sample(rng);
                 if vertices_set.insert(NoMorePartial((x, y))) {
-                    return point2(x, y);
+                    return Ok(point2(x, y));
                 }
             }
-            panic!("failed to generate a new unique random point with the given distributions")
+            Err(GeomError::NoUniqueRandomPoint)
         };
 
         let mut vertices = Vec::with_capacity(n);
-        vertices.push(point(rng));
-        vertices.push(point(rng));
-        vertices.push(point(rng));
+        vertices.push(point(rng)?);
+        vertices.push(point(rng)?);
+        vertices.push(point(rng)?);
 
         // Ensure that the vertices are in counter-clockwise order.
         if !is_counter_clockwise(&vertices) {
@@ -105,7 +181,7 @@ where
 
         let mut candidates = HashSet::new();
         for _ in 3..n {
-            let v = point(rng);
+            let v = point(rng)?;
             for i in 0..vertices.len() {
                 let l = line(vertices[(i + vertices.len() - 1) % vertices.len()], v);
                 let m = line(v, vertices[i]);
@@ -124,7 +200,7 @@ where
             }
         }
 
-        return Polygon::new(vertices);
+        return Polygon::try_new(vertices);
 
         fn any_edges_collide_with<T, U>(
             vertices: &[Point2D<T, U>],
@@ -503,7 +579,7 @@ where
 
 impl<T, U> Polygon<T, U>
 where
-    T: Copy + Num + PartialOrd
+    T: Copy + Num + PartialOrd,
 {
     /// Transform this polygon with the given linear transformation and return
     /// the new, transformed polygon.
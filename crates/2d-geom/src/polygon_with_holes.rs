@@ -0,0 +1,204 @@
+use crate::{ConvexPolygon, Line, Polygon};
+use euclid::Point2D;
+use fart_aabb::{Aabb, ToAabb};
+use num_traits::{Num, NumAssign, Signed};
+use std::fmt;
+
+/// A polygon with zero or more holes cut out of it.
+///
+/// Both the exterior and the holes are `Polygon`s, so all of their vertices are
+/// in counter-clockwise order. The holes are expected to lie within the
+/// exterior and not to overlap each other.
+///
+/// * `T` is the numeric type. `i32` or `f64` etc.
+/// * `U` is the unit. `ScreenSpace` or `WorldSpace` etc.
+#[derive(Clone)]
+pub struct PolygonWithHoles<T, U> {
+    exterior: Polygon<T, U>,
+    holes: Vec<Polygon<T, U>>,
+}
+
+impl<T, U> fmt::Debug for PolygonWithHoles<T, U>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PolygonWithHoles")
+            .field("exterior", &self.exterior)
+            .field("holes", &self.holes)
+            .finish()
+    }
+}
+
+impl<T, U> From<Polygon<T, U>> for PolygonWithHoles<T, U> {
+    #[inline]
+    fn from(exterior: Polygon<T, U>) -> PolygonWithHoles<T, U> {
+        PolygonWithHoles {
+            exterior,
+            holes: vec![],
+        }
+    }
+}
+
+impl<T, U> From<ConvexPolygon<T, U>> for PolygonWithHoles<T, U> {
+    #[inline]
+    fn from(c: ConvexPolygon<T, U>) -> PolygonWithHoles<T, U> {
+        Polygon::from(c).into()
+    }
+}
+
+impl<T, U> PolygonWithHoles<T, U> {
+    /// Construct a new polygon with the given holes cut out of it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::{Polygon, PolygonWithHoles};
+    ///
+    /// let frame = PolygonWithHoles::<i32, UnknownUnit>::new(
+    ///     Polygon::new(vec![
+    ///         point2(0, 0),
+    ///         point2(10, 0),
+    ///         point2(10, 10),
+    ///         point2(0, 10),
+    ///     ]),
+    ///     vec![Polygon::new(vec![
+    ///         point2(2, 2),
+    ///         point2(8, 2),
+    ///         point2(8, 8),
+    ///         point2(2, 8),
+    ///     ])],
+    /// );
+    ///
+    /// assert_eq!(frame.holes().len(), 1);
+    /// ```
+    pub fn new(exterior: Polygon<T, U>, holes: Vec<Polygon<T, U>>) -> PolygonWithHoles<T, U> {
+        PolygonWithHoles { exterior, holes }
+    }
+
+    /// Get this polygon's exterior boundary.
+    pub fn exterior(&self) -> &Polygon<T, U> {
+        &self.exterior
+    }
+
+    /// Get this polygon's holes.
+    pub fn holes(&self) -> &[Polygon<T, U>] {
+        &self.holes
+    }
+
+    /// Iterate over all of this polygon's boundaries: first the exterior,
+    /// followed by each of the holes.
+    pub fn rings<'a>(&'a self) -> impl 'a + Iterator<Item = &'a Polygon<T, U>> {
+        Some(&self.exterior).into_iter().chain(&self.holes)
+    }
+}
+
+impl<T, U> PolygonWithHoles<T, U>
+where
+    T: Copy + NumAssign + PartialOrd + Signed + fmt::Debug,
+{
+    /// Get the area of this polygon, not counting its holes.
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::{Polygon, PolygonWithHoles};
+    ///
+    /// let frame = PolygonWithHoles::<i32, UnknownUnit>::new(
+    ///     Polygon::new(vec![
+    ///         point2(0, 0),
+    ///         point2(10, 0),
+    ///         point2(10, 10),
+    ///         point2(0, 10),
+    ///     ]),
+    ///     vec![Polygon::new(vec![
+    ///         point2(2, 2),
+    ///         point2(8, 2),
+    ///         point2(8, 8),
+    ///         point2(2, 8),
+    ///     ])],
+    /// );
+    ///
+    /// assert_eq!(frame.area(), 64);
+    /// ```
+    pub fn area(&self) -> T {
+        let mut area = self.exterior.area();
+        for hole in &self.holes {
+            area -= hole.area();
+        }
+        area
+    }
+
+    /// Iterate over the edges of all of this polygon's boundaries.
+    pub fn edges<'a>(&'a self) -> impl 'a + Iterator<Item = Line<T, U>> {
+        self.rings().flat_map(|r| r.edges())
+    }
+
+    /// Does this polygon contain the given point?
+    ///
+    /// Points inside a hole are not contained. It is unspecified whether
+    /// points exactly on a boundary are contained or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::{Polygon, PolygonWithHoles};
+    ///
+    /// let frame = PolygonWithHoles::<i32, UnknownUnit>::new(
+    ///     Polygon::new(vec![
+    ///         point2(0, 0),
+    ///         point2(10, 0),
+    ///         point2(10, 10),
+    ///         point2(0, 10),
+    ///     ]),
+    ///     vec![Polygon::new(vec![
+    ///         point2(2, 2),
+    ///         point2(8, 2),
+    ///         point2(8, 8),
+    ///         point2(2, 8),
+    ///     ])],
+    /// );
+    ///
+    /// assert!(frame.contains_point(point2(1, 5)));
+    /// assert!(!frame.contains_point(point2(5, 5)));
+    /// assert!(!frame.contains_point(point2(11, 5)));
+    /// ```
+    pub fn contains_point(&self, point: Point2D<T, U>) -> bool {
+        even_odd_contains(self.edges(), point)
+    }
+}
+
+/// Cast a ray from `point` towards positive `x` and count how many of the
+/// given edges it crosses. Each crossing flips between outside and inside.
+pub(crate) fn even_odd_contains<T, U>(
+    edges: impl Iterator<Item = Line<T, U>>,
+    point: Point2D<T, U>,
+) -> bool
+where
+    T: Copy + Num + PartialOrd,
+{
+    let mut inside = false;
+    for Line { a, b } in edges {
+        if (a.y > point.y) != (b.y > point.y) {
+            // Is the crossing right of `point`? Compare without dividing by
+            // `b.y - a.y`, so that this works for integers too.
+            let lhs = (b.x - a.x) * (point.y - a.y);
+            let rhs = (point.x - a.x) * (b.y - a.y);
+            if (b.y > a.y && lhs > rhs) || (b.y < a.y && lhs < rhs) {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+impl<T, U> ToAabb<T, U> for PolygonWithHoles<T, U>
+where
+    T: Copy + Num + PartialOrd,
+{
+    fn to_aabb(&self) -> Aabb<T, U> {
+        // The holes are within the exterior, so they can't grow its AABB.
+        self.exterior.to_aabb()
+    }
+}
@@ -1,3 +1,4 @@
+use crate::GeomError;
 use euclid::Point2D;
 
 /// A Polyline is a line along multiple points. Like a Polygon that is not closed.
@@ -12,12 +13,29 @@ where
     T: Copy + PartialOrd
 {
     /// Construct a new Polyline
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than two vertices. See `Polyline::try_new`
+    /// for a version that returns an error instead.
     pub fn new(vertices: Vec<Point2D<T, U>>) -> Polyline<T, U> {
         assert!(vertices.len() >= 2);
 
         Polyline { vertices }
     }
 
+    /// Construct a new Polyline, or return an error if there are fewer than
+    /// two vertices.
+    pub fn try_new(vertices: Vec<Point2D<T, U>>) -> Result<Polyline<T, U>, GeomError> {
+        if vertices.len() < 2 {
+            return Err(GeomError::TooFewVertices {
+                required: 2,
+                actual: vertices.len(),
+            });
+        }
+        Ok(Polyline { vertices })
+    }
+
     /// All points stored for this line
     pub fn vertices(&self) -> &[Point2D<T, U>] {
         &self.vertices
@@ -0,0 +1,164 @@
+use crate::{area2, Polyline};
+use euclid::Point2D;
+use fart_utils::NoMorePartial;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+impl<U> Polyline<f64, U> {
+    /// Simplify this polyline with the Ramer-Douglas-Peucker algorithm.
+    ///
+    /// Removes as many vertices as possible while keeping the simplified
+    /// polyline within `tolerance` of every removed vertex. The first and last
+    /// vertices are always kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::Polyline;
+    ///
+    /// let wobbly = Polyline::<f64, UnknownUnit>::new(vec![
+    ///     point2(0.0, 0.0),
+    ///     point2(1.0, 0.1),
+    ///     point2(2.0, -0.1),
+    ///     point2(3.0, 5.0),
+    ///     point2(4.0, 6.0),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     wobbly.simplify(0.7),
+    ///     Polyline::new(vec![point2(0.0, 0.0), point2(2.0, -0.1), point2(4.0, 6.0)]),
+    /// );
+    /// ```
+    pub fn simplify(&self, tolerance: f64) -> Polyline<f64, U> {
+        let vertices = &self.vertices;
+        let mut keep = vec![false; vertices.len()];
+        keep[0] = true;
+        keep[vertices.len() - 1] = true;
+
+        // Use an explicit stack, since polylines can be long enough to
+        // overflow the call stack.
+        let mut stack = vec![(0, vertices.len() - 1)];
+        while let Some((first, last)) = stack.pop() {
+            let farthest = (first + 1..last)
+                .map(|i| {
+                    (
+                        i,
+                        distance_to_segment(vertices[i], vertices[first], vertices[last]),
+                    )
+                })
+                .max_by_key(|&(_, d)| NoMorePartial(d));
+            if let Some((i, distance)) = farthest {
+                if distance > tolerance {
+                    keep[i] = true;
+                    stack.push((first, i));
+                    stack.push((i, last));
+                }
+            }
+        }
+
+        Polyline::new(
+            vertices
+                .iter()
+                .zip(keep)
+                .filter(|&(_, keep)| keep)
+                .map(|(&v, _)| v)
+                .collect(),
+        )
+    }
+
+    /// Simplify this polyline with the Visvalingam-Whyatt algorithm.
+    ///
+    /// Repeatedly removes the vertex that forms the smallest triangle with its
+    /// neighbors, until every remaining triangle has an area of at least
+    /// `min_area`. This tends to keep the overall shape better than
+    /// `simplify` does, at the cost of not bounding the distance to the
+    /// removed vertices. The first and last vertices are always kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_2d_geom::Polyline;
+    ///
+    /// let wobbly = Polyline::<f64, UnknownUnit>::new(vec![
+    ///     point2(0.0, 0.0),
+    ///     point2(1.0, 0.1),
+    ///     point2(2.0, 0.0),
+    ///     point2(3.0, 3.0),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     wobbly.simplify_visvalingam(0.5),
+    ///     Polyline::new(vec![point2(0.0, 0.0), point2(2.0, 0.0), point2(3.0, 3.0)]),
+    /// );
+    /// ```
+    pub fn simplify_visvalingam(&self, min_area: f64) -> Polyline<f64, U> {
+        let vertices = &self.vertices;
+        let n = vertices.len();
+        let mut previous: Vec<usize> = (0..n).map(|i| i.saturating_sub(1)).collect();
+        let mut next: Vec<usize> = (0..n).map(|i| (i + 1).min(n - 1)).collect();
+        let mut areas = vec![f64::INFINITY; n];
+        let triangle =
+            |a: usize, b: usize, c: usize| area2(vertices[a], vertices[b], vertices[c]).abs() / 2.0;
+
+        let mut heap = BinaryHeap::new();
+        for (i, area) in areas.iter_mut().enumerate().take(n - 1).skip(1) {
+            *area = triangle(i - 1, i, i + 1);
+            heap.push(Reverse((NoMorePartial(*area), i)));
+        }
+
+        while let Some(Reverse((NoMorePartial(area), i))) = heap.pop() {
+            // Skip entries for removed vertices, and outdated entries for
+            // vertices whose area changed since.
+            if area != areas[i] {
+                continue;
+            }
+            if area >= min_area {
+                break;
+            }
+
+            areas[i] = f64::NAN;
+            let (p, q) = (previous[i], next[i]);
+            next[p] = q;
+            previous[q] = p;
+
+            // A neighbor's area can shrink when this vertex is removed, but it
+            // must not be removed before this vertex, so it keeps at least
+            // this vertex's area.
+            for &j in &[p, q] {
+                if j != 0 && j != n - 1 {
+                    areas[j] = triangle(previous[j], j, next[j]).max(area);
+                    heap.push(Reverse((NoMorePartial(areas[j]), j)));
+                }
+            }
+        }
+
+        Polyline::new(
+            vertices
+                .iter()
+                .zip(&areas)
+                .filter(|&(_, area)| !area.is_nan())
+                .map(|(&v, _)| v)
+                .collect(),
+        )
+    }
+}
+
+/// Get the distance from `p` to the line segment from `a` to `b`.
+fn distance_to_segment<U>(p: Point2D<f64, U>, a: Point2D<f64, U>, b: Point2D<f64, U>) -> f64 {
+    let ab = b - a;
+    let length_squared = ab.square_length();
+    if length_squared == 0.0 {
+        return (p - a).length();
+    }
+    let t = (p - a).dot(ab) / length_squared;
+    if t <= 0.0 {
+        (p - a).length()
+    } else if t >= 1.0 {
+        (p - b).length()
+    } else {
+        // Collinear points are exactly zero away this way.
+        ab.cross(p - a).abs() / length_squared.sqrt()
+    }
+}
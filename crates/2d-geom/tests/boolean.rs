@@ -0,0 +1,176 @@
+use euclid::{point2, UnknownUnit};
+use fart_2d_geom::{BooleanOp, ConvexPolygon, Polygon, PolygonWithHoles};
+use quickcheck::quickcheck;
+
+type Shape = PolygonWithHoles<f64, UnknownUnit>;
+
+fn rect(x: f64, y: f64, w: f64, h: f64) -> Shape {
+    Polygon::new(vec![
+        point2(x, y),
+        point2(x + w, y),
+        point2(x + w, y + h),
+        point2(x, y + h),
+    ])
+    .into()
+}
+
+fn diamond(x: f64, y: f64, r: f64) -> Shape {
+    Polygon::new(vec![
+        point2(x, y - r),
+        point2(x + r, y),
+        point2(x, y + r),
+        point2(x - r, y),
+    ])
+    .into()
+}
+
+// Keep arbitrary shapes reasonably sized and non-degenerate.
+fn arbitrary_rect((x, y, w, h): (i8, i8, u8, u8)) -> Shape {
+    rect(x.into(), y.into(), (w % 50 + 1).into(), (h % 50 + 1).into())
+}
+
+fn arbitrary_diamond((x, y, r): (i8, i8, u8)) -> Shape {
+    diamond(x.into(), y.into(), (r % 50 + 1).into())
+}
+
+fn arbitrary_frame((x, y, w, h): (i8, i8, u8, u8)) -> Shape {
+    let (x, y, w, h) = (
+        x.into(),
+        y.into(),
+        f64::from(w % 50 + 1),
+        f64::from(h % 50 + 1),
+    );
+    let outer = rect(x - 1.0, y - 1.0, w + 2.0, h + 2.0);
+    let hole = rect(x, y, w, h);
+    PolygonWithHoles::new(outer.exterior().clone(), vec![hole.exterior().clone()])
+}
+
+fn total_area(shapes: &[Shape]) -> f64 {
+    shapes.iter().map(|s| s.area()).sum()
+}
+
+fn check_area_identities(a: &Shape, b: &Shape) -> bool {
+    let union = total_area(&a.union(b));
+    let intersection = total_area(&a.intersection(b));
+    let difference = total_area(&a.difference(b));
+    let xor = total_area(&a.xor(b));
+
+    let close = |x: f64, y: f64| (x - y).abs() < 1e-6 * (1.0 + x.abs().max(y.abs()));
+    close(union + intersection, a.area() + b.area())
+        && close(difference, a.area() - intersection)
+        && close(xor, union - intersection)
+}
+
+quickcheck! {
+    fn rectangle_area_identities(a: (i8, i8, u8, u8), b: (i8, i8, u8, u8)) -> bool {
+        check_area_identities(&arbitrary_rect(a), &arbitrary_rect(b))
+    }
+
+    fn rectangle_intersection_area(a: (i8, i8, u8, u8), b: (i8, i8, u8, u8)) -> bool {
+        let (ra, rb) = (arbitrary_rect(a), arbitrary_rect(b));
+        let (ea, eb) = (ra.exterior().vertices(), rb.exterior().vertices());
+        let w = (ea[2].x.min(eb[2].x) - ea[0].x.max(eb[0].x)).max(0.0);
+        let h = (ea[2].y.min(eb[2].y) - ea[0].y.max(eb[0].y)).max(0.0);
+        (total_area(&ra.intersection(&rb)) - w * h).abs() < 1e-6
+    }
+
+    fn rectangle_and_diamond_area_identities(a: (i8, i8, u8, u8), b: (i8, i8, u8)) -> bool {
+        check_area_identities(&arbitrary_rect(a), &arbitrary_diamond(b))
+    }
+
+    fn frame_and_diamond_area_identities(a: (i8, i8, u8, u8), b: (i8, i8, u8)) -> bool {
+        check_area_identities(&arbitrary_frame(a), &arbitrary_diamond(b))
+    }
+
+    fn convex_intersection_matches_general_intersection(a: (i8, i8, u8, u8), b: (i8, i8, u8)) -> bool {
+        let convex = |s: &Shape| {
+            ConvexPolygon::<f64, UnknownUnit>::hull(s.exterior().vertices().to_vec()).unwrap()
+        };
+        let (ra, db) = (arbitrary_rect(a), arbitrary_diamond(b));
+        let fast = convex(&ra).intersection(&convex(&db)).map_or(0.0, |p| p.area());
+        (fast - total_area(&ra.intersection(&db))).abs() < 1e-6
+    }
+}
+
+#[test]
+fn difference_of_contained_shape_makes_a_hole() {
+    let frame = rect(0.0, 0.0, 10.0, 10.0).difference(&rect(2.0, 2.0, 6.0, 6.0));
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].holes().len(), 1);
+    assert_eq!(frame[0].area(), 100.0 - 36.0);
+
+    // And filling the hole back in leaves no hole.
+    let filled = frame[0].union(&rect(2.0, 2.0, 6.0, 6.0));
+    assert_eq!(filled.len(), 1);
+    assert!(filled[0].holes().is_empty());
+    assert_eq!(filled[0].exterior().len(), 4);
+}
+
+#[test]
+fn long_thin_holes_are_kept() {
+    // The hole is much narrower than it is long, and close to the edges of
+    // the shape around it.
+    let frame = rect(0.0, 0.0, 1000.0, 3e-4).difference(&rect(0.5, 1e-4, 999.0, 1e-4));
+    assert_eq!(frame.len(), 1);
+    assert_eq!(frame[0].holes().len(), 1);
+    assert!((frame[0].area() - (0.3 - 0.0999)).abs() < 1e-9);
+}
+
+#[test]
+fn abutting_shapes_merge_along_their_shared_edge() {
+    let union = rect(0.0, 0.0, 2.0, 2.0).union(&rect(2.0, 0.0, 2.0, 2.0));
+    assert_eq!(union.len(), 1);
+    assert_eq!(union[0].exterior().len(), 4);
+    assert_eq!(union[0].area(), 8.0);
+
+    // They only touch, so they have no intersection.
+    assert!(rect(0.0, 0.0, 2.0, 2.0)
+        .intersection(&rect(2.0, 0.0, 2.0, 2.0))
+        .is_empty());
+}
+
+#[test]
+fn shapes_touching_at_a_corner_stay_separate() {
+    let union = rect(0.0, 0.0, 2.0, 2.0).union(&rect(2.0, 2.0, 2.0, 2.0));
+    assert_eq!(union.len(), 2);
+    for shape in &union {
+        assert_eq!(shape.exterior().len(), 4);
+        assert_eq!(shape.area(), 4.0);
+    }
+}
+
+#[test]
+fn disjoint_shapes() {
+    let (a, b) = (rect(0.0, 0.0, 2.0, 2.0), rect(5.0, 5.0, 2.0, 2.0));
+    assert_eq!(a.union(&b).len(), 2);
+    assert!(a.intersection(&b).is_empty());
+    assert_eq!(total_area(&a.difference(&b)), 4.0);
+    assert_eq!(total_area(&a.xor(&b)), 8.0);
+}
+
+#[test]
+fn concave_shapes_can_split_into_several() {
+    // A "U" shape, cut across its base, leaves its two arms.
+    let u: Shape = Polygon::new(vec![
+        point2(0.0, 0.0),
+        point2(3.0, 0.0),
+        point2(3.0, 3.0),
+        point2(2.0, 3.0),
+        point2(2.0, 1.0),
+        point2(1.0, 1.0),
+        point2(1.0, 3.0),
+        point2(0.0, 3.0),
+    ])
+    .into();
+    let arms = u.difference(&rect(-1.0, 0.0, 5.0, 2.0));
+    assert_eq!(arms.len(), 2);
+    for arm in &arms {
+        assert_eq!(arm.area(), 1.0);
+    }
+}
+
+#[test]
+fn xor_of_overlapping_shapes() {
+    let xor = rect(0.0, 0.0, 2.0, 2.0).boolean(&rect(1.0, 1.0, 2.0, 2.0), BooleanOp::Xor);
+    assert_eq!(total_area(&xor), 6.0);
+}
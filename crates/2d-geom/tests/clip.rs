@@ -0,0 +1,94 @@
+use euclid::{point2, Point2D, UnknownUnit};
+use fart_2d_geom::{ClipMask, ConvexPolygon, Keep, Polyline};
+use fart_aabb::Aabb;
+use quickcheck::quickcheck;
+
+fn length(polyline: &Polyline<f64, UnknownUnit>) -> f64 {
+    polyline
+        .vertices()
+        .windows(2)
+        .map(|w| (w[1] - w[0]).length())
+        .sum()
+}
+
+fn polyline(vertices: &[(i8, i8)]) -> Option<Polyline<f64, UnknownUnit>> {
+    if vertices.len() < 2 {
+        return None;
+    }
+    Some(Polyline::new(
+        vertices
+            .iter()
+            .map(|&(x, y)| point2(x.into(), y.into()))
+            .collect(),
+    ))
+}
+
+fn check_inside_and_outside_add_up<M>(line: &Polyline<f64, UnknownUnit>, mask: &M) -> bool
+where
+    M: ClipMask<UnknownUnit>,
+{
+    let inside = line.clip(mask, Keep::Inside);
+    let outside = line.clip(mask, Keep::Outside);
+
+    let total: f64 = inside.iter().chain(&outside).map(length).sum();
+    let all_inside = inside
+        .iter()
+        .flat_map(|p| p.vertices().windows(2))
+        .all(|w| mask.contains(w[0].lerp(w[1], 0.5)));
+    let all_outside = outside
+        .iter()
+        .flat_map(|p| p.vertices().windows(2))
+        .all(|w| !mask.contains(w[0].lerp(w[1], 0.5)));
+
+    (total - length(line)).abs() < 1e-6 && all_inside && all_outside
+}
+
+quickcheck! {
+    fn clipping_against_aabb_splits_length(vertices: Vec<(i8, i8)>) -> bool {
+        let mask = Aabb::new(point2(-50.0, -30.0), point2(40.0, 60.0));
+        match polyline(&vertices) {
+            None => true,
+            Some(line) => check_inside_and_outside_add_up(&line, &mask),
+        }
+    }
+
+    fn clipping_against_convex_polygon_splits_length(vertices: Vec<(i8, i8)>) -> bool {
+        let mask = ConvexPolygon::<f64, UnknownUnit>::hull(vec![
+            point2(0.0, -70.0),
+            point2(70.0, 10.0),
+            point2(-20.0, 80.0),
+            point2(-60.0, 0.0),
+        ])
+        .unwrap();
+        match polyline(&vertices) {
+            None => true,
+            Some(line) => check_inside_and_outside_add_up(&line, &mask),
+        }
+    }
+}
+
+#[test]
+fn closed_polylines_are_rejoined_across_their_start() {
+    let square: Vec<Point2D<f64, UnknownUnit>> = vec![
+        point2(0.0, 0.0),
+        point2(4.0, 0.0),
+        point2(4.0, 4.0),
+        point2(0.0, 4.0),
+        point2(0.0, 0.0),
+    ];
+    // Cut off the top right corner, which leaves the rest of the square in
+    // one piece even though it runs across the square's start.
+    let mask = Aabb::new(point2(2.0, 2.0), point2(10.0, 10.0));
+    let outside = Polyline::new(square).clip(&mask, Keep::Outside);
+    assert_eq!(outside.len(), 1);
+    assert_eq!(
+        outside[0].vertices(),
+        &[
+            point2(2.0, 4.0),
+            point2(0.0, 4.0),
+            point2(0.0, 0.0),
+            point2(4.0, 0.0),
+            point2(4.0, 2.0),
+        ]
+    );
+}
@@ -0,0 +1,106 @@
+use euclid::{point2, Point2D, UnknownUnit};
+use fart_2d_geom::Polyline;
+use quickcheck::quickcheck;
+
+type Point = Point2D<f64, UnknownUnit>;
+
+fn polyline(vertices: &[(i8, i8)]) -> Option<Polyline<f64, UnknownUnit>> {
+    if vertices.len() < 2 {
+        return None;
+    }
+    Some(Polyline::new(
+        vertices
+            .iter()
+            .map(|&(x, y)| point2(x.into(), y.into()))
+            .collect(),
+    ))
+}
+
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f64 {
+    let ab = b - a;
+    if ab.square_length() == 0.0 {
+        return (p - a).length();
+    }
+    let t = (p - a).dot(ab) / ab.square_length();
+    if t <= 0.0 {
+        (p - a).length()
+    } else if t >= 1.0 {
+        (p - b).length()
+    } else {
+        ab.cross(p - a).abs() / ab.length()
+    }
+}
+
+/// Are the simplified vertices a subsequence of the original ones, with the
+/// same end points?
+fn is_subsequence(
+    simplified: &Polyline<f64, UnknownUnit>,
+    original: &Polyline<f64, UnknownUnit>,
+) -> bool {
+    let mut original_vertices = original.vertices().iter();
+    simplified.vertices().first() == original.vertices().first()
+        && simplified.vertices().last() == original.vertices().last()
+        && simplified
+            .vertices()
+            .iter()
+            .all(|v| original_vertices.any(|o| o == v))
+}
+
+quickcheck! {
+    fn rdp_stays_within_tolerance(ys: Vec<i8>, tolerance: u8) -> bool {
+        // Keep vertices distinct, so that it is clear which segment replaced
+        // which vertices.
+        let vertices: Vec<_> = ys.iter().take(100).enumerate().map(|(x, &y)| (x as i8, y)).collect();
+        let line = match polyline(&vertices) {
+            None => return true,
+            Some(line) => line,
+        };
+        let tolerance = f64::from(tolerance % 20);
+        let simplified = line.simplify(tolerance);
+
+        // Every removed vertex is within the tolerance of the segment that
+        // replaced it.
+        let kept = simplified.vertices();
+        let mut segment = 0;
+        is_subsequence(&simplified, &line)
+            && line.vertices().iter().all(|&v| {
+                if segment + 1 < kept.len() && v == kept[segment + 1] {
+                    segment += 1;
+                    return true;
+                }
+                let end = kept[(segment + 1).min(kept.len() - 1)];
+                distance_to_segment(v, kept[segment], end) <= tolerance + 1e-9
+            })
+    }
+
+    fn visvalingam_keeps_a_subsequence(vertices: Vec<(i8, i8)>, min_area: u8) -> bool {
+        match polyline(&vertices) {
+            None => true,
+            Some(line) => {
+                let simplified = line.simplify_visvalingam(f64::from(min_area));
+                is_subsequence(&simplified, &line)
+            }
+        }
+    }
+}
+
+#[test]
+fn collinear_vertices_are_removed() {
+    let line =
+        Polyline::<f64, UnknownUnit>::new((0..100).map(|i| point2(f64::from(i), 0.0)).collect());
+    let expected = Polyline::new(vec![point2(0.0, 0.0), point2(99.0, 0.0)]);
+    assert_eq!(line.simplify(0.0), expected);
+    assert_eq!(line.simplify_visvalingam(1e-9), expected);
+}
+
+#[test]
+fn zero_tolerance_keeps_corners() {
+    let line = Polyline::<f64, UnknownUnit>::new(vec![
+        point2(0.0, 0.0),
+        point2(1.0, 0.0),
+        point2(1.0, 1.0),
+        point2(2.0, 1.0),
+    ]);
+    assert_eq!(line.simplify(0.0), line);
+    assert_eq!(line.simplify_visvalingam(0.0), line);
+}
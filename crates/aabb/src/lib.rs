@@ -19,6 +19,19 @@ pub struct Aabb<T, U = euclid::UnknownUnit> {
     max: Point2D<T, U>,
 }
 
+/// The error returned by `Aabb::try_new` when `min` is not less than or equal
+/// to `max`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidAabb;
+
+impl fmt::Display for InvalidAabb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an AABB's min must be less than or equal to its max")
+    }
+}
+
+impl std::error::Error for InvalidAabb {}
+
 impl<T, U> fmt::Debug for Aabb<T, U>
 where
     T: fmt::Debug,
@@ -38,6 +51,11 @@ where
     /// Construct a new axis-aligned bounding box.
     ///
     /// `min`'s `x` and `y` components must be less than or equal to `max`'s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is not less than or equal to `max`. See
+    /// `Aabb::try_new` for a version that returns an error instead.
     #[inline]
     pub fn new(min: Point2D<T, U>, max: Point2D<T, U>) -> Aabb<T, U> {
         assert!(min.x <= max.x);
@@ -45,6 +63,28 @@ where
         Aabb { min, max }
     }
 
+    /// Construct a new axis-aligned bounding box, or return an error if
+    /// `min`'s `x` and `y` components are not less than or equal to `max`'s.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use euclid::{point2, UnknownUnit};
+    /// use fart_aabb::Aabb;
+    ///
+    /// assert!(Aabb::<i32, UnknownUnit>::try_new(point2(0, 0), point2(1, 1)).is_ok());
+    /// assert!(Aabb::<i32, UnknownUnit>::try_new(point2(1, 0), point2(0, 1)).is_err());
+    /// assert!(Aabb::<f64, UnknownUnit>::try_new(point2(f64::NAN, 0.0), point2(1.0, 1.0)).is_err());
+    /// ```
+    #[inline]
+    pub fn try_new(min: Point2D<T, U>, max: Point2D<T, U>) -> Result<Aabb<T, U>, InvalidAabb> {
+        if min.x <= max.x && min.y <= max.y {
+            Ok(Aabb { min, max })
+        } else {
+            Err(InvalidAabb)
+        }
+    }
+
     /// Construct a new axis-aligned bounding box that contains the given set of
     /// vertices.
     ///
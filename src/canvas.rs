@@ -1,7 +1,33 @@
 //! A canvas for drawing paths on.
 
+mod dedupe;
+mod gcode;
+mod hpgl;
+mod import;
+mod marks;
+mod merge;
+mod pdf;
+mod pen;
+mod raster;
+mod stats;
+mod travel;
+
+pub use gcode::{GcodeOptions, Origin};
+pub use hpgl::HpglOptions;
+pub use import::{read_svg, SvgLayer};
+pub use marks::Marks;
+pub use pdf::{PdfOptions, PdfPages};
+pub use pen::PenInfo;
+pub use raster::Raster;
+pub use stats::{LayerStats, PlotSpeeds, PlotStats};
+
+use crate::hatch::Hatch;
 use crate::path::{Path, ToPaths};
+use crate::simplify::Simplify;
 use crate::units::*;
+use euclid::point2;
+use fart_2d_geom::{is_counter_clockwise, ClipMask, Keep, Polygon, PolygonWithHoles, Polyline};
+use fart_aabb::{Aabb, AabbTree};
 use penlib::Pen;
 use slotmap::SlotMap;
 
@@ -12,52 +38,51 @@ pub struct CanvasSpace;
 /// Transform from NormalSpace to CanvasSpace
 type CanvasProjection = euclid::Transform2D<f64, NormalSpace, CanvasSpace>;
 
+/// The number of a layer in the order layers were created. It never changes,
+/// so it is what layers are numbered by in SVG group IDs and file names.
 #[derive(Clone, Copy, Debug)]
 struct LayerId(u64);
 
-impl From<LayerId> for String {
-    fn from(id: LayerId) -> String {
-        match id {
-            LayerId(0) => String::from("#00f"),
-            LayerId(1) => String::from("#080"),
-            LayerId(2) => String::from("#f00"),
-            LayerId(3) => String::from("#0cc"),
-            LayerId(4) => String::from("#0f0"),
-            LayerId(5) => String::from("#c0c"),
-            LayerId(6) => String::from("#cc0"),
-            _ => String::from("black"),
-        }
-    }
-}
-
 /// A Layer contains a collection of path to be drawn on that specific layer
 #[derive(Debug)]
 struct Layer {
     id: LayerId,
     paths: Vec<Path<f64, CanvasSpace>>,
-    color: palette::rgb::LinSrgb,
-    nib_size: Millis,
+    pen: PenInfo,
+    /// A human-readable name for this layer. See `Canvas::set_layer_name`.
+    name: Option<String>,
+    /// Whether this layer is left out of everything exported. See
+    /// `Canvas::set_layer_hidden`.
+    hidden: bool,
+    /// Whether this layer holds registration marks, which must stay exactly
+    /// where they are. See `Canvas::add_marks`.
+    marks: bool,
+}
+
+impl Layer {
+    /// The layer's label in Inkscape: its number, name, and pen.
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}: {} ({})", self.id.0 + 1, name, self.pen),
+            None => format!("{}: {}", self.id.0 + 1, self.pen),
+        }
+    }
 }
 
 impl From<&Layer> for svg::node::element::Group {
     fn from(item: &Layer) -> svg::node::element::Group {
-        let color = item.color.into_components();
-        svg::node::element::Group::new()
+        let group = svg::node::element::Group::new()
             .set("fill", "none")
             .set("id", format!("layer{}", item.id.0 + 1))
             .set("inkscape:groupmode", "layer")
-            .set("inkscape:label", (item.id.0 + 1).to_string())
-            .set::<_, String>(
-                "stroke",
-                format!(
-                    "rgb({},{},{})",
-                    color.0 * 255.0,
-                    color.1 * 255.0,
-                    color.2 * 255.0
-                ),
-            )
+            .set("inkscape:label", crate::xml::attribute(&item.label()))
+            .set("stroke", item.pen.svg_color())
             .set("stroke-linecap", "round")
-            .set("style", "display:inline")
+            .set("style", "display:inline");
+        match &item.name {
+            Some(name) => group.set("fart:name", crate::xml::attribute(name)),
+            None => group,
+        }
     }
 }
 
@@ -66,6 +91,99 @@ slotmap::new_key_type! {
     pub struct LayerKey;
 }
 
+/// An error using a canvas's layers.
+///
+/// Returned by the `try_` methods of `Canvas`, like `Canvas::try_draw`, whose
+/// non-`try_` counterparts panic instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanvasError {
+    /// The layer is not on this canvas, for example because it was removed.
+    NoSuchLayer(LayerKey),
+    /// A new order of the layers doesn't have every layer of the canvas
+    /// exactly once.
+    InvalidLayerOrder,
+}
+
+impl std::fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CanvasError::NoSuchLayer(key) => write!(f, "no such layer on this canvas: {:?}", key),
+            CanvasError::InvalidLayerOrder => {
+                write!(f, "the new order must have every layer exactly once")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CanvasError {}
+
+/// How much geometry was trimmed away by clipping to the printable area. See
+/// `Canvas::set_clip_to_printable_area`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClipReport {
+    /// The number of paths that were partially outside of the printable area,
+    /// and got trimmed.
+    pub paths_trimmed: usize,
+    /// The number of paths that were completely outside of the printable area,
+    /// and got removed.
+    pub paths_removed: usize,
+    /// The total length of the removed lines, in the canvas's units.
+    pub length_removed: f64,
+}
+
+/// How far the pen travels while it is up, before and after optimizing the
+/// drawing order. See `Canvas::optimize_travel`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TravelReport {
+    /// The pen-up travel distance before optimizing, in the canvas's units.
+    pub before: f64,
+    /// The pen-up travel distance after optimizing, in the canvas's units.
+    pub after: f64,
+}
+
+/// How many vertices paths had before and after simplifying them. See
+/// `Canvas::simplify`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimplifyReport {
+    /// The number of vertices of the flattened paths before simplifying.
+    pub vertices_before: usize,
+    /// The number of vertices of the paths after simplifying.
+    pub vertices_after: usize,
+}
+
+/// Options for rendering a canvas as an SVG. See `Canvas::create_svg_with`.
+#[derive(Clone, Debug, Default)]
+pub struct SvgOptions {
+    deduplicate: bool,
+    plot_stats: bool,
+}
+
+impl SvgOptions {
+    /// Construct the default options.
+    pub fn new() -> SvgOptions {
+        SvgOptions::default()
+    }
+
+    /// Set whether to leave out the parts of straight lines that retrace
+    /// collinear lines drawn before them on the same layer.
+    ///
+    /// Shared edges, like those of grids and tilings, then only get plotted
+    /// once, instead of twice and bleeding through the paper. Paths that need
+    /// trimming are flattened into straight lines first. Off by default.
+    pub fn deduplicate(mut self, deduplicate: bool) -> SvgOptions {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Set whether to embed the canvas's plot statistics, from
+    /// `Canvas::plot_stats`, in a `<metadata>` element. Layer documents get
+    /// the statistics of their own layer. Off by default.
+    pub fn plot_stats(mut self, plot_stats: bool) -> SvgOptions {
+        self.plot_stats = plot_stats;
+        self
+    }
+}
+
 /// A canvas is a collection of rendered paths. To add new paths to the canvas,
 /// use the `draw` method.
 #[derive(Debug)]
@@ -75,7 +193,18 @@ where
 {
     paper: Paper<Unit>,
     layers: SlotMap<LayerKey, Layer>,
+    /// Every layer, from the bottom to the top. A `SlotMap` reuses the slots
+    /// of removed layers, so its own order is not the order layers were
+    /// created in.
+    order: Vec<LayerKey>,
     layer_id_counter: u64,
+    clip_to_printable_area: bool,
+    clip_report: ClipReport,
+    occlusion: bool,
+    /// Every path drawn while occlusion is enabled, by its layer and index
+    /// within that layer.
+    occludable: AabbTree<f64, CanvasSpace, (LayerKey, usize)>,
+    plot_speeds: PlotSpeeds,
 }
 
 impl<Unit> Canvas<Unit>
@@ -87,7 +216,13 @@ where
         Canvas {
             paper,
             layers: SlotMap::with_key(),
+            order: Vec::new(),
             layer_id_counter: 0,
+            clip_to_printable_area: false,
+            clip_report: ClipReport::default(),
+            occlusion: false,
+            occludable: AabbTree::new(),
+            plot_speeds: PlotSpeeds::default(),
         }
     }
 
@@ -119,86 +254,627 @@ where
         )
     }
 
+    /// Get the area of the paper inside of its margins, in canvas space.
+    pub fn printable_area(&self) -> Aabb<f64, CanvasSpace> {
+        let min = point2(self.paper.margin_left.into(), self.paper.margin_top.into());
+        let max = point2(min.x + self.width().into(), min.y + self.height().into());
+        Aabb::new(min, max)
+    }
+
+    /// Set whether paths drawn from now on are clipped to the printable area.
+    ///
+    /// When enabled, everything outside of the paper's margins is trimmed
+    /// away, so that the plotter never draws into the margins or runs off the
+    /// sheet. Paths that need trimming are flattened into straight lines
+    /// first. Off by default.
+    pub fn set_clip_to_printable_area(&mut self, clip: bool) {
+        self.clip_to_printable_area = clip;
+    }
+
+    /// Get a report of how much has been trimmed by clipping to the printable
+    /// area so far.
+    pub fn clip_report(&self) -> ClipReport {
+        self.clip_report
+    }
+
+    /// Set whether closed shapes drawn from now on hide the lines underneath
+    /// them.
+    ///
+    /// When enabled, drawing works like the painter's algorithm: every closed
+    /// sub-path of a newly drawn path hides the parts of paths drawn earlier
+    /// that lie inside of it, on any layer. A plotter can't paint over a line
+    /// once it is drawn, so the hidden parts are removed geometrically
+    /// instead. Only paths drawn while occlusion is enabled get hidden. Paths
+    /// that need trimming are flattened into straight lines first. Off by
+    /// default.
+    pub fn set_occlusion(&mut self, occlusion: bool) {
+        self.occlusion = occlusion;
+    }
+
     /// Register a new Layer using the given pen
     pub fn create_layer<P>(&mut self, pen: P) -> LayerKey
     where
         P: Pen,
     {
+        self.create_layer_with(PenInfo::of(&pen))
+    }
+
+    /// Register a new layer using the pen with the given identity, which is
+    /// saved in SVGs so that the plot operator knows which pen to use.
+    pub fn create_layer_with(&mut self, pen: PenInfo) -> LayerKey {
+        self.insert_layer(pen, Vec::new(), false)
+    }
+
+    /// Insert a new layer on top of all others.
+    fn insert_layer(
+        &mut self,
+        pen: PenInfo,
+        paths: Vec<Path<f64, CanvasSpace>>,
+        marks: bool,
+    ) -> LayerKey {
         let layer = self.layers.insert(Layer {
-            paths: Vec::new(),
+            paths,
             id: LayerId(self.layer_id_counter),
-            color: pen.rgb_color(),
-            nib_size: Millis(P::nib_size_mm()),
+            pen,
+            name: None,
+            hidden: false,
+            marks,
         });
         self.layer_id_counter += 1;
+        self.order.push(layer);
         layer
     }
 
+    /// Get the identity of the pen that plots the given layer.
+    pub fn pen_info(&self, layer: LayerKey) -> Option<&PenInfo> {
+        self.layers.get(layer).map(|l| &l.pen)
+    }
+
     /// Remove a layer from the canvas
-    pub fn remove_layer(&mut self, key: LayerKey) -> Result<(), ()> {
-        self.layers.remove(key).ok_or(()).map(|_| ())
+    pub fn remove_layer(&mut self, key: LayerKey) -> Result<(), CanvasError> {
+        self.layers
+            .remove(key)
+            .ok_or(CanvasError::NoSuchLayer(key))?;
+        self.order.retain(|&k| k != key);
+        Ok(())
+    }
+
+    /// Get every layer's key, from the bottom layer to the top one.
+    ///
+    /// Layers are stacked in the order they are created, unless they are
+    /// reordered. Everything exported draws the layers in this order.
+    pub fn layers(&self) -> &[LayerKey] {
+        &self.order
+    }
+
+    /// Move the given layer one step up, above the layer that is above it.
+    /// The top layer stays where it is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See
+    /// `Canvas::try_raise_layer` for a version that returns an error instead.
+    pub fn raise_layer(&mut self, layer: LayerKey) {
+        self.try_raise_layer(layer)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Move the given layer one step up, or return an error if the layer is
+    /// not on this canvas.
+    pub fn try_raise_layer(&mut self, layer: LayerKey) -> Result<(), CanvasError> {
+        let index = self.layer_index(layer)?;
+        if index + 1 < self.order.len() {
+            self.order.swap(index, index + 1);
+        }
+        Ok(())
+    }
+
+    /// Move the given layer one step down, below the layer that is below it.
+    /// The bottom layer stays where it is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See
+    /// `Canvas::try_lower_layer` for a version that returns an error instead.
+    pub fn lower_layer(&mut self, layer: LayerKey) {
+        self.try_lower_layer(layer)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Move the given layer one step down, or return an error if the layer
+    /// is not on this canvas.
+    pub fn try_lower_layer(&mut self, layer: LayerKey) -> Result<(), CanvasError> {
+        let index = self.layer_index(layer)?;
+        if index > 0 {
+            self.order.swap(index - 1, index);
+        }
+        Ok(())
+    }
+
+    /// Stack the layers in the given order, from the bottom layer to the top
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` doesn't have every layer of this canvas exactly
+    /// once. See `Canvas::try_reorder_layers` for a version that returns an
+    /// error instead.
+    pub fn reorder_layers(&mut self, order: &[LayerKey]) {
+        self.try_reorder_layers(order)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Stack the layers in the given order, or return an error if `order`
+    /// doesn't have every layer of this canvas exactly once.
+    pub fn try_reorder_layers(&mut self, order: &[LayerKey]) -> Result<(), CanvasError> {
+        let mut sorted = order.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        if sorted.len() != order.len()
+            || order.len() != self.layers.len()
+            || !order.iter().all(|&key| self.layers.contains_key(key))
+        {
+            return Err(CanvasError::InvalidLayerOrder);
+        }
+        self.order = order.to_vec();
+        Ok(())
+    }
+
+    fn layer_index(&self, layer: LayerKey) -> Result<usize, CanvasError> {
+        self.order
+            .iter()
+            .position(|&key| key == layer)
+            .ok_or(CanvasError::NoSuchLayer(layer))
+    }
+
+    /// Give the given layer a human-readable name, which labels it in SVGs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See
+    /// `Canvas::try_set_layer_name` for a version that returns an error
+    /// instead.
+    pub fn set_layer_name<S>(&mut self, layer: LayerKey, name: S)
+    where
+        S: Into<String>,
+    {
+        self.try_set_layer_name(layer, name)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Give the given layer a human-readable name, or return an error if the
+    /// layer is not on this canvas.
+    pub fn try_set_layer_name<S>(&mut self, layer: LayerKey, name: S) -> Result<(), CanvasError>
+    where
+        S: Into<String>,
+    {
+        self.get_layer(layer)?.name = Some(name.into());
+        Ok(())
+    }
+
+    /// Get the given layer's name, if it has one.
+    pub fn layer_name(&self, layer: LayerKey) -> Option<&str> {
+        self.layers.get(layer).and_then(|l| l.name.as_deref())
+    }
+
+    /// Set whether the given layer is hidden. Hidden layers keep their paths,
+    /// but are left out of SVGs and every other export, and out of
+    /// `Canvas::plot_stats`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See
+    /// `Canvas::try_set_layer_hidden` for a version that returns an error
+    /// instead.
+    pub fn set_layer_hidden(&mut self, layer: LayerKey, hidden: bool) {
+        self.try_set_layer_hidden(layer, hidden)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
-    /// Get an existing layer with the given ID or create it if it does not exist
-    fn get_layer(&mut self, key: LayerKey) -> &mut Layer {
-        self.layers.get_mut(key).unwrap()
+    /// Set whether the given layer is hidden, or return an error if the
+    /// layer is not on this canvas.
+    pub fn try_set_layer_hidden(
+        &mut self,
+        layer: LayerKey,
+        hidden: bool,
+    ) -> Result<(), CanvasError> {
+        self.get_layer(layer)?.hidden = hidden;
+        Ok(())
     }
 
+    /// Get whether the given layer is hidden.
+    pub fn is_layer_hidden(&self, layer: LayerKey) -> bool {
+        self.layers.get(layer).is_some_and(|l| l.hidden)
+    }
+
+    /// The layers that are exported, from the bottom to the top.
+    fn visible_layers(&self) -> impl Iterator<Item = (LayerKey, &Layer)> {
+        self.order
+            .iter()
+            .map(move |&key| (key, &self.layers[key]))
+            .filter(|(_, layer)| !layer.hidden)
+    }
+
+    /// Get an existing layer with the given key.
+    fn get_layer(&mut self, key: LayerKey) -> Result<&mut Layer, CanvasError> {
+        self.layers
+            .get_mut(key)
+            .ok_or(CanvasError::NoSuchLayer(key))
+    }
+}
+
+// Drawing and exporting measure things like nib sizes and tolerances in
+// millimeters, so they need to convert millimeters into the canvas's units.
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
     /// Add the given paths to the canvas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See `Canvas::try_draw` for
+    /// a version that returns an error instead.
     pub fn draw<PathsT, P>(&mut self, layer: LayerKey, paths: PathsT)
     where
         PathsT: ToPaths<f64, CanvasSpace>,
         P: Pen + std::hash::Hash + Copy,
     {
-        let paths = paths.to_paths();
+        self.try_draw(layer, paths)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Add the given paths to the canvas, or return an error if the layer is
+    /// not on this canvas.
+    pub fn try_draw<PathsT>(&mut self, layer: LayerKey, paths: PathsT) -> Result<(), CanvasError>
+    where
+        PathsT: ToPaths<f64, CanvasSpace>,
+    {
         let margin_transform = self.margin_transform();
-        let layer = self.get_layer(layer);
-        for path in paths {
-            layer.paths.push(path.transform(&margin_transform));
-        }
+        let paths = paths.to_paths().map(|p| p.transform(&margin_transform));
+        self.try_add_paths(layer, paths)
     }
 
     /// Add the given paths to the canvas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See `Canvas::try_draw_n`
+    /// for a version that returns an error instead.
     pub fn draw_n<PathsT>(&mut self, layer: LayerKey, paths: PathsT)
     where
         PathsT: ToPaths<f64, crate::units::NormalSpace>,
     {
-        let paths = paths.to_paths();
-        let projection = self.canvas_transform();
+        self.try_draw_n(layer, paths)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let layer = self.get_layer(layer);
-        for path in paths {
-            layer.paths.push(path.transform(&projection));
-        }
+    /// Add the given paths to the canvas, or return an error if the layer is
+    /// not on this canvas.
+    pub fn try_draw_n<PathsT>(&mut self, layer: LayerKey, paths: PathsT) -> Result<(), CanvasError>
+    where
+        PathsT: ToPaths<f64, crate::units::NormalSpace>,
+    {
+        let projection = self.canvas_transform();
+        let paths = paths.to_paths().map(|p| p.transform(&projection));
+        self.try_add_paths(layer, paths)
     }
 
     /// Given a collection of things that can be drawn, draw all of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See
+    /// `Canvas::try_draw_many` for a version that returns an error instead.
     pub fn draw_many<I, P, PN>(&mut self, layer: LayerKey, paths: I)
+    where
+        I: IntoIterator<Item = P>,
+        P: ToPaths<f64, CanvasSpace>,
+    {
+        self.try_draw_many(layer, paths)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Given a collection of things that can be drawn, draw all of them, or
+    /// return an error if the layer is not on this canvas.
+    pub fn try_draw_many<I, P>(&mut self, layer: LayerKey, paths: I) -> Result<(), CanvasError>
     where
         I: IntoIterator<Item = P>,
         P: ToPaths<f64, CanvasSpace>,
     {
         let margin_transform = self.margin_transform();
-        let layer = self.get_layer(layer);
-        for p in paths {
-            for path in p.to_paths() {
-                layer.paths.push(path.transform(&margin_transform));
-            }
-        }
+        let paths = paths
+            .into_iter()
+            .flat_map(|p| p.to_paths())
+            .map(|p| p.transform(&margin_transform));
+        self.try_add_paths(layer, paths)
     }
+
     /// Given a collection of things that can be drawn, draw all of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See
+    /// `Canvas::try_draw_n_many` for a version that returns an error
+    /// instead.
     pub fn draw_n_many<I, P>(&mut self, layer: LayerKey, paths: I)
+    where
+        I: IntoIterator<Item = P>,
+        P: ToPaths<f64, NormalSpace>,
+    {
+        self.try_draw_n_many(layer, paths)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Given a collection of things that can be drawn, draw all of them, or
+    /// return an error if the layer is not on this canvas.
+    pub fn try_draw_n_many<I, P>(&mut self, layer: LayerKey, paths: I) -> Result<(), CanvasError>
     where
         I: IntoIterator<Item = P>,
         P: ToPaths<f64, NormalSpace>,
     {
         let transform = self.canvas_transform();
-        let layer = self.get_layer(layer);
-        for p in paths {
-            for path in p.to_paths() {
-                layer.paths.push(path.transform(&transform));
+        let paths = paths
+            .into_iter()
+            .flat_map(|p| p.to_paths())
+            .map(|p| p.transform(&transform));
+        self.try_add_paths(layer, paths)
+    }
+
+    /// Fill the given shape with hatching on the given layer.
+    ///
+    /// If the hatch has no spacing of its own, its lines are spaced by the
+    /// nib size of the layer's pen, so that the fill comes out solid on
+    /// paper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See `Canvas::try_hatch`
+    /// for a version that returns an error instead.
+    pub fn hatch<S>(&mut self, layer: LayerKey, shape: S, hatch: &Hatch)
+    where
+        S: Into<fart_2d_geom::PolygonWithHoles<f64, CanvasSpace>>,
+    {
+        self.try_hatch(layer, shape, hatch)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fill the given shape with hatching on the given layer, or return an
+    /// error if the layer is not on this canvas.
+    pub fn try_hatch<S>(
+        &mut self,
+        layer: LayerKey,
+        shape: S,
+        hatch: &Hatch,
+    ) -> Result<(), CanvasError>
+    where
+        S: Into<fart_2d_geom::PolygonWithHoles<f64, CanvasSpace>>,
+    {
+        let nib_size: f64 = Unit::from(self.get_layer(layer)?.pen.nib_size).into();
+        let paths = hatch.or_spacing(nib_size).fill(&shape.into());
+        let margin_transform = self.margin_transform();
+        let paths = paths.into_iter().map(|p| p.transform(&margin_transform));
+        self.try_add_paths(layer, paths)
+    }
+
+    /// Add paths, which are already in canvas space, to the given layer.
+    fn add_paths<I>(&mut self, layer: LayerKey, paths: I)
+    where
+        I: IntoIterator<Item = Path<f64, CanvasSpace>>,
+    {
+        self.try_add_paths(layer, paths)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Add paths, which are already in canvas space, to the given layer, or
+    /// return an error if the layer is not on this canvas.
+    fn try_add_paths<I>(&mut self, layer: LayerKey, paths: I) -> Result<(), CanvasError>
+    where
+        I: IntoIterator<Item = Path<f64, CanvasSpace>>,
+    {
+        // Check the layer before clipping or occluding anything, so that
+        // nothing changes when there is an error.
+        self.get_layer(layer)?;
+
+        if !self.clip_to_printable_area && !self.occlusion {
+            self.layers[layer].paths.extend(paths);
+            return Ok(());
+        }
+        let kept = if self.clip_to_printable_area {
+            self.clip_paths(paths)
+        } else {
+            paths.into_iter().collect()
+        };
+        if !self.occlusion {
+            self.layers[layer].paths.extend(kept);
+            return Ok(());
+        }
+
+        let tolerance = self.flattening_tolerance();
+        for path in kept {
+            let polylines = path.flatten(tolerance);
+            for (occluder, aabb) in occluders(&polylines) {
+                self.occlude(&occluder, aabb);
             }
+            if let Some(aabb) = polylines_aabb(&polylines) {
+                let paths = &mut self.layers[layer].paths;
+                self.occludable.insert(aabb, (layer, paths.len()));
+                paths.push(path);
+            } else {
+                self.layers[layer].paths.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Clip the given paths to the printable area, and record what got
+    /// trimmed away in the clip report.
+    fn clip_paths<I>(&mut self, paths: I) -> Vec<Path<f64, CanvasSpace>>
+    where
+        I: IntoIterator<Item = Path<f64, CanvasSpace>>,
+    {
+        let area = self.printable_area();
+        let tolerance = self.flattening_tolerance();
+        let mut kept = vec![];
+        for path in paths {
+            let polylines = path.flatten(tolerance);
+            let inside = polylines
+                .iter()
+                .all(|p| p.vertices().iter().all(|v| area.contains_point(*v)));
+            if inside {
+                kept.push(path);
+                continue;
+            }
+
+            let mut length_kept = 0.0;
+            let mut length_total = 0.0;
+            let kept_before = kept.len();
+            for polyline in polylines {
+                length_total += polyline_length(&polyline);
+                for piece in polyline.clip(&area, Keep::Inside) {
+                    length_kept += polyline_length(&piece);
+                    kept.extend(piece.to_paths());
+                }
+            }
+
+            if kept.len() == kept_before {
+                self.clip_report.paths_removed += 1;
+            } else {
+                self.clip_report.paths_trimmed += 1;
+            }
+            self.clip_report.length_removed += length_total - length_kept;
         }
+        kept
+    }
+
+    /// Hide the parts of every occludable path that lie inside of the given
+    /// occluder, whose bounding box is `aabb`.
+    fn occlude(
+        &mut self,
+        occluder: &PolygonWithHoles<f64, CanvasSpace>,
+        aabb: Aabb<f64, CanvasSpace>,
+    ) {
+        let tolerance = self.flattening_tolerance();
+        let candidates: Vec<_> = self
+            .occludable
+            .iter_overlapping(aabb)
+            .map(|(_, &entry)| entry)
+            .collect();
+        for (layer, index) in candidates {
+            // The layer may have been removed since.
+            let path = match self.layers.get_mut(layer) {
+                Some(layer) => &mut layer.paths[index],
+                None => continue,
+            };
+
+            let mut hidden = false;
+            let mut visible = vec![];
+            for polyline in path.flatten(tolerance) {
+                let pieces = polyline.clip(occluder, Keep::Outside);
+                hidden |= pieces.len() != 1 || pieces[0] != polyline;
+                visible.extend(pieces);
+            }
+            if hidden {
+                // Completely hidden paths are left empty, so that the indices
+                // of the paths after them stay valid.
+                let commands: Vec<_> = visible
+                    .into_iter()
+                    .flat_map(|piece| piece.to_paths())
+                    .flat_map(|piece| piece.commands)
+                    .collect();
+                *path = Path::with_commands(commands);
+            }
+        }
+    }
+
+    /// The tolerance for flattening curves into straight lines, when they need
+    /// trimming.
+    fn flattening_tolerance(&self) -> f64 {
+        Unit::from(Millis(0.01)).into()
+    }
+
+    /// Reorder the paths within each layer, reversing them where that helps,
+    /// to minimize how far the pen travels while it is up.
+    ///
+    /// Each layer is plotted on its own, starting with the pen at the paper's
+    /// top left corner. The order is found by greedily drawing the nearest
+    /// path next, and then refined with 2-opt moves, for layers of up to a
+    /// thousand paths. If that doesn't beat the current order, a layer is left
+    /// as it was.
+    ///
+    /// Layers of registration marks are left alone. Paths drawn before
+    /// optimizing can no longer be hidden by occlusion.
+    pub fn optimize_travel(&mut self) -> TravelReport {
+        let home = point2(0.0, 0.0);
+        let mut report = TravelReport::default();
+        for layer in self.layers.values_mut().filter(|l| !l.marks) {
+            let paths = std::mem::take(&mut layer.paths);
+            let before = travel::travel_distance(home, &paths);
+            let optimized = travel::optimize(home, paths.clone());
+            let after = travel::travel_distance(home, &optimized);
+
+            report.before += before;
+            if after < before {
+                report.after += after;
+                layer.paths = optimized;
+            } else {
+                report.after += before;
+                layer.paths = paths;
+            }
+        }
+        // The occludable paths' indices are no longer valid.
+        self.occludable = AabbTree::new();
+        report
+    }
+
+    /// Join paths within each layer whose ends are within `tolerance` of each
+    /// other into single continuous paths, reversing them where needed.
+    ///
+    /// Every pen lift leaves a blob on paper and costs plotting time, so this
+    /// is worth doing for geometry made of many small touching pieces. Small
+    /// gaps within the tolerance are bridged with straight lines. Returns the
+    /// number of paths that were joined onto others.
+    ///
+    /// Layers of registration marks are left alone. Paths drawn before
+    /// merging can no longer be hidden by occlusion.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is not positive.
+    pub fn merge_paths(&mut self, tolerance: f64) -> usize {
+        assert!(tolerance > 0.0, "merge tolerance must be positive");
+        let mut joined = 0;
+        for layer in self.layers.values_mut().filter(|l| !l.marks) {
+            let paths = std::mem::take(&mut layer.paths);
+            let before = paths.iter().filter(|p| p.start().is_some()).count();
+            layer.paths = merge::merge(paths, tolerance);
+            joined += before - layer.paths.len();
+        }
+        // The occludable paths' indices are no longer valid.
+        self.occludable = AabbTree::new();
+        joined
+    }
+
+    /// Simplify every path on the canvas by removing vertices.
+    ///
+    /// If the simplification has no tolerance of its own, each layer uses
+    /// half the nib size of its pen, since details smaller than that don't
+    /// show up on paper anyway. All paths are flattened into straight lines,
+    /// except for those on layers of registration marks, which are left
+    /// alone.
+    pub fn simplify(&mut self, simplify: &Simplify) -> SimplifyReport {
+        let mut report = SimplifyReport::default();
+        for layer in self.layers.values_mut().filter(|l| !l.marks) {
+            let nib_size: f64 = Unit::from(layer.pen.nib_size).into();
+            let simplify = simplify.or_tolerance(nib_size / 2.0);
+            for path in &mut layer.paths {
+                let (simplified, before, after) = simplify.path_with_counts(path);
+                *path = simplified;
+                report.vertices_before += before;
+                report.vertices_after += after;
+            }
+        }
+        report
     }
 
     /// Render this canvas as an SVG with the given physical width and height.
@@ -220,13 +896,65 @@ where
     /// # let _ = svg_doc;
     /// ```
     pub fn create_svg(&self) -> svg::Document {
+        self.create_svg_with(&SvgOptions::new())
+    }
+
+    /// Render this canvas as an SVG with the given options.
+    pub fn create_svg_with(&self, options: &SvgOptions) -> svg::Document {
+        let pens = self
+            .visible_layers()
+            .map(|(_, layer)| (format!("layer{}", layer.id.0 + 1), &layer.pen));
+        let mut doc = self.svg_document().add(pen::metadata(pens));
+        if options.plot_stats {
+            doc = doc.add(plot_stats_metadata(&self.plot_stats()));
+        }
+        self.visible_layers().fold(doc, |doc, (_, layer)| {
+            doc.add(self.svg_layer(layer, options))
+        })
+    }
+
+    /// Render every layer of this canvas as its own SVG, for plotting every
+    /// pen in a separate pass.
+    ///
+    /// The documents are in the order the layers are stacked in, and hidden
+    /// layers are left out. They all have
+    /// the same size and `viewBox` as `Canvas::create_svg`'s document, so
+    /// they line up with each other when plotted on the same sheet.
+    pub fn create_layer_svgs(&self) -> Vec<(LayerKey, svg::Document)> {
+        self.create_layer_svgs_with(&SvgOptions::new())
+    }
+
+    /// Render every layer of this canvas as its own SVG with the given
+    /// options. See `Canvas::create_layer_svgs`.
+    pub fn create_layer_svgs_with(&self, options: &SvgOptions) -> Vec<(LayerKey, svg::Document)> {
+        let stats = if options.plot_stats {
+            Some(self.plot_stats())
+        } else {
+            None
+        };
+        self.visible_layers()
+            .enumerate()
+            .map(|(i, (key, layer))| {
+                let pen = (format!("layer{}", layer.id.0 + 1), &layer.pen);
+                let mut doc = self.svg_document().add(pen::metadata(Some(pen)));
+                if let Some(stats) = &stats {
+                    doc = doc.add(plot_stats_metadata(&stats.layers[i]));
+                }
+                (key, doc.add(self.svg_layer(layer, options)))
+            })
+            .collect()
+    }
+
+    /// An SVG document for this canvas's paper, without any layers yet.
+    fn svg_document(&self) -> svg::Document {
         let width = self.paper.width.into();
         let height = self.paper.height.into();
-        let mut doc = svg::Document::new()
+        svg::Document::new()
             .set(
                 "xmlns:inkscape",
                 "http://www.inkscape.org/namespaces/inkscape",
             )
+            .set("xmlns:fart", crate::generation::NAMESPACE)
             .set(
                 "viewBox",
                 format!(
@@ -238,21 +966,125 @@ where
                 ),
             )
             .set("width", format!("{}{}", width, Unit::SUFFIX))
-            .set("height", format!("{}{}", height, Unit::SUFFIX));
-        for layer in self.layers.values() {
-            // TODO: create svg layer
-            let mut layer_node: svg::node::element::Group = layer.into();
-
-            for path in &layer.paths {
-                let path: svg::node::element::Path = path.into();
-                layer_node =
-                    layer_node.add(path.set("stroke-width", format!("{}", layer.nib_size.0)));
+            .set("height", format!("{}{}", height, Unit::SUFFIX))
+    }
+
+    /// Render the given layer as an SVG group.
+    fn svg_layer(&self, layer: &Layer, options: &SvgOptions) -> svg::node::element::Group {
+        // TODO: create svg layer
+        let mut layer_node: svg::node::element::Group = layer.into();
+
+        let deduplicated;
+        let paths = if options.deduplicate {
+            deduplicated = dedupe::deduplicate(&layer.paths, self.flattening_tolerance());
+            &deduplicated
+        } else {
+            &layer.paths
+        };
+        for path in paths.iter().filter(|p| !p.commands.is_empty()) {
+            let path: svg::node::element::Path = path.into();
+            layer_node =
+                layer_node.add(path.set("stroke-width", format!("{}", layer.pen.nib_size.0)));
+        }
+        layer_node
+    }
+}
+
+impl<Unit> crate::Output for Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    fn into_svgs(self) -> (svg::Document, Vec<(u64, svg::Document)>) {
+        let options = SvgOptions::new().plot_stats(true);
+        let layers = self
+            .create_layer_svgs_with(&options)
+            .into_iter()
+            .map(|(key, doc)| (self.layers[key].id.0 + 1, doc))
+            .collect();
+        (self.create_svg_with(&options), layers)
+    }
+
+    fn report(&self) -> Option<String> {
+        Some(self.plot_stats().to_string())
+    }
+}
+
+/// A `<metadata>` element with the given plot statistics, for `fart serve` and
+/// anyone else reading the SVG to show.
+fn plot_stats_metadata<S>(stats: &S) -> svg::node::element::Element
+where
+    S: std::fmt::Display,
+{
+    use svg::Node;
+    let mut metadata = svg::node::element::Element::new("metadata");
+    metadata.assign("id", "fart-plot-stats");
+    metadata.append(svg::node::Text::new(stats.to_string()));
+    metadata
+}
+
+fn polyline_length(polyline: &Polyline<f64, CanvasSpace>) -> f64 {
+    polyline
+        .vertices()
+        .windows(2)
+        .map(|w| (w[1] - w[0]).length())
+        .sum()
+}
+
+/// Get the shapes enclosed by the closed polylines among the given ones, each
+/// with its bounding box. Like the even-odd rule, a ring inside of an odd
+/// number of other rings is a hole, and belongs to the innermost ring around
+/// it. Every other ring is the outside of a shape of its own.
+fn occluders(
+    polylines: &[Polyline<f64, CanvasSpace>],
+) -> Vec<(PolygonWithHoles<f64, CanvasSpace>, Aabb<f64, CanvasSpace>)> {
+    let rings: Vec<_> = polylines
+        .iter()
+        .filter(|p| p.len() >= 4 && p.vertices().first() == p.vertices().last())
+        .map(|p| {
+            let mut vertices = p.vertices()[1..].to_vec();
+            if !is_counter_clockwise(&vertices) {
+                vertices.reverse();
             }
+            let aabb = polylines_aabb(std::slice::from_ref(p)).unwrap();
+            (Polygon::new(vertices), aabb)
+        })
+        .collect();
+
+    // The indices of the rings around each ring.
+    let around: Vec<Vec<usize>> = rings
+        .iter()
+        .enumerate()
+        .map(|(i, (ring, _))| {
+            let point = ring.vertices()[0];
+            (0..rings.len())
+                .filter(|&j| j != i && rings[j].0.contains(point))
+                .collect()
+        })
+        .collect();
 
-            doc = doc.add(layer_node);
+    let mut occluders = vec![];
+    for (i, (ring, aabb)) in rings.iter().enumerate() {
+        let depth = around[i].len();
+        if depth % 2 == 1 {
+            continue;
         }
-        doc
+        let holes = (0..rings.len())
+            .filter(|&j| around[j].len() == depth + 1 && around[j].contains(&i))
+            .map(|j| rings[j].0.clone())
+            .collect();
+        occluders.push((PolygonWithHoles::new(ring.clone(), holes), aabb.clone()));
     }
+    occluders
+}
+
+fn polylines_aabb(polylines: &[Polyline<f64, CanvasSpace>]) -> Option<Aabb<f64, CanvasSpace>> {
+    let mut vertices = polylines
+        .iter()
+        .flat_map(|p| p.vertices())
+        .cloned()
+        .peekable();
+    vertices.peek()?;
+    Some(Aabb::for_vertices(vertices))
 }
 
 impl<Unit> ToPaths<f64, CanvasSpace> for Canvas<Unit>
@@ -262,10 +1094,521 @@ where
     type Paths = std::vec::IntoIter<Path<f64, CanvasSpace>>;
 
     fn to_paths(&self) -> Self::Paths {
-        self.layers
-            .values()
-            .flat_map(|v| v.paths.clone())
+        self.visible_layers()
+            .flat_map(|(_, v)| v.paths.iter().filter(|p| !p.commands.is_empty()).cloned())
             .collect::<Vec<Path<f64, CanvasSpace>>>()
             .into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, Hash)]
+    pub(crate) struct TestPen;
+
+    impl Pen for TestPen {
+        fn rgb_color(&self) -> palette::rgb::LinSrgb {
+            palette::rgb::LinSrgb::new(0.0, 0.0, 0.0)
+        }
+
+        fn nib_size_mm() -> f64 {
+            0.5
+        }
+    }
+
+    fn line(from: (f64, f64), to: (f64, f64)) -> Polyline<f64, NormalSpace> {
+        Polyline::new(vec![point2(from.0, from.1), point2(to.0, to.1)])
+    }
+
+    fn canvas() -> Canvas<Millis> {
+        Canvas::new(Paper::new(Millis(100.0), Millis(100.0)).add_margin(Millis(10.0)))
+    }
+
+    #[test]
+    fn paths_are_not_clipped_by_default() {
+        let mut canvas = canvas();
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, line((-0.25, 0.5), (0.5, 0.5)));
+
+        let paths: Vec<_> = canvas.to_paths().collect();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].length(), 60.0);
+        assert_eq!(canvas.clip_report(), ClipReport::default());
+    }
+
+    #[test]
+    fn paths_are_clipped_to_the_printable_area() {
+        let mut canvas = canvas();
+        canvas.set_clip_to_printable_area(true);
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n_many(
+            layer,
+            vec![
+                // Inside, and left alone.
+                line((0.0, 0.0), (1.0, 1.0)),
+                // Runs into the left margin.
+                line((-0.25, 0.5), (0.5, 0.5)),
+                // Off the sheet entirely.
+                line((2.0, 2.0), (2.0, 3.0)),
+            ],
+        );
+
+        let paths: Vec<_> = canvas.to_paths().collect();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].commands.len(), 2);
+        assert!((paths[1].length() - 40.0).abs() < 1e-9);
+        for path in &paths {
+            for v in &path.flatten(0.1)[0].vertices {
+                assert!(canvas.printable_area().contains_point(*v));
+            }
+        }
+
+        let report = canvas.clip_report();
+        assert_eq!(report.paths_trimmed, 1);
+        assert_eq!(report.paths_removed, 1);
+        assert!((report.length_removed - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dots_are_clipped_to_the_printable_area() {
+        use crate::path::ToPathsExt;
+
+        let mut canvas = canvas();
+        canvas.set_clip_to_printable_area(true);
+        let layer = canvas.create_layer(TestPen);
+        // Two of the dots are in the left margin.
+        canvas.draw_n(layer, line((-0.375, 0.5), (0.625, 0.5)).dotted(0.25));
+
+        assert_eq!(canvas.to_paths().count(), 3);
+        let report = canvas.clip_report();
+        assert_eq!(report.paths_trimmed, 0);
+        assert_eq!(report.paths_removed, 2);
+        assert_eq!(report.length_removed, 0.0);
+    }
+
+    fn square(min: f64, max: f64) -> Polygon<f64, NormalSpace> {
+        Polygon::new(vec![
+            point2(min, min),
+            point2(max, min),
+            point2(max, max),
+            point2(min, max),
+        ])
+    }
+
+    fn total_length(canvas: &Canvas<Millis>) -> f64 {
+        canvas.to_paths().map(|p| p.length()).sum()
+    }
+
+    #[test]
+    fn later_shapes_hide_earlier_lines() {
+        let mut canvas = canvas();
+        canvas.set_occlusion(true);
+        let back = canvas.create_layer(TestPen);
+        let front = canvas.create_layer(TestPen);
+
+        // Hidden completely.
+        canvas.draw_n(back, line((0.4, 0.5), (0.6, 0.5)));
+        // Hidden in the middle.
+        canvas.draw_n(back, line((0.0, 0.5), (1.0, 0.5)));
+        // Not hidden at all.
+        canvas.draw_n(back, line((0.0, 0.0), (1.0, 0.0)));
+        canvas.draw_n(front, square(0.25, 0.75));
+
+        let paths: Vec<_> = canvas.to_paths().collect();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0].flatten(0.1).len(), 2);
+        assert!((paths[0].length() - 40.0).abs() < 1e-9);
+        assert_eq!(paths[1].length(), 80.0);
+        assert_eq!(paths[2].length(), 160.0);
+    }
+
+    #[test]
+    fn earlier_shapes_do_not_hide_later_lines() {
+        let mut canvas = canvas();
+        canvas.set_occlusion(true);
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, square(0.25, 0.75));
+        canvas.draw_n(layer, line((0.0, 0.5), (1.0, 0.5)));
+        assert_eq!(total_length(&canvas), 160.0 + 80.0);
+    }
+
+    #[test]
+    fn overlapping_shapes_hide_each_others_outlines() {
+        let mut canvas = canvas();
+        canvas.set_occlusion(true);
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, square(0.0, 0.5));
+        canvas.draw_n(layer, square(0.25, 0.75));
+        // The back square loses a quarter of its outline.
+        assert!((total_length(&canvas) - (120.0 + 160.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lines_show_through_holes() {
+        let mut canvas = canvas();
+        canvas.set_occlusion(true);
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, line((0.0, 0.5), (1.0, 0.5)));
+        canvas.draw_n(
+            layer,
+            PolygonWithHoles::new(square(0.0, 1.0), vec![square(0.25, 0.75)]),
+        );
+        // The line is only visible inside of the hole, and the shape itself
+        // is drawn in full.
+        assert!((total_length(&canvas) - (40.0 + 320.0 + 160.0)).abs() < 1e-9);
+    }
+
+    /// A single path whose sub-paths are the outlines of the given shapes.
+    fn one_path(shapes: &[Polygon<f64, CanvasSpace>]) -> Path<f64, CanvasSpace> {
+        let mut path = Path::new();
+        for shape in shapes {
+            path.commands
+                .extend(shape.to_paths().flat_map(|p| p.commands));
+        }
+        path
+    }
+
+    fn rectangle(min: (f64, f64), max: (f64, f64)) -> Polygon<f64, CanvasSpace> {
+        Polygon::new(vec![
+            point2(min.0, min.1),
+            point2(max.0, min.1),
+            point2(max.0, max.1),
+            point2(min.0, max.1),
+        ])
+    }
+
+    #[test]
+    fn every_closed_sub_path_hides_lines() {
+        let mut canvas = canvas();
+        canvas.set_occlusion(true);
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, line((0.5, 0.5), (0.875, 0.5)));
+        canvas
+            .try_draw(
+                layer,
+                one_path(&[
+                    rectangle((0.0, 30.0), (20.0, 50.0)),
+                    rectangle((45.0, 30.0), (65.0, 50.0)),
+                ]),
+            )
+            .unwrap();
+        // Only 5mm of the line is left on either side of the second square.
+        let line = canvas.to_paths().next().unwrap();
+        assert!((line.length() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nested_closed_sub_paths_alternate_between_shapes_and_holes() {
+        let mut canvas = canvas();
+        canvas.set_occlusion(true);
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, line((0.0, 0.5), (1.0, 0.5)));
+        canvas
+            .try_draw(
+                layer,
+                one_path(&[
+                    rectangle((0.0, 0.0), (80.0, 80.0)),
+                    rectangle((20.0, 20.0), (60.0, 60.0)),
+                    rectangle((30.0, 30.0), (50.0, 50.0)),
+                ]),
+            )
+            .unwrap();
+        // The line is only visible between the hole's outline and the island
+        // inside of it.
+        let line = canvas.to_paths().next().unwrap();
+        assert!((line.length() - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lines_are_not_hidden_without_occlusion() {
+        let mut canvas = canvas();
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, line((0.0, 0.5), (1.0, 0.5)));
+        canvas.draw_n(layer, square(0.25, 0.75));
+        assert_eq!(total_length(&canvas), 80.0 + 160.0);
+    }
+
+    #[test]
+    fn travel_is_optimized_per_layer() {
+        let mut canvas = canvas();
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n_many(
+            layer,
+            vec![line((1.0, 0.0), (0.5, 0.0)), line((0.0, 0.0), (0.5, 0.0))],
+        );
+
+        let report = canvas.optimize_travel();
+        // From the corner to the margin, then across and back again.
+        assert!((report.before - (90.0f64.hypot(10.0) + 40.0)).abs() < 1e-9);
+        // From the corner to the margin, then straight on.
+        assert!((report.after - 200.0f64.sqrt()).abs() < 1e-9);
+
+        let starts: Vec<_> = canvas.to_paths().map(|p| p.start().unwrap()).collect();
+        assert_eq!(starts, vec![point2(10.0, 10.0), point2(50.0, 10.0)]);
+    }
+
+    #[test]
+    fn touching_paths_are_merged_per_layer() {
+        let mut canvas = canvas();
+        let first = canvas.create_layer(TestPen);
+        let second = canvas.create_layer(TestPen);
+        canvas.draw_n_many(
+            first,
+            vec![line((0.0, 0.0), (0.5, 0.0)), line((1.0, 0.0), (0.5, 0.0))],
+        );
+        // Touches the first layer's lines, but is drawn with another pen.
+        canvas.draw_n(second, line((0.5, 0.0), (0.5, 1.0)));
+
+        assert_eq!(canvas.merge_paths(0.01), 1);
+        let paths: Vec<_> = canvas.to_paths().collect();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].length(), 80.0);
+        assert_eq!(paths[0].flatten(0.1)[0].len(), 3);
+    }
+
+    #[test]
+    fn retraced_lines_are_deduplicated_when_asked() {
+        let mut canvas = canvas();
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, line((0.0, 0.0), (1.0, 0.0)));
+        canvas.draw_n(layer, line((0.5, 0.0), (0.0, 0.0)));
+        let count_paths = |doc: svg::Document| doc.to_string().matches("<path").count();
+
+        assert_eq!(count_paths(canvas.create_svg()), 2);
+        let options = SvgOptions::new().deduplicate(true);
+        assert_eq!(count_paths(canvas.create_svg_with(&options)), 1);
+
+        // Only the rendered SVG is deduplicated, not the canvas itself.
+        assert_eq!(canvas.to_paths().count(), 2);
+    }
+
+    #[test]
+    fn every_layer_gets_its_own_svg() {
+        use crate::Output;
+
+        let mut canvas = canvas();
+        let first = canvas.create_layer(TestPen);
+        let removed = canvas.create_layer(TestPen);
+        let third = canvas.create_layer(TestPen);
+        canvas.remove_layer(removed).unwrap();
+        canvas.draw_n(first, line((0.0, 0.0), (1.0, 0.0)));
+        canvas.draw_n(first, line((0.0, 1.0), (1.0, 1.0)));
+        canvas.draw_n(third, line((0.0, 0.5), (1.0, 0.5)));
+
+        let svgs = canvas.create_layer_svgs();
+        let keys: Vec<_> = svgs.iter().map(|&(key, _)| key).collect();
+        assert_eq!(keys, vec![first, third]);
+        let combined = canvas.create_svg().to_string();
+        let view_box = &combined[combined.find("viewBox").unwrap()..];
+        let view_box = &view_box[..view_box.find(' ').unwrap()];
+        for ((_, doc), &(id, paths)) in svgs.iter().zip(&[("layer1", 2), ("layer3", 1)]) {
+            let doc = doc.to_string();
+            assert!(doc.contains(view_box));
+            assert!(doc.contains(&format!("id=\"{}\"", id)));
+            assert_eq!(doc.matches("<g").count(), 1);
+            assert_eq!(doc.matches("<path").count(), paths);
+        }
+
+        // Saved layers are numbered like their SVG groups, and the saved
+        // documents carry the plot statistics.
+        let with_stats = canvas.create_svg_with(&SvgOptions::new().plot_stats(true));
+        let (doc, layers) = canvas.into_svgs();
+        assert_eq!(doc.to_string(), with_stats.to_string());
+        let numbers: Vec<_> = layers.iter().map(|&(number, _)| number).collect();
+        assert_eq!(numbers, vec![1, 3]);
+    }
+
+    #[test]
+    fn layers_are_exported_in_order() {
+        let mut canvas = canvas();
+        let first = canvas.create_layer(TestPen);
+        let removed = canvas.create_layer(TestPen);
+        let third = canvas.create_layer(TestPen);
+        canvas.remove_layer(removed).unwrap();
+        // This reuses the removed layer's slot, but still goes on top.
+        let fourth = canvas.create_layer(TestPen);
+        for &layer in &[first, third, fourth] {
+            canvas.draw_n(layer, line((0.0, 0.0), (1.0, 0.0)));
+        }
+        let group_ids = |canvas: &Canvas<Millis>| {
+            let svg = canvas.create_svg().to_string();
+            svg.match_indices("id=\"layer")
+                .map(|(i, _)| svg[i + 9..].split('"').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(canvas.layers(), &[first, third, fourth]);
+        assert_eq!(group_ids(&canvas), vec!["1", "3", "4"]);
+
+        canvas.raise_layer(first);
+        canvas.raise_layer(fourth);
+        assert_eq!(canvas.layers(), &[third, first, fourth]);
+        canvas.lower_layer(fourth);
+        canvas.lower_layer(third);
+        assert_eq!(canvas.layers(), &[third, fourth, first]);
+        canvas.reorder_layers(&[fourth, first, third]);
+        assert_eq!(group_ids(&canvas), vec!["4", "1", "3"]);
+
+        // Hidden layers are left out, but keep their place.
+        canvas.set_layer_hidden(first, true);
+        assert!(canvas.is_layer_hidden(first));
+        assert_eq!(group_ids(&canvas), vec!["4", "3"]);
+        assert_eq!(canvas.to_paths().count(), 2);
+        assert_eq!(canvas.create_layer_svgs().len(), 2);
+        assert_eq!(canvas.plot_stats().layers.len(), 2);
+        canvas.set_layer_hidden(first, false);
+        assert_eq!(group_ids(&canvas), vec!["4", "1", "3"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn reordering_must_keep_every_layer() {
+        let mut canvas = canvas();
+        let first = canvas.create_layer(TestPen);
+        canvas.create_layer(TestPen);
+        canvas.reorder_layers(&[first, first]);
+    }
+
+    #[test]
+    fn removed_layers_are_errors() {
+        let mut canvas = canvas();
+        let kept = canvas.create_layer(TestPen);
+        let removed = canvas.create_layer(TestPen);
+        canvas.remove_layer(removed).unwrap();
+
+        let segment = line((0.0, 0.0), (1.0, 1.0));
+        assert_eq!(
+            canvas.try_draw_n(removed, &segment),
+            Err(CanvasError::NoSuchLayer(removed))
+        );
+        assert_eq!(
+            canvas.remove_layer(removed),
+            Err(CanvasError::NoSuchLayer(removed))
+        );
+        assert_eq!(
+            canvas.try_set_layer_name(removed, "gone"),
+            Err(CanvasError::NoSuchLayer(removed))
+        );
+        assert_eq!(
+            canvas.try_reorder_layers(&[kept, removed]),
+            Err(CanvasError::InvalidLayerOrder)
+        );
+        assert_eq!(canvas.layers(), &[kept]);
+
+        // Canvas errors work with `fart::Result`, so sketches can use `?`.
+        let draw = |canvas: &mut Canvas<Millis>| -> crate::Result<()> {
+            canvas.try_draw_n(kept, &segment)?;
+            canvas.try_draw_n(removed, &segment)?;
+            Ok(())
+        };
+        let error = draw(&mut canvas).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<CanvasError>(),
+            Some(&CanvasError::NoSuchLayer(removed))
+        );
+        assert_eq!(canvas.to_paths().count(), 1);
+    }
+
+    #[test]
+    fn layer_names_label_layers() {
+        let mut canvas = canvas();
+        let layer = canvas.create_layer(TestPen);
+        assert_eq!(canvas.layer_name(layer), None);
+        assert!(canvas
+            .create_svg()
+            .to_string()
+            .contains(r#"inkscape:label="1: TestPen 0.5mm""#));
+
+        canvas.set_layer_name(layer, "Outline");
+        assert_eq!(canvas.layer_name(layer), Some("Outline"));
+        assert!(canvas
+            .create_svg()
+            .to_string()
+            .contains(r#"inkscape:label="1: Outline (TestPen 0.5mm)""#));
+    }
+
+    #[test]
+    fn layer_names_with_quotes_and_ampersands_round_trip() {
+        let name = r#"Sky & "Sea" &amp; <Shore>"#;
+        let mut canvas = canvas();
+        let layer = canvas.create_layer(TestPen);
+        canvas.set_layer_name(layer, name);
+        canvas.draw_n(layer, line((0.0, 0.5), (1.0, 0.5)));
+        let svg = canvas.create_svg().to_string();
+
+        let mut imported = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let keys = imported.import_svg(&svg, TestPen).unwrap();
+        assert_eq!(imported.layer_name(keys[0]), Some(name));
+    }
+
+    #[test]
+    fn simplifying_defaults_to_half_the_nib_size() {
+        use crate::simplify::Method;
+
+        // Zig-zags by 0.1mm, which is less than half the nib size of 0.5mm.
+        let zigzag = || {
+            let mut canvas = canvas();
+            let layer = canvas.create_layer(TestPen);
+            canvas.draw_n(
+                layer,
+                Polyline::new(
+                    (0..=100)
+                        .map(|i| {
+                            let y = if i % 2 == 0 { 0.5 } else { 0.50125 };
+                            point2(f64::from(i) / 100.0, y)
+                        })
+                        .collect(),
+                ),
+            );
+            canvas
+        };
+
+        let report = zigzag().simplify(&Simplify::new(Method::RamerDouglasPeucker));
+        assert_eq!(report.vertices_before, 101);
+        assert_eq!(report.vertices_after, 2);
+
+        // The tolerance can be chosen instead.
+        let simplify = Simplify::new(Method::RamerDouglasPeucker).tolerance(0.1);
+        assert_eq!(zigzag().simplify(&simplify).vertices_after, 101);
+    }
+
+    #[test]
+    fn simplifying_keeps_dots() {
+        use crate::path::ToPathsExt;
+        use crate::simplify::Method;
+
+        let mut canvas = canvas();
+        let layer = canvas.create_layer(TestPen);
+        canvas.draw_n(layer, line((0.0, 0.5), (0.5, 0.5)).dotted(0.25));
+        let report = canvas.simplify(&Simplify::new(Method::Visvalingam));
+        assert_eq!(report.vertices_before, 3);
+        assert_eq!(report.vertices_after, 3);
+        assert_eq!(canvas.to_paths().count(), 3);
+    }
+
+    #[test]
+    fn hatching_defaults_to_the_nib_size() {
+        let square = || -> PolygonWithHoles<f64, CanvasSpace> {
+            Polygon::new(vec![
+                point2(0.0, 0.0),
+                point2(10.0, 0.0),
+                point2(10.0, 10.0),
+                point2(0.0, 10.0),
+            ])
+            .into()
+        };
+        let hatch = Hatch::new(euclid::Angle::zero());
+        let hatched = |hatch: &Hatch| {
+            let mut canvas = canvas();
+            let layer = canvas.create_layer(TestPen);
+            canvas.hatch(layer, square(), hatch);
+            canvas.to_paths().count()
+        };
+
+        // The nib size of `TestPen` is 0.5mm.
+        let expected = hatch.spacing(0.5).fill(&square()).len();
+        assert_eq!(hatched(&hatch), expected);
+        assert!(hatched(&hatch.spacing(1.0)) < expected);
+    }
+}
@@ -0,0 +1,238 @@
+//! Removing lines that retrace lines drawn before them.
+
+use super::CanvasSpace;
+use crate::path::{is_dot, Path, ToPaths};
+use euclid::{point2, Point2D};
+use fart_2d_geom::{line, Line, Polyline};
+use fart_aabb::{Aabb, AabbTree, ToAabb};
+
+type Point = Point2D<f64, CanvasSpace>;
+
+/// Remove the parts of straight segments that overlap collinear segments
+/// earlier in `paths`.
+///
+/// Paths are flattened with `tolerance` to find their segments, and overlaps
+/// and leftover pieces shorter than `tolerance` are ignored. Paths without
+/// any overlaps are kept as they are, while the rest are rebuilt from what is
+/// left of their flattened segments.
+pub(crate) fn deduplicate(
+    paths: &[Path<f64, CanvasSpace>],
+    tolerance: f64,
+) -> Vec<Path<f64, CanvasSpace>> {
+    let mut drawn = AabbTree::new();
+    let mut deduplicated = vec![];
+    for path in paths {
+        let polylines = path.flatten(tolerance);
+
+        let mut removed_any = false;
+        let mut pieces = vec![];
+        for polyline in &polylines {
+            // Dots have no segments to overlap, and are kept as they are.
+            if is_dot(polyline) {
+                pieces.push(polyline.clone());
+                continue;
+            }
+            let mut current: Vec<Point> = vec![];
+            for w in polyline.vertices().windows(2) {
+                let segment = line(w[0], w[1]);
+                if segment.a == segment.b {
+                    continue;
+                }
+                let kept = uncovered(&segment, &drawn, tolerance);
+                removed_any |= kept != [(0.0, 1.0)];
+                for (t0, t1) in kept {
+                    let (a, b) = (point_at(&segment, t0), point_at(&segment, t1));
+                    if current.last() != Some(&a) && !current.is_empty() {
+                        pieces.push(Polyline::new(std::mem::take(&mut current)));
+                    }
+                    if current.is_empty() {
+                        current.push(a);
+                    }
+                    current.push(b);
+                }
+                drawn.insert(segment.to_aabb(), segment);
+            }
+            if current.len() >= 2 {
+                pieces.push(Polyline::new(current));
+            }
+        }
+
+        if !removed_any {
+            deduplicated.push(path.clone());
+        } else if !pieces.is_empty() {
+            let commands = pieces
+                .iter()
+                .flat_map(|piece| piece.to_paths())
+                .flat_map(|piece| piece.commands);
+            deduplicated.push(Path::with_commands(commands));
+        }
+    }
+    deduplicated
+}
+
+/// Get the point at parameter `t` along the segment, using the exact end
+/// points at `0` and `1`.
+fn point_at(segment: &Line<f64, CanvasSpace>, t: f64) -> Point {
+    if t == 0.0 {
+        segment.a
+    } else if t == 1.0 {
+        segment.b
+    } else {
+        segment.a.lerp(segment.b, t)
+    }
+}
+
+/// Get the parameter ranges along `segment` that aren't covered by any
+/// segment in `drawn` that is collinear with it, within `tolerance`.
+///
+/// This can't use the `Collinear` case of `Line::intersection`, because that
+/// needs the segments to be exactly collinear. Segments computed in floating
+/// point rarely are, like the pieces of a diagonal line that was split in two
+/// different places, so instead both ends of the other segment only need to
+/// be within `tolerance` of this segment's line.
+fn uncovered(
+    segment: &Line<f64, CanvasSpace>,
+    drawn: &AabbTree<f64, CanvasSpace, Line<f64, CanvasSpace>>,
+    tolerance: f64,
+) -> Vec<(f64, f64)> {
+    let length = segment.length();
+    let direction = segment.b - segment.a;
+    let parameter = |p: Point| (p - segment.a).dot(direction) / (length * length);
+    let distance = |p: Point| (p - segment.a).cross(direction).abs() / length;
+
+    let aabb = segment.to_aabb();
+    let query = Aabb::new(
+        point2(aabb.min().x - tolerance, aabb.min().y - tolerance),
+        point2(aabb.max().x + tolerance, aabb.max().y + tolerance),
+    );
+    let mut covered: Vec<(f64, f64)> = drawn
+        .iter_overlapping(query)
+        .filter(|(_, other)| distance(other.a) <= tolerance && distance(other.b) <= tolerance)
+        .filter_map(|(_, other)| {
+            let (t0, t1) = (parameter(other.a), parameter(other.b));
+            let (t0, t1) = (t0.min(t1).max(0.0), t0.max(t1).min(1.0));
+            if (t1 - t0) * length > tolerance {
+                Some((t0, t1))
+            } else {
+                None
+            }
+        })
+        .collect();
+    covered.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut kept = vec![];
+    let mut t = 0.0;
+    for (t0, t1) in covered {
+        if (t0 - t) * length > tolerance {
+            kept.push((t, t0));
+        }
+        t = t1.max(t);
+    }
+    if (1.0 - t) * length > tolerance {
+        kept.push((t, 1.0));
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::LineCommand;
+
+    fn line(from: (f64, f64), to: (f64, f64)) -> Path<f64, CanvasSpace> {
+        Path::with_commands(vec![
+            LineCommand::MoveTo(point2(from.0, from.1)),
+            LineCommand::LineTo(point2(to.0, to.1)),
+        ])
+    }
+
+    fn square(x: f64, y: f64) -> Path<f64, CanvasSpace> {
+        Path::with_commands(vec![
+            LineCommand::MoveTo(point2(x, y)),
+            LineCommand::LineTo(point2(x + 1.0, y)),
+            LineCommand::LineTo(point2(x + 1.0, y + 1.0)),
+            LineCommand::LineTo(point2(x, y + 1.0)),
+            LineCommand::Close,
+        ])
+    }
+
+    fn total_length(paths: &[Path<f64, CanvasSpace>]) -> f64 {
+        paths.iter().map(|p| p.length()).sum()
+    }
+
+    #[test]
+    fn shared_edges_of_a_grid_are_drawn_once() {
+        let grid: Vec<_> = (0..3)
+            .flat_map(|x| (0..3).map(move |y| square(f64::from(x), f64::from(y))))
+            .collect();
+        assert_eq!(total_length(&grid), 36.0);
+
+        // Four lines of length three in each direction.
+        let deduplicated = deduplicate(&grid, 0.01);
+        assert_eq!(deduplicated.len(), 9);
+        assert!((total_length(&deduplicated) - 24.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn partial_overlaps_leave_the_uncovered_parts() {
+        let deduplicated = deduplicate(
+            &[
+                line((1.0, 0.0), (2.0, 0.0)),
+                // Covered in the middle, in the opposite direction.
+                line((3.0, 0.0), (0.0, 0.0)),
+                // Completely covered.
+                line((0.5, 0.0), (2.5, 0.0)),
+                // Parallel, but not collinear.
+                line((0.0, 1.0), (3.0, 1.0)),
+            ],
+            0.01,
+        );
+
+        assert_eq!(deduplicated.len(), 3);
+        let pieces = deduplicated[1].flatten(0.1);
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].vertices(), &[point2(3.0, 0.0), point2(2.0, 0.0)]);
+        assert_eq!(pieces[1].vertices(), &[point2(1.0, 0.0), point2(0.0, 0.0)]);
+        assert_eq!(deduplicated[2].length(), 3.0);
+    }
+
+    #[test]
+    fn dots_are_kept_when_the_rest_of_their_path_is_covered() {
+        let mut dotted = line((0.0, 0.0), (1.0, 0.0));
+        dotted.commands.extend(vec![
+            LineCommand::MoveTo(point2(5.0, 5.0)),
+            LineCommand::LineTo(point2(5.0, 5.0)),
+        ]);
+        let deduplicated = deduplicate(&[line((0.0, 0.0), (1.0, 0.0)), dotted], 0.01);
+
+        assert_eq!(deduplicated.len(), 2);
+        let pieces = deduplicated[1].flatten(0.1);
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].vertices(), &[point2(5.0, 5.0), point2(5.0, 5.0)]);
+    }
+
+    #[test]
+    fn touching_collinear_lines_are_kept() {
+        let paths = [line((0.0, 0.0), (1.0, 0.0)), line((1.0, 0.0), (2.0, 0.0))];
+        let deduplicated = deduplicate(&paths, 0.01);
+        assert_eq!(deduplicated.len(), 2);
+        assert_eq!(total_length(&deduplicated), 2.0);
+    }
+
+    #[test]
+    fn nearly_collinear_diagonals_are_deduplicated() {
+        // The same diagonal, once whole and once cut from a rotated shape, so
+        // that rounding puts the two a hair apart.
+        let angle = 30f64.to_radians();
+        let (sin, cos) = angle.sin_cos();
+        let rotate = |x: f64| (x * cos, x * sin);
+        let whole = line(rotate(0.0), rotate(10.0));
+        let transform = euclid::Transform2D::rotation(euclid::Angle::radians(angle))
+            .then_translate(euclid::vec2(0.1 * cos, 0.1 * sin));
+        let cut = line((0.0, 0.0), (9.0, 0.0)).transform(&transform);
+
+        let deduplicated = deduplicate(&[whole, cut], 0.01);
+        assert_eq!(deduplicated.len(), 1);
+        assert!((deduplicated[0].length() - 10.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,320 @@
+//! Exporting canvases as G-code, for plotters and CNC machines holding a pen.
+
+use super::{Canvas, CanvasSpace};
+use crate::path::is_dot;
+use crate::units::{Millis, SvgUnit};
+use euclid::Point2D;
+use std::fmt::Write;
+
+/// Where the machine's origin is on the paper. See `GcodeOptions::origin`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// The origin is the paper's bottom left corner, and the y axis points
+    /// up. This is the usual setup for CNC machines.
+    BottomLeft,
+
+    /// The origin is the paper's top left corner, and the y axis points down,
+    /// like in SVG. This is the usual setup for AxiDraw style plotters.
+    TopLeft,
+}
+
+/// Options for rendering a canvas as G-code. See `Canvas::create_gcode`.
+///
+/// # Example
+///
+/// Drive the pen with a servo instead of the Z axis:
+///
+/// ```
+/// use fart::canvas::{GcodeOptions, Origin};
+///
+/// let options = GcodeOptions::new()
+///     .pen_up("M3 S30")
+///     .pen_down("M3 S90")
+///     .draw_feed_rate(2000.0)
+///     .origin(Origin::TopLeft);
+/// # let _ = options;
+/// ```
+#[derive(Clone, Debug)]
+pub struct GcodeOptions {
+    pen_up: String,
+    pen_down: String,
+    draw_feed_rate: f64,
+    travel_feed_rate: f64,
+    origin: Origin,
+    pause_between_layers: bool,
+}
+
+impl Default for GcodeOptions {
+    fn default() -> GcodeOptions {
+        GcodeOptions {
+            pen_up: String::from("G0 Z5"),
+            pen_down: String::from("G0 Z0"),
+            draw_feed_rate: 1000.0,
+            travel_feed_rate: 3000.0,
+            origin: Origin::BottomLeft,
+            pause_between_layers: true,
+        }
+    }
+}
+
+impl GcodeOptions {
+    /// Construct the default options.
+    ///
+    /// By default, the pen is lifted by moving the Z axis to 5mm and lowered
+    /// by moving it to 0mm, lines are drawn at 1000mm/min, the pen travels at
+    /// 3000mm/min, the origin is the paper's bottom left corner, and the
+    /// machine pauses between layers.
+    pub fn new() -> GcodeOptions {
+        GcodeOptions::default()
+    }
+
+    /// Set the command that lifts the pen off the paper, for example a Z move
+    /// like `G0 Z5` or a servo command like `M3 S30`.
+    pub fn pen_up(mut self, command: &str) -> GcodeOptions {
+        self.pen_up = command.to_string();
+        self
+    }
+
+    /// Set the command that lowers the pen onto the paper, for example a Z
+    /// move like `G0 Z0` or a servo command like `M3 S90`.
+    pub fn pen_down(mut self, command: &str) -> GcodeOptions {
+        self.pen_down = command.to_string();
+        self
+    }
+
+    /// Set the speed at which lines are drawn, in millimeters per minute.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `feed_rate` is not positive.
+    pub fn draw_feed_rate(mut self, feed_rate: f64) -> GcodeOptions {
+        assert!(feed_rate > 0.0, "feed rate must be positive");
+        self.draw_feed_rate = feed_rate;
+        self
+    }
+
+    /// Set the speed at which the pen moves while it is up, in millimeters per
+    /// minute.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `feed_rate` is not positive.
+    pub fn travel_feed_rate(mut self, feed_rate: f64) -> GcodeOptions {
+        assert!(feed_rate > 0.0, "feed rate must be positive");
+        self.travel_feed_rate = feed_rate;
+        self
+    }
+
+    /// Set where the machine's origin is on the paper.
+    pub fn origin(mut self, origin: Origin) -> GcodeOptions {
+        self.origin = origin;
+        self
+    }
+
+    /// Set whether the machine pauses (with `M0`) before every layer after
+    /// the first, so that the pen can be changed.
+    pub fn pause_between_layers(mut self, pause: bool) -> GcodeOptions {
+        self.pause_between_layers = pause;
+        self
+    }
+}
+
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    /// Render this canvas as G-code with the given options.
+    ///
+    /// Coordinates are absolute and in millimeters. Layers are drawn one
+    /// after the other, curves are flattened into straight lines, and the pen
+    /// returns to the origin at the end. Dots are drawn by putting the pen
+    /// down and lifting it again.
+    pub fn create_gcode(&self, options: &GcodeOptions) -> String {
+        let units_per_mm: f64 = Unit::from(Millis(1.0)).into();
+        let height: f64 = self.paper.height.into();
+        let origin = options.origin;
+        let to_machine = |p: Point2D<f64, CanvasSpace>| match origin {
+            Origin::BottomLeft => (p.x / units_per_mm, (height - p.y) / units_per_mm),
+            Origin::TopLeft => (p.x / units_per_mm, p.y / units_per_mm),
+        };
+
+        let tolerance = self.flattening_tolerance();
+        let mut gcode = String::new();
+        writeln!(gcode, "G21 ; millimeters").unwrap();
+        writeln!(gcode, "G90 ; absolute coordinates").unwrap();
+        writeln!(gcode, "{}", options.pen_up).unwrap();
+
+        let mut first_layer = true;
+        for (_, layer) in self.visible_layers() {
+            let polylines: Vec<_> = layer
+                .paths
+                .iter()
+                .flat_map(|path| path.flatten(tolerance))
+                .collect();
+            if polylines.is_empty() {
+                continue;
+            }
+
+            writeln!(gcode, "; layer {}", layer.id.0 + 1).unwrap();
+            if options.pause_between_layers && !first_layer {
+                writeln!(gcode, "M0 ; change to the pen for layer {}", layer.id.0 + 1).unwrap();
+            }
+            first_layer = false;
+
+            for polyline in polylines {
+                let dot = is_dot(&polyline);
+                let mut points = polyline.vertices().iter().map(|&v| to_machine(v));
+                let (x, y) = points.next().unwrap();
+                writeln!(
+                    gcode,
+                    "G1 X{:.3} Y{:.3} F{}",
+                    x, y, options.travel_feed_rate
+                )
+                .unwrap();
+                writeln!(gcode, "{}", options.pen_down).unwrap();
+                if dot {
+                    // Putting the pen down and lifting it again draws a dot.
+                    writeln!(gcode, "{}", options.pen_up).unwrap();
+                    continue;
+                }
+
+                // The feed rate is modal, so it only needs setting on the
+                // first move of every stroke.
+                let mut feed_rate = Some(options.draw_feed_rate);
+                for (x, y) in points {
+                    write!(gcode, "G1 X{:.3} Y{:.3}", x, y).unwrap();
+                    if let Some(feed_rate) = feed_rate.take() {
+                        write!(gcode, " F{}", feed_rate).unwrap();
+                    }
+                    gcode.push('\n');
+                }
+                writeln!(gcode, "{}", options.pen_up).unwrap();
+            }
+        }
+
+        writeln!(gcode, "G1 X0 Y0 F{}", options.travel_feed_rate).unwrap();
+        writeln!(gcode, "M2").unwrap();
+        gcode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::tests::TestPen;
+    use crate::path::{LineCommand, Path, ToPaths, ToPathsExt};
+    use crate::units::Paper;
+    use euclid::point2;
+
+    fn canvas() -> Canvas<Millis> {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(50.0)));
+        let first = canvas.create_layer(TestPen);
+        let second = canvas.create_layer(TestPen);
+        for &(layer, y) in &[(first, 10.0), (second, 20.0)] {
+            canvas.add_paths(
+                layer,
+                Some(Path::with_commands(vec![
+                    LineCommand::MoveTo(point2(10.0, y)),
+                    LineCommand::LineTo(point2(20.0, y)),
+                    LineCommand::LineTo(point2(20.0, y + 5.0)),
+                ])),
+            );
+        }
+        canvas
+    }
+
+    #[test]
+    fn default_snapshot() {
+        let gcode = canvas().create_gcode(&GcodeOptions::new());
+        assert_eq!(
+            gcode,
+            "\
+G21 ; millimeters
+G90 ; absolute coordinates
+G0 Z5
+; layer 1
+G1 X10.000 Y40.000 F3000
+G0 Z0
+G1 X20.000 Y40.000 F1000
+G1 X20.000 Y35.000
+G0 Z5
+; layer 2
+M0 ; change to the pen for layer 2
+G1 X10.000 Y30.000 F3000
+G0 Z0
+G1 X20.000 Y30.000 F1000
+G1 X20.000 Y25.000
+G0 Z5
+G1 X0 Y0 F3000
+M2
+"
+        );
+    }
+
+    #[test]
+    fn servo_snapshot() {
+        let options = GcodeOptions::new()
+            .pen_up("M3 S30")
+            .pen_down("M3 S90")
+            .draw_feed_rate(500.0)
+            .travel_feed_rate(6000.0)
+            .origin(Origin::TopLeft)
+            .pause_between_layers(false);
+        let gcode = canvas().create_gcode(&options);
+        assert_eq!(
+            gcode,
+            "\
+G21 ; millimeters
+G90 ; absolute coordinates
+M3 S30
+; layer 1
+G1 X10.000 Y10.000 F6000
+M3 S90
+G1 X20.000 Y10.000 F500
+G1 X20.000 Y15.000
+M3 S30
+; layer 2
+G1 X10.000 Y20.000 F6000
+M3 S90
+G1 X20.000 Y20.000 F500
+G1 X20.000 Y25.000
+M3 S30
+G1 X0 Y0 F6000
+M2
+"
+        );
+    }
+
+    #[test]
+    fn dots_snapshot() {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(50.0)));
+        let layer = canvas.create_layer(TestPen);
+        let line = Path::with_commands(vec![
+            LineCommand::MoveTo(point2(10.0, 10.0)),
+            LineCommand::LineTo(point2(12.0, 10.0)),
+        ]);
+        canvas.add_paths(layer, line.dotted(1.0).to_paths());
+
+        let gcode = canvas.create_gcode(&GcodeOptions::new());
+        assert_eq!(
+            gcode,
+            "\
+G21 ; millimeters
+G90 ; absolute coordinates
+G0 Z5
+; layer 1
+G1 X10.000 Y40.000 F3000
+G0 Z0
+G0 Z5
+G1 X11.000 Y40.000 F3000
+G0 Z0
+G0 Z5
+G1 X12.000 Y40.000 F3000
+G0 Z0
+G0 Z5
+G1 X0 Y0 F3000
+M2
+"
+        );
+    }
+}
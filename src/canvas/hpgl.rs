@@ -0,0 +1,318 @@
+//! Exporting canvases as HPGL, for older pen plotters that don't speak SVG.
+
+use super::{Canvas, CanvasError, CanvasSpace, LayerKey};
+use crate::units::{Millis, SvgUnit};
+use euclid::Point2D;
+use std::fmt::Write;
+
+/// The number of HPGL plotter units in a millimeter.
+const PLOTTER_UNITS_PER_MM: f64 = 40.0;
+
+/// Options for rendering a canvas as HPGL. See `Canvas::create_hpgl`.
+///
+/// # Example
+///
+/// A plotter with a six pen carousel, where the first layer's pen sits in
+/// the last slot:
+///
+/// ```
+/// use fart::canvas::{Canvas, HpglOptions, PenInfo};
+/// use fart::units::{Millis, Paper};
+/// use palette::rgb::LinSrgb;
+///
+/// let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+/// let outline = canvas.create_layer_with(PenInfo::new(LinSrgb::new(0.0, 0.0, 0.0), Millis(0.3)));
+///
+/// let options = HpglOptions::new().carousel(6).pen(outline, 6);
+/// let hpgl = canvas.create_hpgl(&options).unwrap();
+/// # let _ = hpgl;
+/// ```
+#[derive(Clone, Debug)]
+pub struct HpglOptions {
+    carousel: u32,
+    pens: Vec<(LayerKey, u32)>,
+}
+
+impl Default for HpglOptions {
+    fn default() -> HpglOptions {
+        HpglOptions {
+            carousel: 8,
+            pens: vec![],
+        }
+    }
+}
+
+impl HpglOptions {
+    /// Construct the default options.
+    ///
+    /// By default, the plotter's carousel holds eight pens, and layers are
+    /// given pens in the order they are stacked.
+    pub fn new() -> HpglOptions {
+        HpglOptions::default()
+    }
+
+    /// Set how many pens the plotter's carousel holds. Pens are numbered
+    /// from 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pens` is zero.
+    pub fn carousel(mut self, pens: u32) -> HpglOptions {
+        assert!(pens > 0, "the carousel must hold at least one pen");
+        self.carousel = pens;
+        self
+    }
+
+    /// Plot the given layer with the pen in the given slot of the carousel.
+    /// Several layers can share a pen.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pen` is zero, which is the number for putting the pen
+    /// away.
+    pub fn pen(mut self, layer: LayerKey, pen: u32) -> HpglOptions {
+        assert!(pen > 0, "pens are numbered from 1");
+        self.pens.retain(|&(l, _)| l != layer);
+        self.pens.push((layer, pen));
+        self
+    }
+}
+
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    /// Render this canvas as HPGL instructions for a pen plotter.
+    ///
+    /// Every layer that draws something is drawn with its own pen from the
+    /// plotter's carousel: either the pen given to it with
+    /// `HpglOptions::pen`, or else the lowest numbered pen that isn't given to
+    /// any other layer, in the order the layers are stacked. Load the
+    /// carousel accordingly. Curves are flattened into straight lines, and
+    /// the paper's origin is put in the plotter's bottom left corner, with the
+    /// y axis pointing up as HPGL expects.
+    ///
+    /// Returns an error if the carousel doesn't have enough pens for every
+    /// layer, or if a pen is given to a layer that isn't on this canvas.
+    ///
+    /// The output only depends on what was drawn, so it can be compared
+    /// against a snapshot.
+    pub fn create_hpgl(&self, options: &HpglOptions) -> crate::Result<String> {
+        for &(layer, _) in &options.pens {
+            if !self.layers.contains_key(layer) {
+                return Err(CanvasError::NoSuchLayer(layer).into());
+            }
+        }
+
+        let units_per_mm: f64 = Unit::from(Millis(1.0)).into();
+        let scale = PLOTTER_UNITS_PER_MM / units_per_mm;
+        let height: f64 = self.paper.height.into();
+        let to_plotter = |p: Point2D<f64, CanvasSpace>| {
+            (
+                (p.x * scale).round() as i64,
+                ((height - p.y) * scale).round() as i64,
+            )
+        };
+
+        let tolerance = self.flattening_tolerance();
+        let mut hpgl = String::from("IN;\n");
+        let mut next_pen = 1;
+        for (key, layer) in self.visible_layers() {
+            let polylines: Vec<_> = layer
+                .paths
+                .iter()
+                .flat_map(|path| path.flatten(tolerance))
+                .collect();
+            if polylines.is_empty() {
+                continue;
+            }
+
+            let pen = match options.pens.iter().find(|&&(layer, _)| layer == key) {
+                Some(&(_, pen)) => pen,
+                None => {
+                    // The lowest numbered pen that isn't given to any layer.
+                    while options.pens.iter().any(|&(_, pen)| pen == next_pen) {
+                        next_pen += 1;
+                    }
+                    let pen = next_pen;
+                    next_pen += 1;
+                    pen
+                }
+            };
+            if pen > options.carousel {
+                failure::bail!(
+                    "layer {} needs pen {}, but the carousel only holds {} pens",
+                    layer.id.0 + 1,
+                    pen,
+                    options.carousel
+                );
+            }
+            writeln!(hpgl, "SP{};", pen).unwrap();
+            for polyline in polylines {
+                let mut points = polyline.vertices().iter().map(|&v| to_plotter(v));
+                let (x, y) = points.next().unwrap();
+                writeln!(hpgl, "PU{},{};", x, y).unwrap();
+
+                let mut last = (x, y);
+                let mut pen_down = vec![];
+                for p in points {
+                    // Rounding to whole plotter units can make neighbouring
+                    // points the same.
+                    if p != last {
+                        pen_down.push(format!("{},{}", p.0, p.1));
+                        last = p;
+                    }
+                }
+                // Dots, and lines shorter than a plotter unit, are plotted by
+                // putting the pen down where it already is.
+                if pen_down.is_empty() {
+                    pen_down.push(format!("{},{}", x, y));
+                }
+                writeln!(hpgl, "PD{};", pen_down.join(",")).unwrap();
+            }
+        }
+        hpgl.push_str("PU;\nSP0;\n");
+        Ok(hpgl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::tests::TestPen;
+    use crate::path::{LineCommand, Path, ToPaths, ToPathsExt};
+    use crate::units::{Inches, Paper};
+    use euclid::{point2, Angle};
+
+    /// A canvas with the given number of layers, each with a line on it.
+    fn canvas_with_lines(layers: usize) -> (Canvas<Millis>, Vec<LayerKey>) {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let keys: Vec<_> = (0..layers).map(|_| canvas.create_layer(TestPen)).collect();
+        for &key in &keys {
+            canvas.add_paths(
+                key,
+                Some(Path::with_commands(vec![
+                    LineCommand::MoveTo(point2(10.0, 10.0)),
+                    LineCommand::LineTo(point2(20.0, 10.0)),
+                ])),
+            );
+        }
+        (canvas, keys)
+    }
+
+    fn selected_pens(hpgl: &str) -> Vec<&str> {
+        hpgl.lines().filter(|line| line.starts_with("SP")).collect()
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut canvas = Canvas::new(Paper::new(Inches(2.0), Inches(1.0)));
+        let lines = canvas.create_layer(TestPen);
+        let arcs = canvas.create_layer(TestPen);
+        // Never drawn on, so its pen is never selected.
+        canvas.create_layer(TestPen);
+
+        canvas.add_paths(
+            lines,
+            Some(Path::with_commands(vec![
+                LineCommand::MoveTo(point2(0.0, 0.0)),
+                LineCommand::LineTo(point2(1.0, 0.0)),
+                LineCommand::LineTo(point2(1.0, 0.5)),
+                LineCommand::Close,
+            ])),
+        );
+        canvas.add_paths(
+            arcs,
+            Some(Path::with_commands(vec![
+                LineCommand::MoveTo(point2(1.0, 1.0)),
+                LineCommand::ArcTo {
+                    x_radius: 0.5,
+                    y_radius: 0.5,
+                    x_axis_rotation: Angle::zero(),
+                    large_arc_flag: false,
+                    sweep_flag: true,
+                    end: point2(2.0, 1.0),
+                },
+            ])),
+        );
+
+        let hpgl = canvas.create_hpgl(&HpglOptions::new()).unwrap();
+        assert_eq!(hpgl, canvas.create_hpgl(&HpglOptions::new()).unwrap());
+        let lines: Vec<_> = hpgl.lines().collect();
+        assert_eq!(
+            &lines[..4],
+            &["IN;", "SP1;", "PU0,1016;", "PD1016,1016,1016,508,0,1016;"]
+        );
+        assert_eq!(&lines[4..6], &["SP2;", "PU1016,0;"]);
+        assert!(lines[6].starts_with("PD") && lines[6].ends_with(",2032,0;"));
+        assert_eq!(&lines[7..], &["PU;", "SP0;"]);
+    }
+
+    #[test]
+    fn dots_snapshot() {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let layer = canvas.create_layer(TestPen);
+        let line = Path::with_commands(vec![
+            LineCommand::MoveTo(point2(0.0, 10.0)),
+            LineCommand::LineTo(point2(2.0, 10.0)),
+        ]);
+        canvas.add_paths(layer, line.dotted(1.0).to_paths());
+
+        let hpgl = canvas.create_hpgl(&HpglOptions::new()).unwrap();
+        assert_eq!(
+            hpgl.lines().collect::<Vec<_>>(),
+            &[
+                "IN;",
+                "SP1;",
+                "PU0,3600;",
+                "PD0,3600;",
+                "PU40,3600;",
+                "PD40,3600;",
+                "PU80,3600;",
+                "PD80,3600;",
+                "PU;",
+                "SP0;",
+            ]
+        );
+    }
+
+    #[test]
+    fn pens_follow_the_visible_layers() {
+        let (mut canvas, keys) = canvas_with_lines(4);
+        canvas.remove_layer(keys[0]).unwrap();
+        canvas.set_layer_hidden(keys[2], true);
+        canvas.lower_layer(keys[3]);
+
+        let hpgl = canvas.create_hpgl(&HpglOptions::new()).unwrap();
+        assert_eq!(selected_pens(&hpgl), &["SP1;", "SP2;", "SP0;"]);
+    }
+
+    #[test]
+    fn pens_can_be_given_to_layers() {
+        let (canvas, keys) = canvas_with_lines(3);
+        let options = HpglOptions::new()
+            .carousel(4)
+            .pen(keys[0], 4)
+            .pen(keys[2], 1);
+        let hpgl = canvas.create_hpgl(&options).unwrap();
+        assert_eq!(selected_pens(&hpgl), &["SP4;", "SP2;", "SP1;", "SP0;"]);
+    }
+
+    #[test]
+    fn pens_must_be_in_the_carousel() {
+        let (canvas, keys) = canvas_with_lines(3);
+        assert!(canvas.create_hpgl(&HpglOptions::new().carousel(3)).is_ok());
+        assert!(canvas.create_hpgl(&HpglOptions::new().carousel(2)).is_err());
+        let options = HpglOptions::new().pen(keys[0], 9);
+        assert!(canvas.create_hpgl(&options).is_err());
+
+        let (mut canvas, keys) = canvas_with_lines(2);
+        canvas.remove_layer(keys[1]).unwrap();
+        let options = HpglOptions::new().pen(keys[1], 2);
+        let error = canvas.create_hpgl(&options).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<CanvasError>(),
+            Some(&CanvasError::NoSuchLayer(keys[1]))
+        );
+    }
+}
@@ -0,0 +1,749 @@
+//! Importing existing SVG artwork, so that it can be mixed with generated
+//! art.
+
+use super::{pen, Canvas, CanvasError, CanvasSpace, LayerKey, PenInfo};
+use crate::path::{LineCommand, Path};
+use crate::units::{Inches, Millis, SvgUnit};
+use crate::xml;
+use euclid::{point2, Angle, Transform2D};
+use failure::ResultExt;
+use penlib::Pen;
+use svg::node::element::tag::Type;
+use svg::node::Attributes;
+use svg::parser::{Event, Parser};
+
+/// The coordinate system of an SVG element, before its transforms.
+#[derive(Debug)]
+enum UserSpace {}
+
+/// Elements whose contents are not drawn where they are.
+const UNDRAWN: &[&str] = &[
+    "clipPath", "defs", "marker", "mask", "metadata", "pattern", "symbol",
+];
+
+/// The paths of one layer of an imported SVG. See `read_svg`.
+#[derive(Clone, Debug)]
+pub struct SvgLayer {
+    /// The layer's name, if it is an Inkscape layer. For SVGs made by
+    /// `Canvas`, this is the name given with `Canvas::set_layer_name`.
+    pub name: Option<String>,
+
+    /// The pen that plots the layer, if the SVG was made by `Canvas` and
+    /// says so in its metadata.
+    pub pen: Option<PenInfo>,
+
+    /// The layer's paths, in canvas space.
+    pub paths: Vec<Path<f64, CanvasSpace>>,
+}
+
+/// An open container element while walking the document.
+#[derive(Clone, Copy)]
+struct Group {
+    transform: Transform2D<f64, UserSpace, CanvasSpace>,
+    /// The index of the layer this group's contents go into.
+    layer: usize,
+    hidden: bool,
+}
+
+/// Read the paths drawn by an SVG document.
+///
+/// Paths and the basic shapes (rectangles, circles, ellipses, lines,
+/// polylines, and polygons) are read, with all of their group transforms
+/// applied. The document's physical size is honored: coordinates are
+/// converted into `Unit`s, with the top left corner of the document at the
+/// canvas's origin. Things that aren't drawn, like definitions and elements
+/// hidden with `display:none`, are skipped. Styles, text, images, and `<use>`
+/// elements are ignored.
+///
+/// Every Inkscape layer becomes its own `SvgLayer`, in document order. Paths
+/// that aren't in any Inkscape layer are collected in a first, unnamed layer,
+/// which is left out if it has no paths. The pens that SVGs made by `Canvas`
+/// record for their layers are read back too.
+pub fn read_svg<Unit>(svg: &str) -> crate::Result<Vec<SvgLayer>>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    let mut layers = vec![SvgLayer {
+        name: None,
+        pen: None,
+        paths: vec![],
+    }];
+    // The ID of every layer's group, for matching them with their pens, and
+    // the name that `Canvas` gave it.
+    let mut layer_ids = vec![(None, None)];
+    let mut pens = vec![];
+    let mut groups: Vec<Group> = vec![];
+
+    for event in Parser::new(svg) {
+        let (name, kind, attributes) = match event {
+            Event::Tag(name, kind, attributes) => (name, kind, attributes),
+            Event::Error(e) => return Err(failure::format_err!("invalid SVG: {}", e)),
+            _ => continue,
+        };
+        if kind == Type::End {
+            if is_container(name) {
+                groups.pop();
+            }
+            continue;
+        }
+        if name == "fart:pen" {
+            pens.push(pen::parse(&attributes).context("invalid <fart:pen> element")?);
+            continue;
+        }
+
+        let parent = match groups.last() {
+            Some(&group) => group,
+            // Nothing outside of the root element is drawn.
+            None if name == "svg" => root_group::<Unit>(&attributes)?,
+            None => continue,
+        };
+        let transform = match attributes.get("transform") {
+            Some(transform) => parse_transform(transform)?.then(&parent.transform),
+            None => parent.transform,
+        };
+        let hidden = parent.hidden
+            || UNDRAWN.contains(&name)
+            || attributes.get("display").is_some_and(|d| &**d == "none")
+            || attributes.get("style").is_some_and(|s| {
+                s.split(';')
+                    .any(|declaration| declaration.replace(' ', "") == "display:none")
+            });
+
+        if is_container(name) {
+            if kind == Type::Start {
+                let mut layer = parent.layer;
+                let is_layer = name == "g"
+                    && attributes
+                        .get("inkscape:groupmode")
+                        .is_some_and(|mode| &**mode == "layer");
+                if is_layer && !hidden {
+                    layer = layers.len();
+                    layers.push(SvgLayer {
+                        name: attributes
+                            .get("inkscape:label")
+                            .or_else(|| attributes.get("id"))
+                            .map(|name| xml::attribute_value(name)),
+                        pen: None,
+                        paths: vec![],
+                    });
+                    layer_ids.push((
+                        attributes.get("id").map(|id| xml::unescape(id)),
+                        attributes
+                            .get("fart:name")
+                            .map(|name| xml::attribute_value(name)),
+                    ));
+                }
+                groups.push(Group {
+                    transform,
+                    layer,
+                    hidden,
+                });
+            }
+            continue;
+        }
+
+        if hidden {
+            continue;
+        }
+        if let Some(path) =
+            shape(name, &attributes).with_context(|_| format!("invalid <{}> element", name))?
+        {
+            layers[parent.layer]
+                .paths
+                .push(path.transform_resolved(&transform));
+        }
+    }
+
+    for (layer, (id, name)) in layers.iter_mut().zip(layer_ids) {
+        layer.pen = pens
+            .iter()
+            .find(|(layer_id, _)| Some(layer_id) == id.as_ref())
+            .map(|(_, pen)| pen.clone());
+        // The labels of `Canvas`'s layers are made up from their names and
+        // pens, so only the name itself is read back.
+        if layer.pen.is_some() {
+            layer.name = name;
+        }
+    }
+    if layers[0].paths.is_empty() {
+        layers.remove(0);
+    }
+    Ok(layers)
+}
+
+fn is_container(name: &str) -> bool {
+    name == "g" || name == "svg" || UNDRAWN.contains(&name)
+}
+
+/// The group for the document's root `<svg>` element, which maps its user
+/// space onto the canvas according to its `viewBox`, `width`, and `height`.
+fn root_group<Unit>(attributes: &Attributes) -> crate::Result<Group>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    let units_per_mm: f64 = Unit::from(Millis(1.0)).into();
+    // CSS pixels, which are what user space is in without a `viewBox`.
+    let mm_per_pixel = Millis::from(Inches(1.0)).0 / 96.0;
+
+    let view_box = match attributes.get("viewBox") {
+        None => None,
+        Some(view_box) => {
+            let numbers = parse_numbers(view_box)?;
+            if numbers.len() != 4 || numbers[2] <= 0.0 || numbers[3] <= 0.0 {
+                failure::bail!("invalid viewBox: {:?}", &**view_box);
+            }
+            Some(numbers)
+        }
+    };
+    let length = |name: &str| -> crate::Result<Option<f64>> {
+        Ok(attributes
+            .get(name)
+            .map(|length| parse_length(length))
+            .transpose()?
+            .flatten())
+    };
+    let (width, height) = (length("width")?, length("height")?);
+
+    let (x, y, x_scale, y_scale) = match view_box {
+        Some(v) => (
+            v[0],
+            v[1],
+            width.map_or(mm_per_pixel, |w| w / v[2]),
+            height.map_or(mm_per_pixel, |h| h / v[3]),
+        ),
+        None => (0.0, 0.0, mm_per_pixel, mm_per_pixel),
+    };
+    Ok(Group {
+        transform: Transform2D::<f64, UserSpace, UserSpace>::translation(-x, -y)
+            .then_scale(x_scale * units_per_mm, y_scale * units_per_mm)
+            .with_destination(),
+        layer: 0,
+        hidden: false,
+    })
+}
+
+/// Parse a length with an optional unit into millimeters.
+fn parse_length(length: &str) -> crate::Result<Option<f64>> {
+    let length = length.trim();
+    let number = length.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%');
+    let unit = &length[number.len()..];
+    let number: f64 = number
+        .trim()
+        .parse::<f64>()
+        .with_context(|_| format!("invalid length: {:?}", length))?;
+    let inch = Millis::from(Inches(1.0)).0;
+    Ok(match unit {
+        "" | "px" => Some(number * inch / 96.0),
+        "pt" => Some(number * inch / 72.0),
+        "pc" => Some(number * inch / 6.0),
+        "in" => Some(number * inch),
+        "cm" => Some(number * 10.0),
+        "mm" => Some(number),
+        // Relative lengths don't say anything about the physical size.
+        _ => None,
+    })
+}
+
+/// Parse a list of numbers separated by whitespace or commas.
+fn parse_numbers(numbers: &str) -> crate::Result<Vec<f64>> {
+    numbers
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|n| !n.is_empty())
+        .map(|n| {
+            n.parse::<f64>()
+                .with_context(|_| format!("invalid number: {:?}", n))
+                .map_err(Into::into)
+        })
+        .collect()
+}
+
+/// Parse a `transform` attribute.
+fn parse_transform(transform: &str) -> crate::Result<Transform2D<f64, UserSpace, UserSpace>> {
+    let mut result = Transform2D::identity();
+    let mut rest = transform.trim();
+    while !rest.is_empty() {
+        let open = rest.find('(');
+        let close = rest.find(')');
+        let (open, close) = match (open, close) {
+            (Some(open), Some(close)) if open < close => (open, close),
+            _ => failure::bail!("invalid transform: {:?}", transform),
+        };
+        let name = rest[..open].trim();
+        let args = parse_numbers(&rest[open + 1..close])?;
+        rest = rest[close + 1..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+
+        let arg = |i: usize, default: Option<f64>| {
+            args.get(i)
+                .cloned()
+                .or(default)
+                .ok_or_else(|| failure::format_err!("invalid transform: {:?}", transform))
+        };
+        let matrix = |a, b, c, d, e, f| -> Transform2D<f64, UserSpace, UserSpace> {
+            Transform2D::new(a, b, c, d, e, f)
+        };
+        let t = match name {
+            "matrix" => matrix(
+                arg(0, None)?,
+                arg(1, None)?,
+                arg(2, None)?,
+                arg(3, None)?,
+                arg(4, None)?,
+                arg(5, None)?,
+            ),
+            "translate" => Transform2D::translation(arg(0, None)?, arg(1, Some(0.0))?),
+            "scale" => {
+                let x = arg(0, None)?;
+                Transform2D::scale(x, arg(1, Some(x))?)
+            }
+            "rotate" => {
+                let (sin, cos) = arg(0, None)?.to_radians().sin_cos();
+                let (x, y) = (arg(1, Some(0.0))?, arg(2, Some(0.0))?);
+                Transform2D::translation(-x, -y)
+                    .then(&matrix(cos, sin, -sin, cos, 0.0, 0.0))
+                    .then_translate(euclid::vec2(x, y))
+            }
+            "skewX" => matrix(1.0, 0.0, arg(0, None)?.to_radians().tan(), 1.0, 0.0, 0.0),
+            "skewY" => matrix(1.0, arg(0, None)?.to_radians().tan(), 0.0, 1.0, 0.0, 0.0),
+            _ => failure::bail!("unknown transform: {:?}", name),
+        };
+        // The transforms in the list apply from right to left.
+        result = t.then(&result);
+    }
+    Ok(result)
+}
+
+/// Get the path that an element draws, if it is a shape.
+fn shape(name: &str, attributes: &Attributes) -> crate::Result<Option<Path<f64, UserSpace>>> {
+    let number = |name: &str| -> crate::Result<f64> {
+        match attributes.get(name) {
+            None => Ok(0.0),
+            Some(value) => {
+                let value = value.trim();
+                let value = value.strip_suffix("px").unwrap_or(value);
+                Ok(value
+                    .parse::<f64>()
+                    .with_context(|_| format!("invalid {}: {:?}", name, value))?)
+            }
+        }
+    };
+    let arc = |radius: (f64, f64), x: f64, y: f64| LineCommand::ArcTo {
+        x_radius: radius.0,
+        y_radius: radius.1,
+        x_axis_rotation: Angle::zero(),
+        large_arc_flag: false,
+        sweep_flag: true,
+        end: point2(x, y),
+    };
+    let ellipse = |cx: f64, cy: f64, radius: (f64, f64)| {
+        if radius.0 <= 0.0 || radius.1 <= 0.0 {
+            return None;
+        }
+        Some(Path::with_commands(vec![
+            LineCommand::MoveTo(point2(cx + radius.0, cy)),
+            arc(radius, cx - radius.0, cy),
+            arc(radius, cx + radius.0, cy),
+            LineCommand::Close,
+        ]))
+    };
+
+    Ok(match name {
+        "path" => match attributes.get("d") {
+            None => None,
+            Some(d) => Some(Path::from_svg_data(d)?),
+        },
+        "rect" => {
+            let (x, y, width, height) = (
+                number("x")?,
+                number("y")?,
+                number("width")?,
+                number("height")?,
+            );
+            if width <= 0.0 || height <= 0.0 {
+                return Ok(None);
+            }
+            // A missing corner radius defaults to the other one.
+            let (rx, ry) = match (attributes.get("rx"), attributes.get("ry")) {
+                (None, None) => (0.0, 0.0),
+                (Some(_), None) => (number("rx")?, number("rx")?),
+                (None, Some(_)) => (number("ry")?, number("ry")?),
+                (Some(_), Some(_)) => (number("rx")?, number("ry")?),
+            };
+            let radius = ((rx.min(width / 2.0)), ry.min(height / 2.0));
+            if radius.0 <= 0.0 || radius.1 <= 0.0 {
+                Some(Path::with_commands(vec![
+                    LineCommand::MoveTo(point2(x, y)),
+                    LineCommand::HorizontalLineTo(x + width),
+                    LineCommand::VerticalLineTo(y + height),
+                    LineCommand::HorizontalLineTo(x),
+                    LineCommand::Close,
+                ]))
+            } else {
+                let (right, bottom) = (x + width, y + height);
+                Some(Path::with_commands(vec![
+                    LineCommand::MoveTo(point2(x + radius.0, y)),
+                    LineCommand::HorizontalLineTo(right - radius.0),
+                    arc(radius, right, y + radius.1),
+                    LineCommand::VerticalLineTo(bottom - radius.1),
+                    arc(radius, right - radius.0, bottom),
+                    LineCommand::HorizontalLineTo(x + radius.0),
+                    arc(radius, x, bottom - radius.1),
+                    LineCommand::VerticalLineTo(y + radius.1),
+                    arc(radius, x + radius.0, y),
+                    LineCommand::Close,
+                ]))
+            }
+        }
+        "circle" => {
+            let r = number("r")?;
+            ellipse(number("cx")?, number("cy")?, (r, r))
+        }
+        "ellipse" => ellipse(number("cx")?, number("cy")?, (number("rx")?, number("ry")?)),
+        "line" => Some(Path::with_commands(vec![
+            LineCommand::MoveTo(point2(number("x1")?, number("y1")?)),
+            LineCommand::LineTo(point2(number("x2")?, number("y2")?)),
+        ])),
+        "polyline" | "polygon" => {
+            let points = match attributes.get("points") {
+                None => return Ok(None),
+                Some(points) => parse_numbers(points)?,
+            };
+            // An odd number out is ignored, like browsers do.
+            let mut commands: Vec<_> = points
+                .chunks_exact(2)
+                .enumerate()
+                .map(|(i, p)| {
+                    if i == 0 {
+                        LineCommand::MoveTo(point2(p[0], p[1]))
+                    } else {
+                        LineCommand::LineTo(point2(p[0], p[1]))
+                    }
+                })
+                .collect();
+            if commands.is_empty() {
+                return Ok(None);
+            }
+            if name == "polygon" {
+                commands.push(LineCommand::Close);
+            }
+            Some(Path::with_commands(commands))
+        }
+        _ => None,
+    })
+}
+
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    /// Import the paths of an SVG document onto this canvas.
+    ///
+    /// Every layer that `read_svg` finds becomes a new layer on this canvas,
+    /// with the layer's name, drawn with the pen the SVG records for it, or
+    /// else with the given pen. The new layers' keys are returned in document
+    /// order. The paths are placed on the paper where they are in the
+    /// document, ignoring the canvas's margins. Like anything else drawn,
+    /// they are clipped and occlude earlier paths if the canvas is set up to.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn import<P: fart::penlib::Pen + Clone>(
+    /// #     canvas: &mut fart::canvas::Canvas<fart::units::Millis>,
+    /// #     pen: P,
+    /// # ) -> fart::Result<()> {
+    /// let logo = std::fs::read_to_string("logo.svg")?;
+    /// let layers = canvas.import_svg(&logo, pen)?;
+    /// # let _ = layers;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn import_svg<P>(&mut self, svg: &str, pen: P) -> crate::Result<Vec<LayerKey>>
+    where
+        P: Pen + Clone,
+    {
+        let layers = read_svg::<Unit>(svg)?;
+        Ok(layers
+            .into_iter()
+            .map(|layer| {
+                let key = match &layer.pen {
+                    Some(layer_pen) => self.create_layer_with(layer_pen.clone()),
+                    None => self.create_layer(pen.clone()),
+                };
+                if let Some(name) = &layer.name {
+                    self.set_layer_name(key, name.clone());
+                }
+                self.draw_svg_layer(key, layer);
+                key
+            })
+            .collect())
+    }
+
+    /// Draw the paths of a layer returned by `read_svg` onto an existing
+    /// layer of this canvas.
+    ///
+    /// Like with `Canvas::import_svg`, the paths are placed on the paper
+    /// where they are in the document, ignoring the canvas's margins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer is not on this canvas. See
+    /// `Canvas::try_draw_svg_layer` for a version that returns an error
+    /// instead.
+    pub fn draw_svg_layer(&mut self, layer: LayerKey, svg_layer: SvgLayer) {
+        self.add_paths(layer, svg_layer.paths);
+    }
+
+    /// Draw the paths of a layer returned by `read_svg` onto an existing
+    /// layer of this canvas, or return an error if the layer is not on this
+    /// canvas.
+    pub fn try_draw_svg_layer(
+        &mut self,
+        layer: LayerKey,
+        svg_layer: SvgLayer,
+    ) -> Result<(), CanvasError> {
+        self.try_add_paths(layer, svg_layer.paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::tests::TestPen;
+    use crate::units::Paper;
+    use euclid::Point2D;
+
+    fn vertices(path: &Path<f64, CanvasSpace>) -> Vec<Point2D<f64, CanvasSpace>> {
+        path.flatten(0.01)
+            .into_iter()
+            .flat_map(|p| p.vertices().to_vec())
+            .map(|p| {
+                point2(
+                    (p.x * 1000.0).round() / 1000.0,
+                    (p.y * 1000.0).round() / 1000.0,
+                )
+            })
+            .collect()
+    }
+
+    fn read(svg: &str) -> Vec<SvgLayer> {
+        read_svg::<Millis>(svg).unwrap()
+    }
+
+    #[test]
+    fn basic_shapes() {
+        let layers = read(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100" width="100mm" height="100mm">
+                <rect x="10" y="10" width="20" height="10"/>
+                <rect x="10" y="10" width="20" height="10" rx="2"/>
+                <circle cx="50" cy="50" r="5"/>
+                <ellipse cx="50" cy="50" rx="5" ry="2"/>
+                <line x1="0" y1="0" x2="10" y2="10"/>
+                <polyline points="0,0 10,0 10,10"/>
+                <polygon points="0 0 10 0 10 10"/>
+                <path d="M0,0 h5 v5"/>
+                <circle cx="50" cy="50" r="0"/>
+            </svg>"#,
+        );
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].name, None);
+        let paths = &layers[0].paths;
+        assert_eq!(paths.len(), 8);
+
+        assert_eq!(
+            vertices(&paths[0]),
+            vec![
+                point2(10.0, 10.0),
+                point2(30.0, 10.0),
+                point2(30.0, 20.0),
+                point2(10.0, 20.0),
+                point2(10.0, 10.0),
+            ]
+        );
+        let rounded = paths[1].length();
+        let expected = 2.0 * (16.0 + 6.0) + 2.0 * std::f64::consts::PI * 2.0;
+        assert!((rounded - expected).abs() < 1e-6, "{}", rounded);
+        assert!((paths[2].length() - 10.0 * std::f64::consts::PI).abs() < 1e-6);
+        assert_eq!(
+            vertices(&paths[4]),
+            vec![point2(0.0, 0.0), point2(10.0, 10.0)]
+        );
+        assert_eq!(paths[5].commands.len(), 3);
+        assert_eq!(vertices(&paths[6]).len(), 4);
+        assert_eq!(vertices(&paths[7]).last(), Some(&point2(5.0, 5.0)));
+    }
+
+    #[test]
+    fn group_transforms_are_applied() {
+        let layers = read(
+            r#"<svg viewBox="0 0 100 100" width="100mm" height="100mm">
+                <g transform="translate(10, 20)">
+                    <g transform="scale(2) rotate(90)">
+                        <path d="M0,0 L5,0" transform="translate(1)"/>
+                    </g>
+                </g>
+            </svg>"#,
+        );
+        // (1,0)-(6,0), rotated to (0,1)-(0,6), scaled to (0,2)-(0,12), and
+        // moved.
+        assert_eq!(
+            vertices(&layers[0].paths[0]),
+            vec![point2(10.0, 22.0), point2(10.0, 32.0)]
+        );
+    }
+
+    #[test]
+    fn physical_size_is_honored() {
+        // Two user units per millimeter, on a canvas in inches.
+        let layers = read_svg::<Inches>(
+            r#"<svg viewBox="10 10 254 254" width="127mm" height="127mm">
+                <path d="M10,10 L264,10"/>
+            </svg>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vertices(&layers[0].paths[0]),
+            vec![point2(0.0, 0.0), point2(5.0, 0.0)]
+        );
+
+        // Without a viewBox, user units are CSS pixels.
+        let layers = read(r#"<svg><path d="M0,0 L96,0"/></svg>"#);
+        assert_eq!(
+            vertices(&layers[0].paths[0]),
+            vec![point2(0.0, 0.0), point2(25.4, 0.0)]
+        );
+    }
+
+    #[test]
+    fn inkscape_layers_become_layers() {
+        let svg = r#"<svg viewBox="0 0 100 100" width="100mm" height="100mm"
+                          xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape">
+            <defs><path d="M0,0 L1,1"/></defs>
+            <g inkscape:groupmode="layer" inkscape:label="Outline">
+                <path d="M0,0 L10,0"/>
+                <g><path d="M0,0 L20,0"/></g>
+            </g>
+            <g inkscape:groupmode="layer" inkscape:label="Hidden" style="display:none">
+                <path d="M0,0 L30,0"/>
+            </g>
+            <g inkscape:groupmode="layer" id="layer3">
+                <path d="M0,0 L40,0" style="display: none"/>
+                <path d="M0,0 L50,0"/>
+            </g>
+        </svg>"#;
+        let layers = read(svg);
+        let summary: Vec<_> = layers
+            .iter()
+            .map(|l| {
+                (
+                    l.name.as_deref(),
+                    l.paths.iter().map(|p| p.length()).collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("Outline"), vec![10.0, 20.0]),
+                (Some("layer3"), vec![50.0]),
+            ]
+        );
+
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let keys = canvas.import_svg(svg, TestPen).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(canvas.layers[keys[0]].paths.len(), 2);
+        assert_eq!(canvas.layers[keys[1]].paths.len(), 1);
+    }
+
+    #[test]
+    fn canvases_round_trip() {
+        let mut canvas = Canvas::new(Paper::new(Inches(4.0), Inches(2.0)));
+        let first = canvas.create_layer(TestPen);
+        let second = canvas.create_layer(TestPen);
+        for &(layer, y) in &[(first, 0.5), (second, 1.5)] {
+            canvas.add_paths(
+                layer,
+                Some(Path::with_commands(vec![
+                    LineCommand::MoveTo(point2(1.0, y)),
+                    LineCommand::LineTo(point2(3.0, y)),
+                ])),
+            );
+        }
+
+        let svg = canvas.create_svg().to_string();
+        let layers = read_svg::<Inches>(&svg).unwrap();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(
+            vertices(&layers[1].paths[0]),
+            vec![point2(1.0, 1.5), point2(3.0, 1.5)]
+        );
+    }
+
+    #[test]
+    fn pens_round_trip() {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let pen = PenInfo::new(palette::rgb::LinSrgb::new(0.0, 0.0, 1.0), Millis(0.3))
+            .brand("Staedtler")
+            .model("Pigment Liner")
+            .color_name("blue");
+        let named = canvas.create_layer_with(pen.clone());
+        canvas.set_layer_name(named, "Outline");
+        let unnamed = canvas.create_layer(TestPen);
+        let special = canvas.create_layer_with(
+            PenInfo::new(palette::rgb::LinSrgb::new(1.0, 0.0, 0.0), Millis(1.5))
+                .brand("Faber & Castell")
+                .model("Pitt <B>")
+                .color_name(r#"red "deep scarlet""#),
+        );
+        canvas.set_layer_name(special, "Sky & <Sea>");
+        for &layer in &[named, unnamed, special] {
+            canvas.add_paths(
+                layer,
+                Some(Path::with_commands(vec![
+                    LineCommand::MoveTo(point2(10.0, 10.0)),
+                    LineCommand::LineTo(point2(20.0, 10.0)),
+                ])),
+            );
+        }
+
+        let svg = canvas.create_svg().to_string();
+        assert!(svg.contains(r#"inkscape:label="1: Outline (Staedtler Pigment Liner blue 0.3mm)""#));
+        assert!(svg.contains(r#"inkscape:label="2: TestPen 0.5mm""#));
+
+        let layers = read(&svg);
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[0].pen.as_ref(), Some(&pen));
+        assert_eq!(layers[1].pen.as_ref(), canvas.pen_info(unnamed));
+        assert_eq!(layers[0].name.as_deref(), Some("Outline"));
+        assert_eq!(layers[1].name, None);
+
+        // Special characters are escaped, and come back as they were.
+        let special = layers[2].pen.as_ref().unwrap();
+        assert_eq!(special.brand.as_deref(), Some("Faber & Castell"));
+        assert_eq!(special.model.as_deref(), Some("Pitt <B>"));
+        assert_eq!(special.color_name.as_deref(), Some(r#"red "deep scarlet""#));
+        assert_eq!(layers[2].name.as_deref(), Some("Sky & <Sea>"));
+
+        // Layers are imported with their own pens, not the given one.
+        let mut imported = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let keys = imported.import_svg(&svg, TestPen).unwrap();
+        assert_eq!(imported.pen_info(keys[0]), Some(&pen));
+        assert_eq!(imported.layer_name(keys[0]), Some("Outline"));
+
+        // Each layer's own document only records its own pen.
+        let (_, doc) = &canvas.create_layer_svgs()[1];
+        let layers = read(&doc.to_string());
+        assert_eq!(
+            layers[0].pen.as_ref().unwrap().model.as_deref(),
+            Some("TestPen")
+        );
+        assert_eq!(doc.to_string().matches("<fart:pen").count(), 1);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(read_svg::<Millis>(r#"<svg><path d="M0,0 L1"/></svg>"#).is_err());
+        assert!(read_svg::<Millis>(r#"<svg><g transform="wobble(1)"/></svg>"#).is_err());
+        assert!(read_svg::<Millis>(r#"<svg><rect width="ten" height="1"/></svg>"#).is_err());
+    }
+}
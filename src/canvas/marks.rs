@@ -0,0 +1,275 @@
+//! Registration marks, crop marks, and frames, for lining up plots made in
+//! several passes.
+
+use super::{Canvas, CanvasSpace, LayerKey, PenInfo};
+use crate::path::{LineCommand, Path};
+use crate::units::{Millis, SvgUnit};
+use euclid::{point2, vec2, Angle, Point2D, Vector2D};
+use penlib::Pen;
+
+/// Which marks to draw in the margins of the paper. See `Canvas::add_marks`.
+///
+/// # Example
+///
+/// Small crop marks and a frame, without registration crosshairs:
+///
+/// ```
+/// use fart::canvas::Marks;
+/// use fart::units::Millis;
+///
+/// let marks = Marks::new()
+///     .registration(false)
+///     .frame(true)
+///     .size(Millis(3.0));
+/// # let _ = marks;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Marks {
+    registration: bool,
+    crop_marks: bool,
+    frame: bool,
+    size: Millis,
+}
+
+impl Default for Marks {
+    fn default() -> Marks {
+        Marks {
+            registration: true,
+            crop_marks: true,
+            frame: false,
+            size: Millis(5.0),
+        }
+    }
+}
+
+impl Marks {
+    /// Construct the default marks.
+    ///
+    /// By default, there are registration crosshairs and crop marks, which
+    /// are 5mm large, and no frame.
+    pub fn new() -> Marks {
+        Marks::default()
+    }
+
+    /// Set whether to draw registration crosshairs in the middle of every
+    /// margin.
+    pub fn registration(mut self, registration: bool) -> Marks {
+        self.registration = registration;
+        self
+    }
+
+    /// Set whether to draw crop marks that extend the edges of the printable
+    /// area out into the margins at its corners.
+    pub fn crop_marks(mut self, crop_marks: bool) -> Marks {
+        self.crop_marks = crop_marks;
+        self
+    }
+
+    /// Set whether to draw a frame around the printable area.
+    pub fn frame(mut self, frame: bool) -> Marks {
+        self.frame = frame;
+        self
+    }
+
+    /// Set how large the registration crosshairs are, and how long the crop
+    /// marks are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not positive.
+    pub fn size(mut self, size: Millis) -> Marks {
+        assert!(size.0 > 0.0, "mark size must be positive");
+        self.size = size;
+        self
+    }
+}
+
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    /// Draw the given marks in the margins of the paper, on a new layer that
+    /// uses the given pen.
+    ///
+    /// Draw the marks with every pen, or plot the marks' layer before
+    /// swapping pens, to line the passes up with each other. The marks stay
+    /// exactly where they are: the layer is never clipped, occluded,
+    /// reordered, merged, or simplified.
+    ///
+    /// Registration crosshairs go in the middle of every margin that is at
+    /// least as deep as the marks' size, and crop marks are shortened to fit
+    /// their margins, or left out if there is no room for them at all.
+    pub fn add_marks<P>(&mut self, pen: P, marks: &Marks) -> LayerKey
+    where
+        P: Pen,
+    {
+        let paths = self.mark_paths(marks);
+        self.insert_layer(PenInfo::of(&pen), paths, true)
+    }
+
+    fn mark_paths(&self, marks: &Marks) -> Vec<Path<f64, CanvasSpace>> {
+        let size: f64 = Unit::from(marks.size).into();
+        let area = self.printable_area();
+        let (min, max) = (area.min(), area.max());
+        let (width, height): (f64, f64) = (self.paper.width.into(), self.paper.height.into());
+        let center = min.lerp(max, 0.5);
+        let mut paths = vec![];
+
+        if marks.registration {
+            // Every margin's depth, and the middle of that margin.
+            let margins = [
+                (min.y, point2(center.x, min.y / 2.0)),
+                (height - max.y, point2(center.x, (max.y + height) / 2.0)),
+                (min.x, point2(min.x / 2.0, center.y)),
+                (width - max.x, point2((max.x + width) / 2.0, center.y)),
+            ];
+            for &(depth, center) in &margins {
+                if depth >= size {
+                    paths.push(crosshair(center, size));
+                }
+            }
+        }
+
+        if marks.crop_marks {
+            // There is a small gap between the printable area and the marks,
+            // so that they don't touch the art.
+            let gap = size / 5.0;
+            let corners = [
+                (min, (-1.0, -1.0), (min.x, min.y)),
+                (point2(max.x, min.y), (1.0, -1.0), (width - max.x, min.y)),
+                (point2(min.x, max.y), (-1.0, 1.0), (min.x, height - max.y)),
+                (max, (1.0, 1.0), (width - max.x, height - max.y)),
+            ];
+            for &(corner, (dx, dy), (x_margin, y_margin)) in &corners {
+                let x_length = size.min(x_margin - gap);
+                if x_length > 0.0 {
+                    let start = corner + vec2(dx * gap, 0.0);
+                    paths.push(line(start, vec2(dx * x_length, 0.0)));
+                }
+                let y_length = size.min(y_margin - gap);
+                if y_length > 0.0 {
+                    let start = corner + vec2(0.0, dy * gap);
+                    paths.push(line(start, vec2(0.0, dy * y_length)));
+                }
+            }
+        }
+
+        if marks.frame {
+            paths.push(Path::with_commands(vec![
+                LineCommand::MoveTo(min),
+                LineCommand::HorizontalLineTo(max.x),
+                LineCommand::VerticalLineTo(max.y),
+                LineCommand::HorizontalLineTo(min.x),
+                LineCommand::Close,
+            ]));
+        }
+        paths
+    }
+}
+
+fn line(from: Point2D<f64, CanvasSpace>, by: Vector2D<f64, CanvasSpace>) -> Path<f64, CanvasSpace> {
+    Path::with_commands(vec![LineCommand::MoveTo(from), LineCommand::LineBy(by)])
+}
+
+/// A circle with a cross through it, which fits in a square of the given
+/// size around the given center.
+fn crosshair(center: Point2D<f64, CanvasSpace>, size: f64) -> Path<f64, CanvasSpace> {
+    let radius = size / 3.0;
+    let arc = |end| LineCommand::ArcTo {
+        x_radius: radius,
+        y_radius: radius,
+        x_axis_rotation: Angle::zero(),
+        large_arc_flag: false,
+        sweep_flag: true,
+        end,
+    };
+    let half = size / 2.0;
+    Path::with_commands(vec![
+        LineCommand::MoveTo(center + vec2(radius, 0.0)),
+        arc(center - vec2(radius, 0.0)),
+        arc(center + vec2(radius, 0.0)),
+        LineCommand::Close,
+        LineCommand::MoveTo(center - vec2(half, 0.0)),
+        LineCommand::LineTo(center + vec2(half, 0.0)),
+        LineCommand::MoveTo(center - vec2(0.0, half)),
+        LineCommand::LineTo(center + vec2(0.0, half)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::tests::TestPen;
+    use crate::path::ToPaths;
+    use crate::simplify::{Method, Simplify};
+    use crate::units::Paper;
+
+    fn canvas() -> Canvas<Millis> {
+        // Margins of 10mm, 10mm, 20mm, and 3mm.
+        Canvas::new(Paper::new(Millis(100.0), Millis(100.0)).add_margins(
+            Millis(10.0),
+            Millis(10.0),
+            Millis(20.0),
+            Millis(3.0),
+        ))
+    }
+
+    fn commands(canvas: &Canvas<Millis>) -> String {
+        format!("{:?}", canvas.to_paths().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn marks_are_in_the_margins() {
+        let mut canvas = canvas();
+        canvas.add_marks(TestPen, &Marks::new());
+        let paths: Vec<_> = canvas.to_paths().collect();
+
+        // The left margin is too narrow for a crosshair, and the crop marks
+        // there are shortened to 2mm.
+        let crosshairs = paths.iter().filter(|p| p.commands.len() == 8).count();
+        assert_eq!(crosshairs, 3);
+        assert_eq!(paths.len(), 3 + 8);
+        let lengths: Vec<_> = paths[3..].iter().map(|p| p.length()).collect();
+        assert_eq!(lengths, vec![2.0, 5.0, 5.0, 5.0, 2.0, 5.0, 5.0, 5.0]);
+
+        let printable = canvas.printable_area();
+        for path in &paths {
+            for polyline in path.flatten(0.01) {
+                for v in polyline.vertices() {
+                    assert!(v.x >= 0.0 && v.x <= 100.0 && v.y >= 0.0 && v.y <= 100.0);
+                    let inside = v.x > printable.min().x
+                        && v.x < printable.max().x
+                        && v.y > printable.min().y
+                        && v.y < printable.max().y;
+                    assert!(!inside, "{:?} is in the printable area", v);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn frame_is_the_printable_area() {
+        let mut canvas = canvas();
+        let marks = Marks::new()
+            .registration(false)
+            .crop_marks(false)
+            .frame(true);
+        canvas.add_marks(TestPen, &marks);
+        let paths: Vec<_> = canvas.to_paths().collect();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].length(), 2.0 * (87.0 + 70.0));
+    }
+
+    #[test]
+    fn marks_are_left_alone_by_optimizations() {
+        let mut canvas = canvas();
+        canvas.set_clip_to_printable_area(true);
+        canvas.add_marks(TestPen, &Marks::new().frame(true));
+        let before = commands(&canvas);
+
+        canvas.optimize_travel();
+        canvas.merge_paths(100.0);
+        canvas.simplify(&Simplify::new(Method::Visvalingam).tolerance(10.0));
+        assert_eq!(commands(&canvas), before);
+    }
+}
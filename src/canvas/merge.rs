@@ -0,0 +1,223 @@
+//! Merging paths whose ends touch into continuous strokes.
+
+use super::CanvasSpace;
+use crate::path::Path;
+use euclid::Point2D;
+use std::collections::{HashMap, VecDeque};
+
+type Point = Point2D<f64, CanvasSpace>;
+
+/// The path ends in each cell of an `EndIndex`: where each end is, the index
+/// of its path, and which end of the path it is.
+type Cells = HashMap<(i64, i64), Vec<(Point, usize, End)>>;
+
+/// Join paths whose ends are within `tolerance` of each other into single
+/// paths, reversing them where needed.
+///
+/// Chains are grown from each path in turn, in their original order, by
+/// repeatedly joining the nearest touching path onto either end. Paths that
+/// don't draw anything are dropped.
+pub(crate) fn merge(
+    paths: Vec<Path<f64, CanvasSpace>>,
+    tolerance: f64,
+) -> Vec<Path<f64, CanvasSpace>> {
+    let paths: Vec<_> = paths
+        .into_iter()
+        .filter_map(|path| Some((path.start()?, path.end()?, path)))
+        .collect();
+
+    let mut ends = EndIndex::new(tolerance);
+    for (i, &(start, end, _)) in paths.iter().enumerate() {
+        ends.insert(start, (i, End::Start));
+        ends.insert(end, (i, End::End));
+    }
+
+    let mut used = vec![false; paths.len()];
+    let mut merged = vec![];
+    for seed in 0..paths.len() {
+        if used[seed] {
+            continue;
+        }
+        used[seed] = true;
+
+        // The paths in the chain, and whether each is reversed.
+        let mut chain = VecDeque::new();
+        chain.push_back((seed, false));
+
+        let (mut start, mut end, _) = paths[seed];
+        while let Some((i, touching)) = ends.nearest(end, &used) {
+            used[i] = true;
+            // Continue from the end that touches, and leave from the other.
+            let reversed = touching == End::End;
+            end = if reversed { paths[i].0 } else { paths[i].1 };
+            chain.push_back((i, reversed));
+        }
+        while let Some((i, touching)) = ends.nearest(start, &used) {
+            used[i] = true;
+            // Lead into the chain from the end that touches.
+            let reversed = touching == End::Start;
+            start = if reversed { paths[i].1 } else { paths[i].0 };
+            chain.push_front((i, reversed));
+        }
+
+        let mut path = Path::new();
+        for (i, reversed) in chain {
+            if reversed {
+                path.connect(&paths[i].2.reversed());
+            } else {
+                path.connect(&paths[i].2);
+            }
+        }
+        merged.push(path);
+    }
+    merged
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum End {
+    Start,
+    End,
+}
+
+/// A spatial hash of path ends, with cells as big as the tolerance, so that
+/// every end within the tolerance of a point is in one of the nine cells
+/// around it.
+struct EndIndex {
+    tolerance: f64,
+    cells: Cells,
+}
+
+impl EndIndex {
+    fn new(tolerance: f64) -> EndIndex {
+        EndIndex {
+            tolerance,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, p: Point) -> (i64, i64) {
+        (
+            (p.x / self.tolerance).floor() as i64,
+            (p.y / self.tolerance).floor() as i64,
+        )
+    }
+
+    fn insert(&mut self, p: Point, (i, end): (usize, End)) {
+        let cell = self.cell(p);
+        self.cells.entry(cell).or_default().push((p, i, end));
+    }
+
+    /// Find the nearest end of an unused path within the tolerance of `p`.
+    fn nearest(&self, p: Point, used: &[bool]) -> Option<(usize, End)> {
+        let (cx, cy) = self.cell(p);
+        let mut best: Option<(f64, usize, End)> = None;
+        for x in cx - 1..=cx + 1 {
+            for y in cy - 1..=cy + 1 {
+                let cell = match self.cells.get(&(x, y)) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                for &(q, i, end) in cell {
+                    let distance = (q - p).length();
+                    if used[i] || distance > self.tolerance {
+                        continue;
+                    }
+                    match best {
+                        Some((d, _, _)) if d <= distance => {}
+                        _ => best = Some((distance, i, end)),
+                    }
+                }
+            }
+        }
+        best.map(|(_, i, end)| (i, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::LineCommand;
+    use euclid::point2;
+
+    fn line(from: (f64, f64), to: (f64, f64)) -> Path<f64, CanvasSpace> {
+        Path::with_commands(vec![
+            LineCommand::MoveTo(point2(from.0, from.1)),
+            LineCommand::LineTo(point2(to.0, to.1)),
+        ])
+    }
+
+    #[test]
+    fn touching_lines_are_joined_in_either_direction() {
+        let merged = merge(
+            vec![
+                line((1.0, 0.0), (2.0, 0.0)),
+                // Touches the first line's end, but runs backwards.
+                line((3.0, 0.0), (2.0, 0.0)),
+                // Leads into the first line's start.
+                line((0.0, 0.0), (1.0, 0.0)),
+                // Touches nothing.
+                line((0.0, 5.0), (1.0, 5.0)),
+            ],
+            0.01,
+        );
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(
+            merged[0].flatten(0.1)[0].vertices(),
+            &[
+                point2(0.0, 0.0),
+                point2(1.0, 0.0),
+                point2(2.0, 0.0),
+                point2(3.0, 0.0)
+            ]
+        );
+        assert_eq!(merged[1].length(), 1.0);
+    }
+
+    #[test]
+    fn ends_within_the_tolerance_are_bridged() {
+        let merged = merge(
+            vec![line((0.0, 0.0), (1.0, 0.0)), line((1.005, 0.0), (2.0, 0.0))],
+            0.01,
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].flatten(0.1).len(), 1);
+        assert!((merged[0].length() - 2.0).abs() < 1e-9);
+
+        let apart = merge(
+            vec![line((0.0, 0.0), (1.0, 0.0)), line((1.02, 0.0), (2.0, 0.0))],
+            0.01,
+        );
+        assert_eq!(apart.len(), 2);
+    }
+
+    #[test]
+    fn segments_of_a_closed_loop_are_joined_into_one() {
+        let merged = merge(
+            vec![
+                line((0.0, 0.0), (1.0, 0.0)),
+                line((1.0, 1.0), (0.0, 1.0)),
+                line((0.0, 1.0), (0.0, 0.0)),
+                line((1.0, 0.0), (1.0, 1.0)),
+            ],
+            0.01,
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].flatten(0.1)[0].len(), 5);
+        assert_eq!(merged[0].length(), 4.0);
+    }
+
+    #[test]
+    fn closed_shapes_joined_onto_lines_are_not_closed_again() {
+        let square = Path::with_commands(vec![
+            LineCommand::MoveTo(point2(1.0, 0.0)),
+            LineCommand::LineTo(point2(2.0, 0.0)),
+            LineCommand::LineTo(point2(2.0, 1.0)),
+            LineCommand::LineTo(point2(1.0, 1.0)),
+            LineCommand::Close,
+        ]);
+        let merged = merge(vec![line((0.0, 0.0), (1.0, 0.0)), square], 0.01);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].length(), 5.0);
+    }
+}
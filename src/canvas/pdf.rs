@@ -0,0 +1,384 @@
+//! Exporting canvases as vector PDFs, for print shops and plotter drivers
+//! that want them.
+
+use super::{Canvas, CanvasSpace};
+use crate::units::{Inches, Millis, SvgUnit};
+use euclid::Point2D;
+use std::fmt::Write;
+
+/// How far the crop marks are from the paper's corners, in millimeters.
+const CROP_MARK_OFFSET: f64 = 3.0;
+
+/// How long the crop marks are, in millimeters.
+const CROP_MARK_LENGTH: f64 = 5.0;
+
+/// How much room is added around the paper for the crop marks, in
+/// millimeters.
+const CROP_MARK_MARGIN: f64 = CROP_MARK_OFFSET + CROP_MARK_LENGTH + 2.0;
+
+/// How the layers of a canvas are laid out on PDF pages. See
+/// `PdfOptions::pages`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PdfPages {
+    /// Draw every layer on the same page, like the SVG output.
+    Combined,
+
+    /// Draw every layer on its own page, in the order the layers were
+    /// created, so that each pen's part of the plot can be printed or plotted
+    /// separately.
+    PerLayer,
+}
+
+/// Options for rendering a canvas as a PDF. See `Canvas::create_pdf`.
+///
+/// # Example
+///
+/// One page per pen, with crop marks:
+///
+/// ```
+/// use fart::canvas::{PdfOptions, PdfPages};
+///
+/// let options = PdfOptions::new()
+///     .pages(PdfPages::PerLayer)
+///     .crop_marks(true);
+/// # let _ = options;
+/// ```
+#[derive(Clone, Debug)]
+pub struct PdfOptions {
+    pages: PdfPages,
+    crop_marks: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> PdfOptions {
+        PdfOptions {
+            pages: PdfPages::Combined,
+            crop_marks: false,
+        }
+    }
+}
+
+impl PdfOptions {
+    /// Construct the default options.
+    ///
+    /// By default, every layer is drawn on one page, without crop marks.
+    pub fn new() -> PdfOptions {
+        PdfOptions::default()
+    }
+
+    /// Set how the layers are laid out on pages.
+    pub fn pages(mut self, pages: PdfPages) -> PdfOptions {
+        self.pages = pages;
+        self
+    }
+
+    /// Set whether to draw crop marks at the paper's corners.
+    ///
+    /// The marks are drawn outside of the paper, so the pages get larger to
+    /// make room for them, and the paper itself becomes the pages' trim box.
+    pub fn crop_marks(mut self, crop_marks: bool) -> PdfOptions {
+        self.crop_marks = crop_marks;
+        self
+    }
+}
+
+/// Convert millimeters into PDF points.
+fn points(mm: f64) -> f64 {
+    mm * 72.0 / Millis::from(Inches(1.0)).0
+}
+
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    /// Render this canvas as a PDF with the given options.
+    ///
+    /// The pages are the paper's physical size. Every layer is stroked in its
+    /// pen's color with lines as wide as the pen's nib, and curves are
+    /// flattened into straight lines. Dots are stroked as lines without any
+    /// length, which the round line caps turn into spots of the nib's size.
+    pub fn create_pdf(&self, options: &PdfOptions) -> Vec<u8> {
+        let units_per_mm: f64 = Unit::from(Millis(1.0)).into();
+        let width = self.paper.width.into() / units_per_mm;
+        let height = self.paper.height.into() / units_per_mm;
+        let margin = if options.crop_marks {
+            CROP_MARK_MARGIN
+        } else {
+            0.0
+        };
+        let to_page = |p: Point2D<f64, CanvasSpace>| {
+            (
+                points(margin + p.x / units_per_mm),
+                points(margin + height - p.y / units_per_mm),
+            )
+        };
+
+        // Draw every layer separately, and then put them on pages.
+        let tolerance = self.flattening_tolerance();
+        let mut layers = vec![];
+        for (_, layer) in self.visible_layers() {
+            let polylines: Vec<_> = layer
+                .paths
+                .iter()
+                .flat_map(|path| path.flatten(tolerance))
+                .collect();
+            if polylines.is_empty() {
+                continue;
+            }
+
+            let (r, g, b) = layer.pen.color.into_components();
+            let mut content = String::from("q\n1 J 1 j\n");
+            writeln!(content, "{:.3} {:.3} {:.3} RG", r, g, b).unwrap();
+            writeln!(content, "{:.3} w", points(layer.pen.nib_size.0)).unwrap();
+            for polyline in polylines {
+                let mut operator = "m";
+                for &v in polyline.vertices() {
+                    let (x, y) = to_page(v);
+                    writeln!(content, "{:.3} {:.3} {}", x, y, operator).unwrap();
+                    operator = "l";
+                }
+                content.push_str("S\n");
+            }
+            content.push_str("Q\n");
+            layers.push(content);
+        }
+
+        let mut pages = match options.pages {
+            PdfPages::Combined => vec![layers.concat()],
+            PdfPages::PerLayer => layers,
+        };
+        if pages.is_empty() {
+            // A document needs at least one page, even if it is blank.
+            pages.push(String::new());
+        }
+        if options.crop_marks {
+            let marks = crop_marks(width, height);
+            for page in &mut pages {
+                page.push_str(&marks);
+            }
+        }
+
+        let media_box = [
+            0.0,
+            0.0,
+            points(width + 2.0 * margin),
+            points(height + 2.0 * margin),
+        ];
+        let trim_box = [
+            points(margin),
+            points(margin),
+            points(margin + width),
+            points(margin + height),
+        ];
+        write_document(&pages, media_box, options.crop_marks.then_some(trim_box))
+    }
+}
+
+/// The content stream for crop marks around a `width` by `height` paper,
+/// which is `CROP_MARK_MARGIN` away from the page's edges.
+fn crop_marks(width: f64, height: f64) -> String {
+    let mut marks = String::from("q\n0 G\n0.25 w\n");
+    let near = CROP_MARK_OFFSET;
+    let far = CROP_MARK_OFFSET + CROP_MARK_LENGTH;
+    for &x in &[0.0, width] {
+        for &y in &[0.0, height] {
+            // Which way is away from the paper at this corner.
+            let dx = if x == 0.0 { -1.0 } else { 1.0 };
+            let dy = if y == 0.0 { -1.0 } else { 1.0 };
+            let (x, y) = (CROP_MARK_MARGIN + x, CROP_MARK_MARGIN + y);
+
+            let lines = [
+                ((x, y + dy * near), (x, y + dy * far)),
+                ((x + dx * near, y), (x + dx * far, y)),
+            ];
+            for &((x0, y0), (x1, y1)) in &lines {
+                writeln!(
+                    marks,
+                    "{:.3} {:.3} m {:.3} {:.3} l S",
+                    points(x0),
+                    points(y0),
+                    points(x1),
+                    points(y1)
+                )
+                .unwrap();
+            }
+        }
+    }
+    marks.push_str("Q\n");
+    marks
+}
+
+/// Write a PDF document with the given page content streams.
+fn write_document(pages: &[String], media_box: [f64; 4], trim_box: Option<[f64; 4]>) -> Vec<u8> {
+    let rect = |r: [f64; 4]| format!("[{:.3} {:.3} {:.3} {:.3}]", r[0], r[1], r[2], r[3]);
+
+    // The catalog is object 1, the page tree is object 2, and then every
+    // page is followed by its content stream.
+    let page_id = |i: usize| 3 + 2 * i;
+    let mut objects = vec![
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", page_id(i)))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        let mut page = format!("<< /Type /Page /Parent 2 0 R /MediaBox {}", rect(media_box));
+        if let Some(trim_box) = trim_box {
+            write!(page, " /TrimBox {}", rect(trim_box)).unwrap();
+        }
+        write!(
+            page,
+            " /Resources << >> /Contents {} 0 R >>",
+            page_id(i) + 1
+        )
+        .unwrap();
+        objects.push(page);
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    // The comment with high bytes tells tools that the file is binary.
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        writeln!(trailer, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )
+    .unwrap();
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::tests::TestPen;
+    use crate::path::{LineCommand, Path};
+    use crate::units::Paper;
+    use euclid::point2;
+
+    fn canvas() -> Canvas<Millis> {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(50.0)));
+        let first = canvas.create_layer(TestPen);
+        // Never drawn on, so it gets no page.
+        canvas.create_layer(TestPen);
+        let third = canvas.create_layer(TestPen);
+        for &(layer, y) in &[(first, 10.0), (third, 20.0)] {
+            canvas.add_paths(
+                layer,
+                Some(Path::with_commands(vec![
+                    LineCommand::MoveTo(point2(10.0, y)),
+                    LineCommand::LineTo(point2(20.0, y)),
+                ])),
+            );
+        }
+        canvas
+    }
+
+    /// Check that the cross-reference table points at the objects, and
+    /// return the document as text.
+    fn check_xref(pdf: &[u8]) -> String {
+        // Keep every byte a single character, so that offsets still work.
+        let text: String = pdf
+            .iter()
+            .map(|&b| if b.is_ascii() { char::from(b) } else { '?' })
+            .collect();
+        let startxref = text.rfind("startxref\n").unwrap();
+        let xref: usize = text[startxref + 10..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(text[xref..].starts_with("xref\n"));
+        for (i, entry) in text[xref..].lines().skip(3).enumerate() {
+            if entry == "trailer" {
+                break;
+            }
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+        assert!(text.ends_with("%%EOF\n"));
+        text
+    }
+
+    #[test]
+    fn dots_are_stroked_with_round_caps() {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(50.0)));
+        let layer = canvas.create_layer(TestPen);
+        canvas.add_paths(
+            layer,
+            Some(Path::with_commands(vec![
+                LineCommand::MoveTo(point2(10.0, 10.0)),
+                LineCommand::LineTo(point2(10.0, 10.0)),
+            ])),
+        );
+
+        let pdf = check_xref(&canvas.create_pdf(&PdfOptions::new()));
+        assert!(pdf.contains("q\n1 J 1 j\n"));
+        assert!(pdf.contains("28.346 113.386 m\n28.346 113.386 l\nS\n"));
+    }
+
+    #[test]
+    fn combined_page() {
+        let pdf = check_xref(&canvas().create_pdf(&PdfOptions::new()));
+        assert!(pdf.contains("/Count 1 "));
+        assert!(pdf.contains("/MediaBox [0.000 0.000 283.465 141.732]"));
+        assert!(!pdf.contains("/TrimBox"));
+        // Both lines, with the y axis flipped.
+        assert!(pdf.contains("28.346 113.386 m\n56.693 113.386 l\nS\n"));
+        assert!(pdf.contains("28.346 85.039 m\n56.693 85.039 l\nS\n"));
+        // The test pen's 0.5mm nib.
+        assert!(pdf.contains("1.417 w"));
+    }
+
+    #[test]
+    fn page_per_layer() {
+        let options = PdfOptions::new().pages(PdfPages::PerLayer);
+        let pdf = check_xref(&canvas().create_pdf(&options));
+        assert!(pdf.contains("/Kids [3 0 R 5 0 R] /Count 2 "));
+        assert_eq!(pdf.matches("l\nS\n").count(), 2);
+    }
+
+    #[test]
+    fn crop_marks_enlarge_the_page() {
+        let options = PdfOptions::new().crop_marks(true);
+        let pdf = check_xref(&canvas().create_pdf(&options));
+        assert!(pdf.contains("/MediaBox [0.000 0.000 340.157 198.425]"));
+        assert!(pdf.contains("/TrimBox [28.346 28.346 311.811 170.079]"));
+        assert_eq!(pdf.matches(" l S\n").count(), 8);
+        // The paper's bottom left corner is 10mm from the page's corner.
+        assert!(pdf.contains("28.346 19.843 m 28.346 5.669 l S\n"));
+        assert!(pdf.contains("19.843 28.346 m 5.669 28.346 l S\n"));
+        // The lines move along with the paper.
+        assert!(pdf.contains("56.693 141.732 m\n85.039 141.732 l\nS\n"));
+    }
+
+    #[test]
+    fn empty_canvases_get_a_blank_page() {
+        let canvas = Canvas::new(Paper::new(Millis(100.0), Millis(50.0)));
+        let options = PdfOptions::new().pages(PdfPages::PerLayer);
+        let pdf = check_xref(&canvas.create_pdf(&options));
+        assert!(pdf.contains("/Count 1 "));
+    }
+}
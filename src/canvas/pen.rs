@@ -0,0 +1,182 @@
+//! Which pen plots which layer, and recording that in SVGs.
+
+use crate::units::Millis;
+use crate::xml;
+use failure::ResultExt;
+use penlib::Pen;
+use std::fmt;
+use svg::node::element::Element;
+use svg::node::Attributes;
+use svg::Node;
+
+/// The identity of the pen that plots a layer, so that the plot operator
+/// knows which pen to put in for which layer.
+///
+/// `Canvas::create_layer` fills this in from a `penlib::Pen`, which only
+/// knows its color and nib size, so the model defaults to the name of the
+/// pen's type. Use `Canvas::create_layer_with` to say more.
+///
+/// It is saved in SVGs' layer labels and metadata, and read back by
+/// `read_svg`.
+///
+/// # Example
+///
+/// ```
+/// use fart::canvas::PenInfo;
+/// use palette::rgb::LinSrgb;
+/// use fart::units::Millis;
+///
+/// let pen = PenInfo::new(LinSrgb::new(0.0, 0.0, 1.0), Millis(0.3))
+///     .brand("Staedtler")
+///     .model("Pigment Liner")
+///     .color_name("blue");
+/// assert_eq!(pen.to_string(), "Staedtler Pigment Liner blue 0.3mm");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PenInfo {
+    /// Who makes the pen.
+    pub brand: Option<String>,
+    /// The pen's model.
+    pub model: Option<String>,
+    /// What the pen's ink color is called.
+    pub color_name: Option<String>,
+    /// The pen's ink color.
+    pub color: palette::rgb::LinSrgb,
+    /// How wide the pen's lines are.
+    pub nib_size: Millis,
+}
+
+impl PenInfo {
+    /// Construct the identity of an unnamed pen with the given color and nib
+    /// size.
+    pub fn new(color: palette::rgb::LinSrgb, nib_size: Millis) -> PenInfo {
+        PenInfo {
+            brand: None,
+            model: None,
+            color_name: None,
+            color,
+            nib_size,
+        }
+    }
+
+    /// Construct the identity of the given pen, with its color and nib size,
+    /// and the name of its type as its model.
+    pub fn of<P>(pen: &P) -> PenInfo
+    where
+        P: Pen,
+    {
+        let type_name = std::any::type_name::<P>();
+        let type_name = type_name.split('<').next().unwrap_or(type_name);
+        let model = type_name.rsplit("::").next().unwrap_or(type_name);
+        PenInfo::new(pen.rgb_color(), Millis(P::nib_size_mm())).model(model)
+    }
+
+    /// Set who makes the pen.
+    pub fn brand<S: Into<String>>(mut self, brand: S) -> PenInfo {
+        self.brand = Some(brand.into());
+        self
+    }
+
+    /// Set the pen's model.
+    pub fn model<S: Into<String>>(mut self, model: S) -> PenInfo {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Set what the pen's ink color is called.
+    pub fn color_name<S: Into<String>>(mut self, color_name: S) -> PenInfo {
+        self.color_name = Some(color_name.into());
+        self
+    }
+
+    /// The pen's color as an SVG color.
+    pub(super) fn svg_color(&self) -> String {
+        let (r, g, b) = self.color.into_components();
+        format!("rgb({},{},{})", r * 255.0, g * 255.0, b * 255.0)
+    }
+}
+
+impl fmt::Display for PenInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in [&self.brand, &self.model, &self.color_name]
+            .iter()
+            .filter_map(|name| name.as_ref())
+        {
+            write!(f, "{} ", name)?;
+        }
+        write!(f, "{}mm", self.nib_size.0)
+    }
+}
+
+/// A `<metadata>` element listing the pens of the given layers, by the IDs of
+/// their SVG groups.
+pub(super) fn metadata<'a, I>(pens: I) -> Element
+where
+    I: IntoIterator<Item = (String, &'a PenInfo)>,
+{
+    let mut metadata = Element::new("metadata");
+    metadata.assign("id", "fart-pens");
+    for (layer, pen) in pens {
+        let mut element = Element::new("fart:pen");
+        element.assign("layer", layer);
+        for &(name, value) in &[
+            ("brand", &pen.brand),
+            ("model", &pen.model),
+            ("color-name", &pen.color_name),
+        ] {
+            if let Some(value) = value {
+                element.assign(name, xml::attribute(value));
+            }
+        }
+        element.assign("color", pen.svg_color());
+        element.assign("nib-size", pen.nib_size.0.to_string());
+        metadata.append(element);
+    }
+    metadata
+}
+
+/// Parse a `<fart:pen>` element written by `metadata` into the ID of its
+/// layer's SVG group and the pen.
+pub(super) fn parse(attributes: &Attributes) -> crate::Result<(String, PenInfo)> {
+    let get = |name: &str| {
+        attributes
+            .get(name)
+            .map(|value| xml::attribute_value(value))
+    };
+    let required =
+        |name: &str| get(name).ok_or_else(|| failure::format_err!("<fart:pen> has no {}", name));
+
+    let color = required("color")?;
+    let components: Vec<f32> = color
+        .trim()
+        .strip_prefix("rgb(")
+        .and_then(|c| c.strip_suffix(')'))
+        .ok_or_else(|| failure::format_err!("invalid pen color: {:?}", color))?
+        .split(',')
+        .map(|c| {
+            c.trim()
+                .parse::<f32>()
+                .with_context(|_| format!("invalid pen color: {:?}", color))
+        })
+        .collect::<Result<_, _>>()?;
+    if components.len() != 3 {
+        failure::bail!("invalid pen color: {:?}", color);
+    }
+    let nib_size = required("nib-size")?;
+    let nib_size = nib_size
+        .parse::<f64>()
+        .with_context(|_| format!("invalid nib size: {:?}", nib_size))?;
+
+    let pen = PenInfo {
+        brand: get("brand"),
+        model: get("model"),
+        color_name: get("color-name"),
+        color: palette::rgb::LinSrgb::new(
+            components[0] / 255.0,
+            components[1] / 255.0,
+            components[2] / 255.0,
+        ),
+        nib_size: Millis(nib_size),
+    };
+    Ok((required("layer")?, pen))
+}
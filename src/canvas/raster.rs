@@ -0,0 +1,278 @@
+//! Rasterizing canvases into images, for previews and thumbnails.
+//!
+//! This is a small software rasterizer: every straight line is drawn as a
+//! round capped stroke of the pen's width, with anti-aliased edges. It is
+//! meant to look like the plot, not to be fast.
+
+use super::{Canvas, CanvasSpace};
+use crate::units::{Inches, Millis, SvgUnit};
+use euclid::{point2, Point2D};
+use failure::ResultExt;
+use std::io::Write;
+
+type PixelPoint = Point2D<f64, PixelSpace>;
+
+/// The space of pixels in a raster, with the origin in the top left corner
+/// of the top left pixel.
+#[derive(Debug)]
+enum PixelSpace {}
+
+/// An RGB image of a rendered canvas. See `Canvas::rasterize`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Raster {
+    fn new(width: u32, height: u32) -> Raster {
+        Raster {
+            width,
+            height,
+            pixels: vec![[255; 3]; width as usize * height as usize],
+        }
+    }
+
+    /// The width of this image, in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of this image, in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the RGB color of the pixel at the given column and row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel is outside of the image.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        self.pixels[self.index(x, y)]
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Encode this image as a PNG and write it to `writer`.
+    pub fn write_png<W>(&self, writer: W) -> crate::Result<()>
+    where
+        W: Write,
+    {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .context("failed to write PNG header")?;
+        let data: Vec<u8> = self.pixels.iter().flat_map(|p| p.iter().cloned()).collect();
+        writer
+            .write_image_data(&data)
+            .context("failed to write PNG image data")?;
+        Ok(())
+    }
+
+    /// Encode this image as a PNG.
+    pub fn to_png(&self) -> crate::Result<Vec<u8>> {
+        let mut png = vec![];
+        self.write_png(&mut png)?;
+        Ok(png)
+    }
+
+    /// Draw the given line strokes with the given color.
+    ///
+    /// Every pixel is painted once, with the most coverage any of the strokes
+    /// give it, so that overlapping strokes of one pen don't get darker where
+    /// they cross.
+    fn draw_strokes(&mut self, strokes: &[(PixelPoint, PixelPoint)], radius: f64, color: [u8; 3]) {
+        let mut coverage = vec![0.0_f64; self.pixels.len()];
+        // Pixels a half pixel beyond the stroke's edge still get some of it.
+        let reach = radius + 0.5;
+        for &(a, b) in strokes {
+            let clamp = |v: f64, max: u32| v.max(0.0).min(f64::from(max)) as u32;
+            let x0 = clamp((a.x.min(b.x) - reach).floor(), self.width);
+            let x1 = clamp((a.x.max(b.x) + reach).ceil(), self.width);
+            let y0 = clamp((a.y.min(b.y) - reach).floor(), self.height);
+            let y1 = clamp((a.y.max(b.y) + reach).ceil(), self.height);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let center = point2(f64::from(x) + 0.5, f64::from(y) + 0.5);
+                    let c = (reach - distance_to_segment(center, a, b)).clamp(0.0, 1.0);
+                    let i = self.index(x, y);
+                    coverage[i] = coverage[i].max(c);
+                }
+            }
+        }
+
+        for (pixel, c) in self.pixels.iter_mut().zip(coverage) {
+            if c > 0.0 {
+                for (channel, &target) in pixel.iter_mut().zip(&color) {
+                    let blended = f64::from(*channel) * (1.0 - c) + f64::from(target) * c;
+                    *channel = blended.round() as u8;
+                }
+            }
+        }
+    }
+}
+
+fn distance_to_segment(p: PixelPoint, a: PixelPoint, b: PixelPoint) -> f64 {
+    let ab = b - a;
+    let t = if ab.square_length() == 0.0 {
+        0.0
+    } else {
+        ((p - a).dot(ab) / ab.square_length()).clamp(0.0, 1.0)
+    };
+    (p - a.lerp(b, t)).length()
+}
+
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    /// Render this canvas into an image with the given resolution, in dots
+    /// per inch.
+    ///
+    /// The image covers the whole paper, which is white. Every layer is drawn
+    /// on top of the previous ones in its pen's color, with lines as wide as
+    /// the pen's nib. Dots are drawn as round spots of the nib's size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dpi` is not positive.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn thumbnail(canvas: &fart::canvas::Canvas<fart::units::Millis>) -> fart::Result<()> {
+    /// let png = canvas.rasterize(96.0).to_png()?;
+    /// std::fs::write("thumbnail.png", png)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rasterize(&self, dpi: f64) -> Raster {
+        assert!(dpi > 0.0, "DPI must be positive");
+        let pixels_per_mm = dpi / Millis::from(Inches(1.0)).0;
+        let units_per_mm: f64 = Unit::from(Millis(1.0)).into();
+        let scale = pixels_per_mm / units_per_mm;
+        let size = |length: Unit| {
+            let length: f64 = length.into();
+            (length * scale).round().max(1.0) as u32
+        };
+        let mut raster = Raster::new(size(self.paper.width), size(self.paper.height));
+
+        let to_pixels = |p: Point2D<f64, CanvasSpace>| point2(p.x * scale, p.y * scale);
+        let tolerance = self.flattening_tolerance();
+        for (_, layer) in self.visible_layers() {
+            let strokes: Vec<_> = layer
+                .paths
+                .iter()
+                .flat_map(|path| path.flatten(tolerance))
+                .flat_map(|polyline| {
+                    let vertices: Vec<_> =
+                        polyline.vertices().iter().map(|&v| to_pixels(v)).collect();
+                    vertices
+                        .windows(2)
+                        .map(|w| (w[0], w[1]))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            if strokes.is_empty() {
+                continue;
+            }
+
+            let (r, g, b) = layer.pen.color.into_components();
+            let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+            raster.draw_strokes(
+                &strokes,
+                layer.pen.nib_size.0 * pixels_per_mm / 2.0,
+                [channel(r), channel(g), channel(b)],
+            );
+        }
+        raster
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::tests::TestPen;
+    use crate::path::{LineCommand, Path};
+    use crate::units::Paper;
+
+    /// A 20mm by 10mm canvas with a horizontal line across its middle.
+    fn canvas() -> Canvas<Millis> {
+        let mut canvas = Canvas::new(Paper::new(Millis(20.0), Millis(10.0)));
+        let layer = canvas.create_layer(TestPen);
+        canvas.add_paths(
+            layer,
+            Some(Path::with_commands(vec![
+                LineCommand::MoveTo(point2(5.0, 5.0)),
+                LineCommand::LineTo(point2(15.0, 5.0)),
+            ])),
+        );
+        canvas
+    }
+
+    #[test]
+    fn strokes_are_as_wide_as_the_nib() {
+        // 254 DPI is 10 pixels per millimeter, so the 0.5mm nib of the test
+        // pen is 5 pixels wide.
+        let raster = canvas().rasterize(254.0);
+        assert_eq!((raster.width(), raster.height()), (200, 100));
+
+        assert_eq!(raster.pixel(100, 50), [0, 0, 0]);
+        assert_eq!(raster.pixel(100, 10), [255, 255, 255]);
+        assert_eq!(raster.pixel(10, 50), [255, 255, 255]);
+
+        let ink: f64 = (0..raster.height())
+            .map(|y| f64::from(255 - raster.pixel(100, y)[0]) / 255.0)
+            .sum();
+        assert!((ink - 5.0).abs() < 0.01, "{} pixels of ink", ink);
+
+        // The round caps stick out half the nib size past the ends.
+        assert!(raster.pixel(48, 50)[0] < 128);
+        assert_eq!(raster.pixel(45, 50), [255, 255, 255]);
+    }
+
+    #[test]
+    fn dots_are_as_wide_as_the_nib() {
+        let mut canvas = Canvas::new(Paper::new(Millis(20.0), Millis(10.0)));
+        let layer = canvas.create_layer(TestPen);
+        canvas.add_paths(
+            layer,
+            Some(Path::with_commands(vec![
+                LineCommand::MoveTo(point2(10.0, 5.0)),
+                LineCommand::LineTo(point2(10.0, 5.0)),
+            ])),
+        );
+
+        let raster = canvas.rasterize(254.0);
+        assert_eq!(raster.pixel(100, 50), [0, 0, 0]);
+        assert!(raster.pixel(101, 48)[0] < 128);
+        assert_eq!(raster.pixel(103, 50), [255, 255, 255]);
+        assert_eq!(raster.pixel(100, 46), [255, 255, 255]);
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let raster = canvas().rasterize(96.0);
+        let png = raster.to_png().unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let decoder = png::Decoder::new(&png[..]);
+        let (info, mut reader) = decoder.read_info().unwrap();
+        assert_eq!((info.width, info.height), (raster.width(), raster.height()));
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        let expected: Vec<u8> = raster
+            .pixels
+            .iter()
+            .flat_map(|p| p.iter().cloned())
+            .collect();
+        assert_eq!(data, expected);
+    }
+}
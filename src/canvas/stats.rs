@@ -0,0 +1,482 @@
+//! Statistics about plotting a canvas, and an estimate of how long it takes.
+
+use super::{travel, Canvas, CanvasSpace, LayerKey};
+use crate::units::{Millis, SvgUnit};
+use euclid::{point2, Point2D};
+use fart_aabb::Aabb;
+use std::fmt;
+use std::time::Duration;
+
+/// How fast a plotter moves, for estimating plot durations. See
+/// `Canvas::set_plot_speeds`.
+///
+/// The plotter is modelled as accelerating and braking at a constant rate:
+/// it slows down for sharp corners, comes to a stop at the end of every
+/// stroke, and waits for the pen to be lifted and lowered between strokes.
+///
+/// # Example
+///
+/// ```
+/// use fart::canvas::PlotSpeeds;
+/// use std::time::Duration;
+///
+/// let speeds = PlotSpeeds::new()
+///     .draw_speed(40.0)
+///     .pen_lift_time(Duration::from_millis(150));
+/// # let _ = speeds;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlotSpeeds {
+    draw_speed: f64,
+    travel_speed: f64,
+    acceleration: f64,
+    pen_lift_time: Duration,
+}
+
+impl Default for PlotSpeeds {
+    fn default() -> PlotSpeeds {
+        PlotSpeeds {
+            draw_speed: 25.0,
+            travel_speed: 75.0,
+            acceleration: 1000.0,
+            pen_lift_time: Duration::from_millis(200),
+        }
+    }
+}
+
+impl PlotSpeeds {
+    /// Construct the default speeds.
+    ///
+    /// By default, the pen draws at 25mm/s, travels at 75mm/s while it is up,
+    /// accelerates at 1000mm/s², and takes 0.2s to lift and lower again.
+    pub fn new() -> PlotSpeeds {
+        PlotSpeeds::default()
+    }
+
+    /// Set the top speed while drawing, in millimeters per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is not positive.
+    pub fn draw_speed(mut self, speed: f64) -> PlotSpeeds {
+        assert!(speed > 0.0, "draw speed must be positive");
+        self.draw_speed = speed;
+        self
+    }
+
+    /// Set the top speed while the pen is up, in millimeters per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is not positive.
+    pub fn travel_speed(mut self, speed: f64) -> PlotSpeeds {
+        assert!(speed > 0.0, "travel speed must be positive");
+        self.travel_speed = speed;
+        self
+    }
+
+    /// Set how fast the plotter speeds up and slows down, in millimeters per
+    /// second squared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `acceleration` is not positive.
+    pub fn acceleration(mut self, acceleration: f64) -> PlotSpeeds {
+        assert!(acceleration > 0.0, "acceleration must be positive");
+        self.acceleration = acceleration;
+        self
+    }
+
+    /// Set how long it takes to lift the pen after a stroke and lower it
+    /// again before the next one.
+    pub fn pen_lift_time(mut self, time: Duration) -> PlotSpeeds {
+        self.pen_lift_time = time;
+        self
+    }
+}
+
+/// Statistics about plotting one layer. See `Canvas::plot_stats`.
+#[derive(Clone, Debug)]
+pub struct LayerStats {
+    /// The layer.
+    pub layer: LayerKey,
+    /// The layer's number, as used in SVG group IDs and file names.
+    pub number: u64,
+    /// The number of paths that draw anything.
+    pub paths: usize,
+    /// How many times the pen is lifted, which is once for every stroke.
+    pub pen_lifts: usize,
+    /// The distance drawn with the pen down, in the canvas's units.
+    pub pen_down_distance: f64,
+    /// The distance travelled with the pen up, starting from the paper's top
+    /// left corner, in the canvas's units.
+    pub pen_up_distance: f64,
+    /// The bounding box of everything drawn, if anything is.
+    pub bounds: Option<Aabb<f64, CanvasSpace>>,
+    /// The estimated time to plot the layer.
+    pub duration: Duration,
+    suffix: &'static str,
+}
+
+impl fmt::Display for LayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "layer {}: {} paths, {} pen lifts, {:.1}{} pen down, {:.1}{} pen up, ",
+            self.number,
+            self.paths,
+            self.pen_lifts,
+            self.pen_down_distance,
+            self.suffix,
+            self.pen_up_distance,
+            self.suffix,
+        )?;
+        if let Some(bounds) = &self.bounds {
+            write!(
+                f,
+                "bounds ({:.1}, {:.1})-({:.1}, {:.1}), ",
+                bounds.min().x,
+                bounds.min().y,
+                bounds.max().x,
+                bounds.max().y,
+            )?;
+        }
+        write!(f, "{}", FormatDuration(self.duration))
+    }
+}
+
+/// Statistics about plotting a whole canvas, layer by layer. See
+/// `Canvas::plot_stats`.
+///
+/// Its `Display` implementation is a small report with a line per layer and
+/// a line of totals.
+#[derive(Clone, Debug)]
+pub struct PlotStats {
+    /// The statistics of every layer that isn't hidden, from the bottom layer
+    /// to the top one.
+    pub layers: Vec<LayerStats>,
+    suffix: &'static str,
+}
+
+impl PlotStats {
+    /// The number of paths that draw anything, on all layers.
+    pub fn paths(&self) -> usize {
+        self.layers.iter().map(|l| l.paths).sum()
+    }
+
+    /// How many times the pen is lifted, on all layers.
+    pub fn pen_lifts(&self) -> usize {
+        self.layers.iter().map(|l| l.pen_lifts).sum()
+    }
+
+    /// The distance drawn with the pen down, on all layers.
+    pub fn pen_down_distance(&self) -> f64 {
+        self.layers
+            .iter()
+            .fold(0.0, |sum, l| sum + l.pen_down_distance)
+    }
+
+    /// The distance travelled with the pen up, on all layers.
+    pub fn pen_up_distance(&self) -> f64 {
+        self.layers
+            .iter()
+            .fold(0.0, |sum, l| sum + l.pen_up_distance)
+    }
+
+    /// The estimated time to plot all layers, not counting pen swaps.
+    pub fn duration(&self) -> Duration {
+        self.layers.iter().map(|l| l.duration).sum()
+    }
+}
+
+impl fmt::Display for PlotStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for layer in &self.layers {
+            writeln!(f, "{}", layer)?;
+        }
+        write!(
+            f,
+            "total: {} paths, {} pen lifts, {:.1}{} pen down, {:.1}{} pen up, {}",
+            self.paths(),
+            self.pen_lifts(),
+            self.pen_down_distance(),
+            self.suffix,
+            self.pen_up_distance(),
+            self.suffix,
+            FormatDuration(self.duration()),
+        )
+    }
+}
+
+/// Formats durations as hours, minutes, and seconds.
+struct FormatDuration(Duration);
+
+impl fmt::Display for FormatDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self.0.as_secs_f64().round() as u64;
+        write!(
+            f,
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+impl<Unit> Canvas<Unit>
+where
+    Unit: SvgUnit + From<Millis>,
+{
+    /// Set the plotter speeds that plot durations are estimated with. See
+    /// `Canvas::plot_stats`.
+    pub fn set_plot_speeds(&mut self, speeds: PlotSpeeds) {
+        self.plot_speeds = speeds;
+    }
+
+    /// Get statistics about plotting this canvas, and an estimate of how long
+    /// that takes with the speeds set by `Canvas::set_plot_speeds`.
+    ///
+    /// Every layer that isn't hidden is plotted on its own, from the bottom
+    /// layer to the top one, starting with the pen at the paper's top left
+    /// corner. Curves are measured along their flattened polylines.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::canvas::Canvas;
+    /// use fart::units::{Millis, Paper};
+    ///
+    /// let canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+    /// let stats = canvas.plot_stats();
+    /// assert_eq!(stats.paths(), 0);
+    /// println!("{}", stats);
+    /// ```
+    pub fn plot_stats(&self) -> PlotStats {
+        let units_per_mm: f64 = Unit::from(Millis(1.0)).into();
+        let speeds = &self.plot_speeds;
+        let home = point2(0.0, 0.0);
+        let tolerance = self.flattening_tolerance();
+
+        let layers = self
+            .visible_layers()
+            .map(|(key, layer)| {
+                let paths: Vec<_> = layer
+                    .paths
+                    .iter()
+                    .filter(|p| p.start().is_some())
+                    .cloned()
+                    .collect();
+                // Dots flatten to polylines of their own, so each of them
+                // counts as a pen lift, and the travel to it is included.
+                let polylines: Vec<_> = paths.iter().flat_map(|p| p.flatten(tolerance)).collect();
+
+                let pen_down_distance = polylines
+                    .iter()
+                    .fold(0.0, |sum, p| sum + super::polyline_length(p));
+                let pen_up_distance = travel::travel_distance(home, &paths);
+
+                let mut seconds = 0.0;
+                let mut position = home;
+                for polyline in &polylines {
+                    let vertices = polyline.vertices();
+                    seconds += move_time(
+                        &[position, vertices[0]],
+                        speeds.travel_speed * units_per_mm,
+                        speeds.acceleration * units_per_mm,
+                    );
+                    seconds += move_time(
+                        vertices,
+                        speeds.draw_speed * units_per_mm,
+                        speeds.acceleration * units_per_mm,
+                    );
+                    position = *vertices.last().unwrap();
+                }
+                let duration = Duration::from_secs_f64(seconds)
+                    + speeds.pen_lift_time * polylines.len() as u32;
+
+                LayerStats {
+                    layer: key,
+                    number: layer.id.0 + 1,
+                    paths: paths.len(),
+                    pen_lifts: polylines.len(),
+                    pen_down_distance,
+                    pen_up_distance,
+                    bounds: super::polylines_aabb(&polylines),
+                    duration,
+                    suffix: Unit::SUFFIX,
+                }
+            })
+            .collect();
+
+        PlotStats {
+            layers,
+            suffix: Unit::SUFFIX,
+        }
+    }
+}
+
+/// Get the time, in seconds, to move through the given vertices, starting and
+/// stopping at rest, with the given top speed and acceleration.
+///
+/// The speed through every corner is limited by how sharp it is: the plotter
+/// can go straight on at full speed, has to stop for a right angle or
+/// anything sharper, and slows down proportionally in between. Within these
+/// limits, and the limits of accelerating and braking from one corner to the
+/// next, every segment is driven as fast as possible.
+fn move_time(vertices: &[Point2D<f64, CanvasSpace>], speed: f64, acceleration: f64) -> f64 {
+    let segments: Vec<_> = vertices
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|v| v.length() > 0.0)
+        .collect();
+    if segments.is_empty() {
+        return 0.0;
+    }
+
+    // The top speed at every corner, including the start and end.
+    let mut corners = vec![0.0; segments.len() + 1];
+    for (i, w) in segments.windows(2).enumerate() {
+        let cos = w[0].dot(w[1]) / (w[0].length() * w[1].length());
+        corners[i + 1] = speed * cos.max(0.0);
+    }
+    for i in 0..segments.len() {
+        let reachable =
+            (corners[i] * corners[i] + 2.0 * acceleration * segments[i].length()).sqrt();
+        corners[i + 1] = f64::min(corners[i + 1], reachable);
+    }
+    for i in (0..segments.len()).rev() {
+        let reachable =
+            (corners[i + 1] * corners[i + 1] + 2.0 * acceleration * segments[i].length()).sqrt();
+        corners[i] = f64::min(corners[i], reachable);
+    }
+
+    segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            let (entry, exit, distance) = (corners[i], corners[i + 1], segment.length());
+            // The fastest speed reached when accelerating as long as there
+            // is still room to brake.
+            let peak = ((2.0 * acceleration * distance + entry * entry + exit * exit) / 2.0).sqrt();
+            if peak <= speed {
+                (2.0 * peak - entry - exit) / acceleration
+            } else {
+                let ramps =
+                    (2.0 * speed * speed - entry * entry - exit * exit) / (2.0 * acceleration);
+                (2.0 * speed - entry - exit) / acceleration + (distance - ramps) / speed
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::tests::TestPen;
+    use crate::path::{LineCommand, Path};
+    use crate::units::Paper;
+
+    fn line(from: (f64, f64), to: (f64, f64)) -> Path<f64, CanvasSpace> {
+        Path::with_commands(vec![
+            LineCommand::MoveTo(point2(from.0, from.1)),
+            LineCommand::LineTo(point2(to.0, to.1)),
+        ])
+    }
+
+    #[test]
+    fn distances_lifts_and_bounds_per_layer() {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let first = canvas.create_layer(TestPen);
+        let second = canvas.create_layer(TestPen);
+        canvas.add_paths(first, Some(line((30.0, 40.0), (30.0, 50.0))));
+        canvas.add_paths(first, Some(line((30.0, 50.0), (40.0, 50.0))));
+        canvas.add_paths(first, Some(Path::new()));
+        canvas.add_paths(second, Some(line((3.0, 4.0), (3.0, 14.0))));
+
+        let stats = canvas.plot_stats();
+        assert_eq!(stats.layers.len(), 2);
+        let layer = &stats.layers[0];
+        assert_eq!((layer.layer, layer.number), (first, 1));
+        assert_eq!((layer.paths, layer.pen_lifts), (2, 2));
+        assert_eq!(layer.pen_down_distance, 20.0);
+        assert_eq!(layer.pen_up_distance, 50.0);
+        let bounds = layer.bounds.as_ref().unwrap();
+        assert_eq!(
+            (bounds.min(), bounds.max()),
+            (point2(30.0, 40.0), point2(40.0, 50.0))
+        );
+
+        assert_eq!(stats.paths(), 3);
+        assert_eq!(stats.pen_down_distance(), 30.0);
+        assert_eq!(stats.pen_up_distance(), 55.0);
+        assert_eq!(stats.duration(), layer.duration + stats.layers[1].duration);
+        assert_eq!(stats.to_string().lines().count(), 3);
+    }
+
+    #[test]
+    fn dots_are_pen_lifts() {
+        use crate::path::{ToPaths, ToPathsExt};
+
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let layer = canvas.create_layer(TestPen);
+        canvas.add_paths(
+            layer,
+            line((30.0, 40.0), (32.0, 40.0)).dotted(1.0).to_paths(),
+        );
+
+        let stats = canvas.plot_stats();
+        let layer = &stats.layers[0];
+        assert_eq!((layer.paths, layer.pen_lifts), (3, 3));
+        assert_eq!(layer.pen_down_distance, 0.0);
+        assert_eq!(layer.pen_up_distance, 52.0);
+        assert!(layer.duration >= canvas.plot_speeds.pen_lift_time * 3);
+    }
+
+    #[test]
+    fn stats_are_embedded_in_svg_metadata() {
+        let mut canvas = Canvas::new(Paper::new(Millis(100.0), Millis(100.0)));
+        let layer = canvas.create_layer(TestPen);
+        canvas.add_paths(layer, Some(line((3.0, 4.0), (3.0, 14.0))));
+
+        let options = crate::canvas::SvgOptions::new().plot_stats(true);
+        let doc = canvas.create_svg_with(&options).to_string();
+        assert!(doc.contains("<metadata id=\"fart-plot-stats\">"));
+        assert!(doc.contains("total: 1 paths, 1 pen lifts, 10.0mm pen down, 5.0mm pen up"));
+        let (_, layer_doc) = &canvas.create_layer_svgs_with(&options)[0];
+        assert!(layer_doc.to_string().contains("layer 1: 1 paths"));
+        assert!(!canvas.create_svg().to_string().contains("fart-plot-stats"));
+    }
+
+    #[test]
+    fn long_moves_are_dominated_by_the_top_speed() {
+        let mut canvas = Canvas::new(Paper::new(Millis(1000.0), Millis(1000.0)));
+        canvas.set_plot_speeds(
+            PlotSpeeds::new()
+                .draw_speed(10.0)
+                .travel_speed(100.0)
+                .acceleration(100.0)
+                .pen_lift_time(Duration::from_secs(1)),
+        );
+        let layer = canvas.create_layer(TestPen);
+        canvas.add_paths(layer, Some(line((0.0, 0.0), (0.0, 1000.0))));
+
+        // 0.1s to speed up and 0.1s to slow down, over 1mm, and then 99.9s
+        // at full speed, plus a second for lifting the pen.
+        let duration = canvas.plot_stats().duration().as_secs_f64();
+        assert!((duration - 101.1).abs() < 1e-6, "{}", duration);
+    }
+
+    #[test]
+    fn corners_slow_the_pen_down() {
+        let straight = [point2(0.0, 0.0), point2(50.0, 0.0), point2(100.0, 0.0)];
+        let square = [point2(0.0, 0.0), point2(50.0, 0.0), point2(50.0, 50.0)];
+        let time = |vertices: &[Point2D<f64, CanvasSpace>]| move_time(vertices, 10.0, 100.0);
+        assert!((time(&straight) - 10.1).abs() < 1e-9);
+        assert!((time(&square) - 2.0 * 5.1).abs() < 1e-9);
+
+        // Short moves never reach the top speed.
+        let short = [point2(0.0, 0.0), point2(1.0, 0.0)];
+        assert!((time(&short) - 0.2).abs() < 1e-9);
+    }
+}
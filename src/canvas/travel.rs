@@ -0,0 +1,393 @@
+//! Ordering paths to minimize how far the pen travels while it is up.
+
+use super::CanvasSpace;
+use crate::path::Path;
+use euclid::Point2D;
+use std::collections::HashMap;
+
+type Point = Point2D<f64, CanvasSpace>;
+
+/// The path ends in each cell of an `EndGrid`: where each end is, the index
+/// of its stop, and whether it is the stop's end rather than its start.
+type Cells = HashMap<(i64, i64), Vec<(Point, usize, bool)>>;
+
+/// The maximum number of 2-opt passes over the whole order. Each pass is
+/// quadratic in the number of paths, and almost all of the improvement
+/// happens in the first few passes.
+const MAX_TWO_OPT_PASSES: usize = 16;
+
+/// The most paths that 2-opt refines. Beyond this, like for the thousands of
+/// strokes of a hatched layer, its quadratic passes take too long, and the
+/// greedy order is kept.
+const MAX_TWO_OPT_PATHS: usize = 1_000;
+
+/// Get the distance that the pen travels while it is up, when starting from
+/// `home` and drawing the given paths in order.
+pub(crate) fn travel_distance(home: Point, paths: &[Path<f64, CanvasSpace>]) -> f64 {
+    let mut position = home;
+    let mut distance = 0.0;
+    for path in paths {
+        for subpath in path.subpaths() {
+            distance += (subpath.start - position).length();
+            position = subpath.segments.last().unwrap().to();
+        }
+    }
+    distance
+}
+
+/// Reorder, and reverse where it helps, the given paths to minimize pen-up
+/// travel when starting from `home`.
+///
+/// Builds a tour by greedily drawing the nearest path next, and then refines
+/// it with 2-opt moves if there are at most `MAX_TWO_OPT_PATHS` paths. Paths
+/// that don't draw anything are dropped.
+pub(crate) fn optimize(
+    home: Point,
+    paths: Vec<Path<f64, CanvasSpace>>,
+) -> Vec<Path<f64, CanvasSpace>> {
+    let stops: Vec<Stop> = paths
+        .into_iter()
+        .filter_map(|path| {
+            Some(Stop {
+                start: path.start()?,
+                end: path.end()?,
+                path,
+                reversed: false,
+            })
+        })
+        .collect();
+
+    let mut order = nearest_neighbour(home, stops);
+    if order.len() <= MAX_TWO_OPT_PATHS {
+        two_opt(home, &mut order);
+    }
+
+    order
+        .into_iter()
+        .map(|stop| {
+            if stop.reversed {
+                stop.path.reversed()
+            } else {
+                stop.path
+            }
+        })
+        .collect()
+}
+
+/// A path in the tour, along with which way around it is drawn.
+struct Stop {
+    path: Path<f64, CanvasSpace>,
+    start: Point,
+    end: Point,
+    reversed: bool,
+}
+
+impl Stop {
+    fn entry(&self) -> Point {
+        if self.reversed {
+            self.end
+        } else {
+            self.start
+        }
+    }
+
+    fn exit(&self) -> Point {
+        if self.reversed {
+            self.start
+        } else {
+            self.end
+        }
+    }
+}
+
+/// Order the stops by repeatedly drawing the one with the nearest end next,
+/// entering it from that end.
+fn nearest_neighbour(home: Point, stops: Vec<Stop>) -> Vec<Stop> {
+    let mut ends = EndGrid::new(&stops);
+    let mut remaining: Vec<_> = stops.into_iter().map(Some).collect();
+    let mut order = Vec::with_capacity(remaining.len());
+    let mut position = home;
+    while let Some((i, reversed)) = ends.nearest(position) {
+        let mut stop = remaining[i].take().unwrap();
+        ends.remove(i, &stop);
+        stop.reversed = reversed;
+        position = stop.exit();
+        order.push(stop);
+    }
+    order
+}
+
+/// A spatial hash of the ends of the stops that aren't in the tour yet, with
+/// cells sized so that there are about as many cells as stops.
+struct EndGrid {
+    size: f64,
+    cells: Cells,
+    /// The lowest and highest cells that can hold any ends.
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl EndGrid {
+    fn new(stops: &[Stop]) -> EndGrid {
+        let mut grid = EndGrid {
+            size: 1.0,
+            cells: HashMap::new(),
+            min: (0, 0),
+            max: (0, 0),
+        };
+        let first = match stops.first() {
+            Some(stop) => stop.start,
+            None => return grid,
+        };
+
+        let (mut low, mut high) = (first, first);
+        for stop in stops {
+            low = low.min(stop.start).min(stop.end);
+            high = high.max(stop.start).max(stop.end);
+        }
+        let (width, height) = (high.x - low.x, high.y - low.y);
+        let n = stops.len() as f64;
+        if width > 0.0 && height > 0.0 {
+            grid.size = (width * height / n).sqrt();
+        } else if width > 0.0 || height > 0.0 {
+            // The ends are all on one line.
+            grid.size = width.max(height) / n;
+        }
+        grid.min = grid.cell(low);
+        grid.max = grid.cell(high);
+
+        for (i, stop) in stops.iter().enumerate() {
+            grid.cells
+                .entry(grid.cell(stop.start))
+                .or_default()
+                .push((stop.start, i, false));
+            grid.cells
+                .entry(grid.cell(stop.end))
+                .or_default()
+                .push((stop.end, i, true));
+        }
+        grid
+    }
+
+    fn cell(&self, p: Point) -> (i64, i64) {
+        (
+            (p.x / self.size).floor() as i64,
+            (p.y / self.size).floor() as i64,
+        )
+    }
+
+    fn remove(&mut self, i: usize, stop: &Stop) {
+        for &p in &[stop.start, stop.end] {
+            let cell = self.cell(p);
+            if let Some(ends) = self.cells.get_mut(&cell) {
+                ends.retain(|&(_, j, _)| j != i);
+                if ends.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    /// Find the stop with the end nearest to `p`, and whether that end is the
+    /// stop's end rather than its start. Ties go to the earliest stop, and to
+    /// starts over ends.
+    fn nearest(&self, p: Point) -> Option<(usize, bool)> {
+        if self.cells.is_empty() {
+            return None;
+        }
+
+        // Search rings of cells around `p`'s cell, until the nearest end found
+        // is nearer than anything in the next ring could be.
+        let (cx, cy) = self.cell(p);
+        let last_ring = (cx - self.min.0)
+            .max(self.max.0 - cx)
+            .max(cy - self.min.1)
+            .max(self.max.1 - cy)
+            .max(0);
+        let mut best: Option<(f64, usize, bool)> = None;
+        for ring in 0..=last_ring {
+            let mut visit = |x: i64, y: i64| {
+                for &(q, i, is_end) in self.cells.get(&(x, y)).into_iter().flatten() {
+                    let candidate = ((q - p).square_length(), i, is_end);
+                    match best {
+                        Some(b) if b <= candidate => {}
+                        _ => best = Some(candidate),
+                    }
+                }
+            };
+            for x in cx - ring..=cx + ring {
+                visit(x, cy - ring);
+                if ring > 0 {
+                    visit(x, cy + ring);
+                }
+            }
+            for y in cy - ring + 1..cy + ring {
+                visit(cx - ring, y);
+                visit(cx + ring, y);
+            }
+
+            let reach = ring as f64 * self.size;
+            if let Some((distance, _, _)) = best {
+                if distance <= reach * reach {
+                    break;
+                }
+            }
+        }
+        best.map(|(_, i, is_end)| (i, is_end))
+    }
+}
+
+/// Repeatedly reverse runs of the tour when that makes it shorter.
+///
+/// Reversing the run `i..=j` draws those paths in the opposite order and each
+/// in the opposite direction, so only the travel into `i` and out of `j`
+/// changes.
+fn two_opt(home: Point, order: &mut [Stop]) {
+    let n = order.len();
+    for _ in 0..MAX_TWO_OPT_PASSES {
+        let mut improved = false;
+        for i in 0..n {
+            let before = if i == 0 { home } else { order[i - 1].exit() };
+            for j in i + 1..n {
+                let after = order.get(j + 1).map(|s| s.entry());
+                let distance = |a: Point, b: Option<Point>| b.map_or(0.0, |b| (b - a).length());
+
+                let current =
+                    (order[i].entry() - before).length() + distance(order[j].exit(), after);
+                let swapped =
+                    (order[j].exit() - before).length() + distance(order[i].entry(), after);
+                if swapped < current - 1e-9 {
+                    order[i..=j].reverse();
+                    for stop in &mut order[i..=j] {
+                        stop.reversed = !stop.reversed;
+                    }
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::LineCommand;
+    use euclid::point2;
+
+    fn line(from: (f64, f64), to: (f64, f64)) -> Path<f64, CanvasSpace> {
+        Path::with_commands(vec![
+            LineCommand::MoveTo(point2(from.0, from.1)),
+            LineCommand::LineTo(point2(to.0, to.1)),
+        ])
+    }
+
+    #[test]
+    fn travel_is_measured_between_sub_paths() {
+        let paths = vec![line((1.0, 0.0), (2.0, 0.0)), line((2.0, 3.0), (5.0, 3.0))];
+        assert_eq!(travel_distance(point2(0.0, 0.0), &paths), 4.0);
+    }
+
+    #[test]
+    fn paths_are_reordered_and_reversed() {
+        // Lines along a row, each going backwards, in a scrambled order.
+        let paths = vec![
+            line((3.0, 0.0), (2.0, 0.0)),
+            line((7.0, 0.0), (6.0, 0.0)),
+            line((1.0, 0.0), (0.0, 0.0)),
+            line((5.0, 0.0), (4.0, 0.0)),
+        ];
+        let home = point2(0.0, 0.0);
+        let optimized = optimize(home, paths);
+
+        assert_eq!(travel_distance(home, &optimized), 3.0);
+        let starts: Vec<_> = optimized.iter().map(|p| p.start().unwrap().x).collect();
+        assert_eq!(starts, vec![0.0, 2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn two_opt_improves_on_the_greedy_order() {
+        // Scatter lines deterministically across the page.
+        let mut seed = 1_u64;
+        let mut random = || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (seed >> 33) as f64 / f64::from(1_u32 << 31) * 100.0
+        };
+        let paths: Vec<_> = (0..50)
+            .map(|_| line((random(), random()), (random(), random())))
+            .collect();
+        let home = point2(0.0, 0.0);
+
+        let stops = paths
+            .iter()
+            .map(|path| Stop {
+                start: path.start().unwrap(),
+                end: path.end().unwrap(),
+                path: path.clone(),
+                reversed: false,
+            })
+            .collect();
+        let greedy: Vec<_> = nearest_neighbour(home, stops)
+            .into_iter()
+            .map(|s| {
+                if s.reversed {
+                    s.path.reversed()
+                } else {
+                    s.path
+                }
+            })
+            .collect();
+
+        let optimized = optimize(home, paths.clone());
+        assert_eq!(optimized.len(), paths.len());
+        assert!(travel_distance(home, &optimized) < travel_distance(home, &greedy));
+        assert!(travel_distance(home, &greedy) < travel_distance(home, &paths));
+    }
+
+    #[test]
+    fn the_nearest_path_is_found_across_empty_cells() {
+        let mut seed = 7_u64;
+        let mut random = || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (seed >> 33) as f64 / f64::from(1_u32 << 31) * 10.0
+        };
+        // Clusters of lines far apart, with empty cells between them.
+        let mut paths = vec![];
+        for &offset in &[0.0, 500.0, 1000.0] {
+            for _ in 0..100 {
+                let from = (offset + random(), offset + random());
+                let to = (offset + random(), offset + random());
+                paths.push(line(from, to));
+            }
+        }
+        let stops = || -> Vec<Stop> {
+            paths
+                .iter()
+                .map(|path| Stop {
+                    start: path.start().unwrap(),
+                    end: path.end().unwrap(),
+                    path: path.clone(),
+                    reversed: false,
+                })
+                .collect()
+        };
+
+        // Check every step against looking through all of the paths.
+        let home = point2(2000.0, 0.0);
+        let mut remaining = stops();
+        let mut position = home;
+        for stop in nearest_neighbour(home, stops()) {
+            let distance = |p: Point| (p - position).length();
+            let nearest = remaining
+                .iter()
+                .map(|s| distance(s.start).min(distance(s.end)))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(distance(stop.entry()), nearest);
+            remaining.retain(|s| s.start != stop.start || s.end != stop.end);
+            position = stop.exit();
+        }
+        assert!(remaining.is_empty());
+    }
+}
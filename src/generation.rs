@@ -0,0 +1,293 @@
+//! Recording how an SVG was generated, so that it can be generated again.
+
+use crate::xml;
+use failure::ResultExt;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use svg::node::element::tag::Type;
+use svg::node::element::Element;
+use svg::node::Text;
+use svg::parser::{Event, Parser};
+use svg::Node;
+
+/// The XML namespace of fart's own SVG metadata.
+pub(crate) const NAMESPACE: &str = "https://github.com/fitzgen/fart";
+
+lazy_static::lazy_static! {
+    static ref USER_CONSTS: Mutex<Vec<UserConst>> = Mutex::new(vec![]);
+}
+
+/// Record the value of a `fart::user_const!` when it is first used.
+#[doc(hidden)]
+pub fn record_user_const(name: &str, ty: &str, value: String) {
+    USER_CONSTS.lock().unwrap().push(UserConst {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        value,
+    });
+}
+
+/// A `fart::user_const!` and the value it had.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserConst {
+    /// The constant's name.
+    pub name: String,
+    /// The constant's type, as written in the sketch.
+    pub ty: String,
+    /// The constant's value, as given in its `FART_USER_CONST_*` environment
+    /// variable, or else formatted with `Debug`.
+    ///
+    /// Whitespace around the value is not kept in SVGs.
+    pub value: String,
+}
+
+/// How an SVG was generated by `fart::generate`: everything needed to
+/// generate it again.
+///
+/// `fart::generate` embeds this in a `<metadata>` element of every SVG that
+/// it saves, and `Generation::read_svg` reads it back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Generation {
+    /// The version of `fart` that generated the SVG.
+    pub fart_version: String,
+    /// The sketch's crate name and version, when it was run by `cargo`.
+    pub sketch: Option<(String, String)>,
+    /// The seed of `fart::rng()`.
+    pub seed: u64,
+    /// Every user constant that was used, in the order they were first used,
+    /// including `RNG_SEED`.
+    pub user_consts: Vec<UserConst>,
+    /// The commit that the sketch's git repository was at, if it is in one.
+    pub git_commit: Option<String>,
+    /// Whether the sketch's git repository had uncommitted changes, which
+    /// the commit doesn't include.
+    pub git_dirty: bool,
+    /// When the SVG was generated, in seconds since the Unix epoch.
+    pub generated_at: u64,
+    /// How long generating the SVG took.
+    pub duration: Duration,
+}
+
+impl Generation {
+    /// Record how the running sketch generated its output, which took the
+    /// given time.
+    pub(crate) fn current(duration: Duration) -> Generation {
+        let seed = crate::thread_rng::seed();
+        let sketch = match (
+            std::env::var("CARGO_PKG_NAME"),
+            std::env::var("CARGO_PKG_VERSION"),
+        ) {
+            (Ok(name), Ok(version)) => Some((name, version)),
+            _ => None,
+        };
+        let dir = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let git = |args: &[&str]| {
+            let output = process::Command::new("git")
+                .arg("-C")
+                .arg(&dir)
+                .args(args)
+                .output()
+                .ok()?;
+            if output.status.success() {
+                String::from_utf8(output.stdout).ok()
+            } else {
+                None
+            }
+        };
+        let git_commit = git(&["rev-parse", "HEAD"]).map(|commit| commit.trim().to_string());
+        let git_dirty = git_commit.is_some()
+            && git(&["status", "--porcelain", "--untracked-files=no"])
+                .is_some_and(|status| !status.trim().is_empty());
+
+        Generation {
+            fart_version: env!("CARGO_PKG_VERSION").to_string(),
+            sketch,
+            seed,
+            user_consts: USER_CONSTS.lock().unwrap().clone(),
+            git_commit,
+            git_dirty,
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            duration,
+        }
+    }
+
+    /// Get the environment variables that set every user constant to the
+    /// value it had, for running the sketch again.
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        self.user_consts
+            .iter()
+            .map(|c| (format!("FART_USER_CONST_{}", c.name), c.value.clone()))
+            .collect()
+    }
+
+    /// Read how an SVG saved by `fart::generate` was generated, if it says.
+    pub fn read_svg(svg: &str) -> crate::Result<Option<Generation>> {
+        let mut generation: Option<Generation> = None;
+        // The `<fart:const>` whose value is being read.
+        let mut user_const: Option<UserConst> = None;
+        for event in Parser::new(svg) {
+            let (name, kind, attributes) = match event {
+                Event::Tag(name, kind, attributes) => (name, kind, attributes),
+                Event::Text(text) => {
+                    if let Some(user_const) = &mut user_const {
+                        user_const.value.push_str(&xml::unescape(text));
+                    }
+                    continue;
+                }
+                Event::Error(e) => return Err(failure::format_err!("invalid SVG: {}", e)),
+                _ => continue,
+            };
+            if kind == Type::End {
+                if name == "fart:const" {
+                    if let (Some(generation), Some(user_const)) =
+                        (generation.as_mut(), user_const.take())
+                    {
+                        generation.user_consts.push(user_const);
+                    }
+                }
+                continue;
+            }
+            let get = |name: &str| {
+                attributes
+                    .get(name)
+                    .map(|value| xml::attribute_value(value))
+            };
+            let required = |name: &str| {
+                get(name).ok_or_else(|| failure::format_err!("generation metadata has no {}", name))
+            };
+            match name {
+                "fart:generation" => {
+                    let seed = required("seed")?;
+                    let generated_at = required("generated-at")?;
+                    let duration = required("duration")?;
+                    generation = Some(Generation {
+                        fart_version: required("fart-version")?,
+                        sketch: get("sketch").zip(get("sketch-version")),
+                        seed: seed
+                            .parse::<u64>()
+                            .with_context(|_| format!("invalid seed: {:?}", seed))?,
+                        user_consts: vec![],
+                        git_commit: get("git-commit"),
+                        git_dirty: get("git-dirty").as_deref() == Some("true"),
+                        generated_at: generated_at
+                            .parse::<u64>()
+                            .with_context(|_| format!("invalid time: {:?}", generated_at))?,
+                        duration: Duration::from_secs_f64(
+                            duration
+                                .parse::<f64>()
+                                .with_context(|_| format!("invalid duration: {:?}", duration))?,
+                        ),
+                    });
+                }
+                "fart:const" => {
+                    let read = UserConst {
+                        name: required("name")?,
+                        ty: required("type")?,
+                        value: String::new(),
+                    };
+                    match &mut generation {
+                        // An empty value is written as an empty element.
+                        Some(generation) if kind == Type::Empty => {
+                            generation.user_consts.push(read)
+                        }
+                        Some(_) => user_const = Some(read),
+                        None => failure::bail!("<fart:const> outside of <fart:generation>"),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(generation)
+    }
+
+    /// A `<metadata>` element that records this generation.
+    pub(crate) fn to_metadata(&self) -> Element {
+        let mut generation = Element::new("fart:generation");
+        generation.assign("fart-version", self.fart_version.clone());
+        if let Some((name, version)) = &self.sketch {
+            generation.assign("sketch", name.clone());
+            generation.assign("sketch-version", version.clone());
+        }
+        generation.assign("seed", self.seed.to_string());
+        if let Some(commit) = &self.git_commit {
+            generation.assign("git-commit", commit.clone());
+            generation.assign("git-dirty", self.git_dirty.to_string());
+        }
+        generation.assign("generated-at", self.generated_at.to_string());
+        generation.assign("duration", self.duration.as_secs_f64().to_string());
+        for user_const in &self.user_consts {
+            let mut element = Element::new("fart:const");
+            element.assign("name", xml::attribute(&user_const.name));
+            element.assign("type", xml::attribute(&user_const.ty));
+            // Values can be any string, so they are written as text, which
+            // keeps double quotes.
+            element.append(Text::new(user_const.value.clone()));
+            generation.append(element);
+        }
+
+        let mut metadata = Element::new("metadata");
+        metadata.assign("id", "fart-generation");
+        metadata.append(generation);
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generations_round_trip() {
+        let generation = Generation {
+            fart_version: "0.4.0".to_string(),
+            sketch: Some(("sketch".to_string(), "0.1.0".to_string())),
+            seed: 42,
+            user_consts: vec![
+                UserConst {
+                    name: "RNG_SEED".to_string(),
+                    ty: "u64".to_string(),
+                    value: "42".to_string(),
+                },
+                UserConst {
+                    name: "TITLE".to_string(),
+                    ty: "&'static str".to_string(),
+                    value: r#""Waves & <Ripples>""#.to_string(),
+                },
+                UserConst {
+                    name: "EMPTY".to_string(),
+                    ty: "String".to_string(),
+                    value: "".to_string(),
+                },
+            ],
+            git_commit: Some("0123abcd".to_string()),
+            git_dirty: true,
+            generated_at: 1_600_000_000,
+            duration: Duration::from_millis(1500),
+        };
+        let doc = svg::Document::new()
+            .set("xmlns:fart", NAMESPACE)
+            .add(generation.to_metadata());
+
+        let read = Generation::read_svg(&doc.to_string()).unwrap();
+        assert_eq!(read, Some(generation.clone()));
+        assert_eq!(
+            generation.env_vars(),
+            vec![
+                ("FART_USER_CONST_RNG_SEED".to_string(), "42".to_string()),
+                (
+                    "FART_USER_CONST_TITLE".to_string(),
+                    r#""Waves & <Ripples>""#.to_string()
+                ),
+                ("FART_USER_CONST_EMPTY".to_string(), "".to_string()),
+            ]
+        );
+
+        assert_eq!(Generation::read_svg("<svg/>").unwrap(), None);
+    }
+}
@@ -0,0 +1,298 @@
+//! Filling shapes with hatching.
+//!
+//! Pen plotters can't fill a shape, so fills are made out of many parallel
+//! strokes instead.
+
+use crate::path::{LineCommand, Path};
+use euclid::{Angle, Point2D};
+use fart_2d_geom::PolygonWithHoles;
+use partial_min_max::{max, min};
+
+/// A hatch fill: parallel lines at a given angle and spacing, optionally
+/// crossed by a second set of lines at a right angle to the first.
+///
+/// # Example
+///
+/// ```
+/// use fart::euclid::{point2, Angle, UnknownUnit};
+/// use fart::geom::Polygon;
+/// use fart::hatch::Hatch;
+///
+/// let square = Polygon::<f64, UnknownUnit>::new(vec![
+///     point2(0.0, 0.0),
+///     point2(10.0, 0.0),
+///     point2(10.0, 10.0),
+///     point2(0.0, 10.0),
+/// ]);
+///
+/// let hatch = Hatch::new(Angle::degrees(45.0)).spacing(0.5).crosshatch(true);
+/// let paths = hatch.fill(&square.into());
+/// assert!(!paths.is_empty());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Hatch {
+    angle: Angle<f64>,
+    spacing: Option<f64>,
+    crosshatch: bool,
+}
+
+impl Hatch {
+    /// Construct a new hatch whose lines run in the direction of the given
+    /// angle.
+    ///
+    /// It has no spacing yet, and no crosshatching.
+    pub fn new(angle: Angle<f64>) -> Hatch {
+        Hatch {
+            angle,
+            spacing: None,
+            crosshatch: false,
+        }
+    }
+
+    /// Set the distance between neighbouring hatch lines.
+    ///
+    /// When drawing with `Canvas::hatch`, this defaults to the nib size of
+    /// the layer's pen, which makes for solid looking fills.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spacing` is not positive.
+    pub fn spacing(mut self, spacing: f64) -> Hatch {
+        assert!(spacing > 0.0, "hatch spacing must be positive");
+        self.spacing = Some(spacing);
+        self
+    }
+
+    /// Whether to also draw a second set of lines at a right angle to the
+    /// first.
+    pub fn crosshatch(mut self, crosshatch: bool) -> Hatch {
+        self.crosshatch = crosshatch;
+        self
+    }
+
+    /// Use the given spacing if this hatch doesn't have one yet.
+    pub(crate) fn or_spacing(mut self, spacing: f64) -> Hatch {
+        self.spacing = self.spacing.or(Some(spacing));
+        self
+    }
+
+    /// Fill the given shape with this hatch.
+    ///
+    /// Every hatch line is clipped exactly to the interior of the shape, so
+    /// nothing is drawn inside its holes. Each stroke becomes its own path,
+    /// and every other line runs backwards so that the pen doesn't have to
+    /// travel back across the shape between strokes.
+    ///
+    /// Hatch lines sit at whole multiples of the spacing, so neighbouring
+    /// shapes filled with the same hatch have lines that line up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no spacing has been set.
+    pub fn fill<U>(&self, shape: &PolygonWithHoles<f64, U>) -> Vec<Path<f64, U>> {
+        let spacing = self
+            .spacing
+            .expect("a hatch needs a spacing to fill a shape with");
+
+        let mut paths = vec![];
+        hatch_lines(shape, self.angle, spacing, &mut paths);
+        if self.crosshatch {
+            let right_angle = Angle::frac_pi_2();
+            hatch_lines(shape, self.angle + right_angle, spacing, &mut paths);
+        }
+        paths
+    }
+}
+
+fn hatch_lines<U>(
+    shape: &PolygonWithHoles<f64, U>,
+    angle: Angle<f64>,
+    spacing: f64,
+    paths: &mut Vec<Path<f64, U>>,
+) {
+    // Measure everything along the hatch lines (`u`) and across them (`v`).
+    let (sin, cos) = angle.sin_cos();
+    let u = |p: Point2D<f64, U>| p.x * cos + p.y * sin;
+    let v = |p: Point2D<f64, U>| p.y * cos - p.x * sin;
+
+    let (mut v_min, mut v_max) = (f64::INFINITY, f64::NEG_INFINITY);
+    for ring in shape.rings() {
+        for &p in ring.vertices() {
+            v_min = min(v_min, v(p));
+            v_max = max(v_max, v(p));
+        }
+    }
+
+    let mut crossings = vec![];
+    let mut backwards = false;
+    let mut k = (v_min / spacing).ceil();
+    while k * spacing <= v_max {
+        let line_v = k * spacing;
+        k += 1.0;
+
+        // Find everywhere the hatch line crosses the shape's boundary. Edges
+        // are treated as half-open so that a line through a vertex crosses
+        // exactly one of the vertex's edges, or both of them when it only
+        // touches the boundary there.
+        crossings.clear();
+        for edge in shape.edges() {
+            let (va, vb) = (v(edge.a), v(edge.b));
+            if (va <= line_v) != (vb <= line_v) {
+                let t = (line_v - va) / (vb - va);
+                crossings.push(edge.a.lerp(edge.b, t));
+            }
+        }
+        crossings.sort_by(|&a, &b| u(a).partial_cmp(&u(b)).unwrap());
+
+        // By the even-odd rule, the inside of the shape is between every
+        // other pair of crossings.
+        let mut strokes: Vec<_> = crossings
+            .chunks(2)
+            .filter(|pair| pair.len() == 2 && pair[0] != pair[1])
+            .map(|pair| (pair[0], pair[1]))
+            .collect();
+        if backwards {
+            strokes.reverse();
+        }
+        for (a, b) in strokes {
+            let (from, to) = if backwards { (b, a) } else { (a, b) };
+            paths.push(Path::with_commands(vec![
+                LineCommand::MoveTo(from),
+                LineCommand::LineTo(to),
+            ]));
+        }
+        backwards = !backwards;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hatch;
+    use crate::path::{LineCommand, Path};
+    use euclid::{point2, Angle, Point2D, UnknownUnit};
+    use fart_2d_geom::{Polygon, PolygonWithHoles};
+
+    /// The ends of a stroke, as `(x, y)` pairs.
+    type Stroke = ((f64, f64), (f64, f64));
+
+    fn square(min: f64, max: f64) -> Polygon<f64, UnknownUnit> {
+        Polygon::new(vec![
+            point2(min, min),
+            point2(max, min),
+            point2(max, max),
+            point2(min, max),
+        ])
+    }
+
+    fn ends(
+        path: &Path<f64, UnknownUnit>,
+    ) -> (Point2D<f64, UnknownUnit>, Point2D<f64, UnknownUnit>) {
+        match path.commands[..] {
+            [LineCommand::MoveTo(a), LineCommand::LineTo(b)] => (a, b),
+            ref otherwise => panic!("expected a single stroke, found {:?}", otherwise),
+        }
+    }
+
+    fn assert_strokes(paths: &[Path<f64, UnknownUnit>], expected: &[Stroke]) {
+        assert_eq!(paths.len(), expected.len());
+        for (path, &(a, b)) in paths.iter().zip(expected) {
+            let (from, to) = ends(path);
+            assert!(
+                (from - point2(a.0, a.1)).length() < 1e-9
+                    && (to - point2(b.0, b.1)).length() < 1e-9,
+                "expected stroke {:?}, found {:?}",
+                (a, b),
+                (from, to)
+            );
+        }
+    }
+
+    fn total_length(paths: &[Path<f64, UnknownUnit>]) -> f64 {
+        paths.iter().map(|p| p.length()).sum()
+    }
+
+    #[test]
+    fn horizontal_hatching_alternates_direction() {
+        let paths = Hatch::new(Angle::zero())
+            .spacing(1.0)
+            .fill(&square(0.5, 3.5).into());
+        assert_strokes(
+            &paths,
+            &[
+                ((0.5, 1.0), (3.5, 1.0)),
+                ((3.5, 2.0), (0.5, 2.0)),
+                ((0.5, 3.0), (3.5, 3.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn holes_are_left_empty() {
+        let frame = PolygonWithHoles::new(square(0.0, 10.0), vec![square(2.5, 7.5)]);
+        let hatch = Hatch::new(Angle::degrees(30.0)).spacing(0.1);
+        let paths = hatch.fill(&frame);
+
+        for path in &paths {
+            let (a, b) = ends(path);
+            let mid = a.lerp(b, 0.5);
+            let in_hole = mid.x > 2.5 && mid.x < 7.5 && mid.y > 2.5 && mid.y < 7.5;
+            assert!(!in_hole, "stroke {:?} crosses the hole", (a, b));
+            for p in &[a, b] {
+                assert!(p.x >= -1e-9 && p.x <= 10.0 + 1e-9);
+                assert!(p.y >= -1e-9 && p.y <= 10.0 + 1e-9);
+            }
+        }
+
+        // Hatching covers the area with one spacing's worth of line length
+        // per unit of area.
+        let covered = total_length(&paths) * 0.1;
+        assert!((covered - frame.area()).abs() < 1.0, "covered {}", covered);
+    }
+
+    #[test]
+    fn concave_shapes_are_clipped_exactly() {
+        // A "U" shape: the middle hatch lines are split in two.
+        let u = Polygon::<f64, UnknownUnit>::new(vec![
+            point2(0.0, 0.0),
+            point2(3.0, 0.0),
+            point2(3.0, 3.0),
+            point2(2.0, 3.0),
+            point2(2.0, 1.0),
+            point2(1.0, 1.0),
+            point2(1.0, 3.0),
+            point2(0.0, 3.0),
+        ]);
+        let paths = Hatch::new(Angle::zero()).spacing(1.5).fill(&u.into());
+        assert_strokes(
+            &paths,
+            &[
+                ((0.0, 0.0), (3.0, 0.0)),
+                ((3.0, 1.5), (2.0, 1.5)),
+                ((1.0, 1.5), (0.0, 1.5)),
+            ],
+        );
+    }
+
+    #[test]
+    fn crosshatching_adds_perpendicular_lines() {
+        let shape: PolygonWithHoles<_, _> = square(0.0, 4.0).into();
+        let hatch = Hatch::new(Angle::degrees(45.0)).spacing(0.25);
+        let single = hatch.fill(&shape);
+        let cross = hatch.crosshatch(true).fill(&shape);
+
+        // The two sets of lines don't sit at the same offsets within the
+        // square, so their lengths only roughly agree.
+        assert!((total_length(&cross) - 2.0 * total_length(&single)).abs() < 1.0);
+        for path in &cross[single.len()..] {
+            let (a, b) = ends(path);
+            let d = b - a;
+            assert!((d.x + d.y).abs() < 1e-9, "{:?} is not at 135 degrees", d);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn filling_needs_a_spacing() {
+        Hatch::new(Angle::zero()).fill(&square(0.0, 1.0).into());
+    }
+}
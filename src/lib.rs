@@ -33,13 +33,17 @@
 #![deny(missing_docs, missing_debug_implementations)]
 
 pub mod canvas;
+pub mod hatch;
 pub mod path;
 pub mod prelude;
 pub mod process;
+pub mod simplify;
 pub mod units;
 
+mod generation;
 mod thread_rng;
 mod user_const;
+mod xml;
 
 // Re-exports of our public dependencies.
 pub use euclid;
@@ -55,11 +59,15 @@ pub use rand;
 pub use svg;
 pub use penlib;
 
+pub use generation::{Generation, UserConst};
 pub use thread_rng::FartThreadRng;
 
+#[doc(hidden)]
+pub use generation::record_user_const as __record_user_const;
+
 use failure::ResultExt;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Either an `Ok(T)` or an `Err(failure::Error)`.
 pub type Result<T> = ::std::result::Result<T, failure::Error>;
@@ -92,23 +100,58 @@ impl Config {
     }
 }
 
+/// Something that `fart::generate` can save.
+///
+/// This is implemented for `svg::Document`, which is saved as it is, and for
+/// `Canvas`, which is also saved as one SVG per layer, next to the combined
+/// SVG, so that every pen can be plotted in a separate pass.
+pub trait Output {
+    /// Get the combined SVG document, and the SVG documents of the separate
+    /// layers along with their layer numbers, if there are any.
+    fn into_svgs(self) -> (svg::Document, Vec<(u64, svg::Document)>);
+
+    /// Get a report about the output to print after generating it, if there
+    /// is one. For a `Canvas`, this is its `Canvas::plot_stats`.
+    fn report(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Output for svg::Document {
+    fn into_svgs(self) -> (svg::Document, Vec<(u64, svg::Document)>) {
+        (self, vec![])
+    }
+}
+
 /// Generate an SVG with the given function `f`.
 ///
+/// If `f` returns a `Canvas`, then every layer is also saved in its own file
+/// next to the combined SVG: when the SVG is saved as `name.svg`, the layers
+/// are saved as `name-layer1.svg`, `name-layer2.svg`, and so on. The canvas's
+/// plot statistics and estimated plot duration are printed, and embedded in
+/// the SVGs' metadata.
+///
+/// Every saved SVG also records how it was generated, so that it can be
+/// generated again: the seed, every user constant, the versions of `fart` and
+/// the sketch, the sketch's git commit, and when and how quickly it was
+/// generated. See `Generation`.
+///
 /// ```no_run
 /// # #![allow(warnings)]
 /// fn main() {
-///     fart::generate(|cfg| {
+///     fart::generate(|cfg| -> fart::Result<fart::svg::Document> {
 ///         unimplemented!("Your code here...")
 ///     });
 /// }
 /// ```
-pub fn generate<F>(f: F) -> !
+pub fn generate<F, O>(f: F) -> !
 where
-    F: FnOnce(&mut Config) -> Result<svg::Document>,
+    F: FnOnce(&mut Config) -> Result<O>,
+    O: Output,
 {
     let then = std::time::Instant::now();
     eprintln!("fart: PARAMS START");
-    let code = match try_generate(f) {
+    let code = match try_generate(f, then) {
         Ok(()) => {
             eprintln!(
                 "Generated in {:?}",
@@ -127,18 +170,70 @@ where
     std::process::exit(code);
 }
 
-fn try_generate<F>(f: F) -> Result<()>
+fn try_generate<F, O>(f: F, then: std::time::Instant) -> Result<()>
 where
-    F: FnOnce(&mut Config) -> Result<svg::Document>,
+    F: FnOnce(&mut Config) -> Result<O>,
+    O: Output,
 {
     let mut config = Config::new().context("failed to read configuration")?;
-    let doc = f(&mut config).context("function supplied to `fart::generate` failed")?;
+    let output = f(&mut config).context("function supplied to `fart::generate` failed")?;
     eprintln!("fart: PARAMS END");
-    svg::save(&config.file_name, &doc).context("failed to save SVG to a file")?;
+    if let Some(report) = output.report() {
+        eprintln!("{}", report);
+    }
+    let (doc, layers) = output.into_svgs();
+    let generation = Generation::current(then.elapsed());
+    let with_generation = |doc: svg::Document| {
+        doc.set("xmlns:fart", generation::NAMESPACE)
+            .add(generation.to_metadata())
+    };
+    svg::save(&config.file_name, &with_generation(doc))
+        .context("failed to save SVG to a file")?;
+    for (number, doc) in layers {
+        let doc = with_generation(doc);
+        let file_name = layer_file_name(&config.file_name, number);
+        svg::save(&file_name, &doc)
+            .with_context(|_| format!("failed to save SVG to {}", file_name.display()))?;
+    }
     Ok(())
 }
 
+/// Get the file name for the given layer's SVG, next to the combined SVG.
+fn layer_file_name(file_name: &Path, layer: u64) -> PathBuf {
+    let mut layer_file_name = file_name
+        .file_stem()
+        .unwrap_or_default()
+        .to_os_string();
+    layer_file_name.push(format!("-layer{}", layer));
+    if let Some(extension) = file_name.extension() {
+        layer_file_name.push(".");
+        layer_file_name.push(extension);
+    }
+    file_name.with_file_name(layer_file_name)
+}
+
 /// Get this thread's `FartThreadRng`!
 pub fn rng() -> FartThreadRng {
     return FartThreadRng::default();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_files_are_next_to_the_combined_file() {
+        assert_eq!(
+            layer_file_name(Path::new("images/2021-01-01.svg"), 2),
+            PathBuf::from("images/2021-01-01-layer2.svg")
+        );
+        assert_eq!(
+            layer_file_name(Path::new("out"), 1),
+            PathBuf::from("out-layer1")
+        );
+        assert_eq!(
+            layer_file_name(Path::new("v1.2.svg"), 3),
+            PathBuf::from("v1.2-layer3.svg")
+        );
+    }
+}
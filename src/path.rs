@@ -3,6 +3,20 @@
 //! Everything in a scene is eventually lowered into a series of paths, which
 //! are finally compiled to SVG.
 
+mod clip;
+mod dash;
+mod flatten;
+mod join;
+mod measure;
+mod parse;
+mod reverse;
+mod segment;
+mod transform;
+
+pub use clip::Clipped;
+pub use dash::Dashed;
+pub(crate) use flatten::is_dot;
+
 use crate::canvas::CanvasSpace;
 use euclid::{point2, vec2, Point2D, Vector2D};
 use num_traits::{Num, NumAssign, NumCast, Signed};
@@ -151,7 +165,7 @@ pub enum LineCommand<T, U> {
         x_radius: T,
         /// The y radius of the arc's ellipse.
         y_radius: T,
-        /// The rotation of the arc.
+        /// The rotation of the arc, in degrees (like in SVG path data).
         x_axis_rotation: T,
         /// Should the arc be greater than or less than 180 degrees?
         large_arc_flag: bool,
@@ -164,7 +178,7 @@ pub enum LineCommand<T, U> {
 
 impl<T, U> LineCommand<T, U>
 where
-    T: Copy + Num + PartialOrd,
+    T: Copy + Num + NumCast + PartialOrd,
 {
     /// Transform this line command with the given linear transformation and
     /// return the new, transformed line command.
@@ -235,11 +249,13 @@ where
                 sweep_flag,
                 end,
             } => {
-                let radius = transformation.transform_point(point2(x_radius, y_radius));
+                let rotation = x_axis_rotation.get().to_f64().unwrap();
+                let (x_radius, y_radius, rotation, sweep_flag) =
+                    transform_arc(transformation, x_radius, y_radius, rotation, sweep_flag);
                 LineCommand::ArcTo {
-                    x_radius: radius.x,
-                    y_radius: radius.y,
-                    x_axis_rotation,
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation: euclid::Angle::radians(T::from(rotation).unwrap()),
                     large_arc_flag,
                     sweep_flag,
                     end: transformation.transform_point(end),
@@ -253,11 +269,13 @@ where
                 sweep_flag,
                 end,
             } => {
-                let radius = transformation.transform_vector(vec2(x_radius, y_radius));
+                let rotation = x_axis_rotation.to_f64().unwrap().to_radians();
+                let (x_radius, y_radius, rotation, sweep_flag) =
+                    transform_arc(transformation, x_radius, y_radius, rotation, sweep_flag);
                 LineCommand::ArcBy {
-                    x_radius: radius.x,
-                    y_radius: radius.y,
-                    x_axis_rotation,
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation: T::from(rotation.to_degrees()).unwrap(),
                     large_arc_flag,
                     sweep_flag,
                     end: transformation.transform_vector(end),
@@ -272,6 +290,37 @@ where
     }
 }
 
+/// Transform an arc's radii, the rotation of its x axis in radians, and its
+/// sweep flag.
+///
+/// Each radius is scaled by how much the transformation stretches the axis it
+/// lies along, ignoring any translation, and the x axis turns along with the
+/// transformation. Mirroring transformations reverse the arc's direction.
+fn transform_arc<T, U, V>(
+    transformation: &euclid::Transform2D<T, U, V>,
+    x_radius: T,
+    y_radius: T,
+    rotation: f64,
+    sweep_flag: bool,
+) -> (T, T, f64, bool)
+where
+    T: Copy + Num + NumCast,
+{
+    let transformation: euclid::Transform2D<f64, U, V> = transformation.cast();
+    let (sin, cos) = rotation.sin_cos();
+    let x_axis = transformation.transform_vector(vec2(cos, sin));
+    let y_axis = transformation.transform_vector(vec2(-sin, cos));
+    let scale = |radius: T, axis: Vector2D<f64, V>| {
+        T::from(radius.to_f64().unwrap().abs() * axis.length()).unwrap()
+    };
+    (
+        scale(x_radius, x_axis),
+        scale(y_radius, y_axis),
+        x_axis.y.atan2(x_axis.x),
+        sweep_flag != (transformation.determinant() < 0.0),
+    )
+}
+
 impl<T, U> LineCommand<T, U>
 where
     T: Copy + NumCast,
@@ -409,7 +458,7 @@ where
 
 impl<T, U> Path<T, U>
 where
-    T: Copy + Num + PartialOrd,
+    T: Copy + Num + NumCast + PartialOrd,
 {
     /// Transform this path with the given linear transformation and return
     /// the new, transformed path.
@@ -433,7 +482,7 @@ where
 
 impl<'a, T> From<&'a Path<T, CanvasSpace>> for svg::node::element::Path
 where
-    T: Copy + Debug + Into<svg::node::element::path::Parameters>,
+    T: Copy + Debug + NumCast + Into<svg::node::element::path::Parameters>,
 {
     fn from(path: &'a Path<T, CanvasSpace>) -> svg::node::element::Path {
         let mut data = svg::node::element::path::Data::new();
@@ -466,7 +515,67 @@ where
                 LineCommand::SmoothQuadtraticCurveBy(v) => {
                     data.smooth_quadratic_curve_by((v.x, v.y))
                 }
-                cmd => unimplemented!("Have not implemented support for command yet: {:?}", cmd),
+                LineCommand::CubicBezierTo {
+                    control_1,
+                    control_2,
+                    end,
+                } => data.cubic_curve_to((
+                    control_1.x,
+                    control_1.y,
+                    control_2.x,
+                    control_2.y,
+                    end.x,
+                    end.y,
+                )),
+                LineCommand::CubicBezierBy {
+                    control_1,
+                    control_2,
+                    end,
+                } => data.cubic_curve_by((
+                    control_1.x,
+                    control_1.y,
+                    control_2.x,
+                    control_2.y,
+                    end.x,
+                    end.y,
+                )),
+                LineCommand::ArcTo {
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation,
+                    large_arc_flag,
+                    sweep_flag,
+                    end,
+                } => {
+                    // SVG wants the rotation in degrees, but `euclid::Angle`
+                    // stores radians.
+                    let radians: f64 = NumCast::from(x_axis_rotation.radians).unwrap();
+                    data.elliptical_arc_to((
+                        *x_radius,
+                        *y_radius,
+                        radians.to_degrees(),
+                        *large_arc_flag as u8,
+                        *sweep_flag as u8,
+                        end.x,
+                        end.y,
+                    ))
+                }
+                LineCommand::ArcBy {
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation,
+                    large_arc_flag,
+                    sweep_flag,
+                    end,
+                } => data.elliptical_arc_by((
+                    *x_radius,
+                    *y_radius,
+                    *x_axis_rotation,
+                    *large_arc_flag as u8,
+                    *sweep_flag as u8,
+                    end.x,
+                    end.y,
+                )),
             };
         }
         svg::node::element::Path::new()
@@ -477,8 +586,9 @@ where
 
 /// Anything that can render as one or more `Path`s.
 ///
-/// This is implemented for `fart::geom::Polygon` and
-/// `fart::geom::ConvexPolygon`, and draws the outline of the polygon. It is
+/// This is implemented for `fart::geom::Polygon`,
+/// `fart::geom::ConvexPolygon`, and `fart::geom::PolygonWithHoles`, and draws
+/// the outline of the polygon. It is
 /// also implemented for `fart::geom::Line`, and draws just the line.
 pub trait ToPaths<T, U> {
     /// An iterator over this thing's paths.
@@ -535,6 +645,30 @@ where
     }
 }
 
+impl<T, U> ToPaths<T, U> for fart_2d_geom::PolygonWithHoles<T, U>
+where
+    T: Copy + NumAssign + PartialOrd + Signed + Debug,
+{
+    type Paths = iter::Once<Path<T, U>>;
+
+    fn to_paths(&self) -> Self::Paths {
+        // Every ring is its own closed sub-path of a single path.
+        let mut commands = vec![];
+        for ring in self.rings() {
+            for (i, v) in ring.vertices().iter().enumerate() {
+                commands.push(if i == 0 {
+                    LineCommand::MoveTo(*v)
+                } else {
+                    LineCommand::LineTo(*v)
+                });
+            }
+            commands.push(LineCommand::Close);
+        }
+
+        iter::once(Path::with_commands(commands))
+    }
+}
+
 impl<T, U> ToPaths<T, U> for fart_2d_geom::Polyline<T, U>
 where
     T: Copy + NumAssign + PartialOrd + Signed + Debug,
@@ -589,68 +723,295 @@ where
     }
 }
 
-/*
-/// A `ToPaths` wrapper type that forces all of the paths produced by the inner
-/// type to be of a certain color. Created using `ToPathsExt::color`.
-#[derive(Debug, Clone)]
-pub struct Color<P> {
-    inner: P,
-    color: Cow<'static, str>,
-}
+/// An extension trait for shapes to add various helper utilities.
+pub trait ToPathsExt<T, U>: ToPaths<T, U> {
+    /// Split this shape's paths into dashes.
+    ///
+    /// The `pattern` alternates between the lengths of dashes and the lengths
+    /// of the gaps between them, like SVG's `stroke-dasharray`: a pattern with
+    /// an odd number of lengths is repeated to make it even, and a pattern of
+    /// all zeros leaves the paths undashed. The `offset` is the distance into
+    /// the pattern at which each sub-path starts, like SVG's
+    /// `stroke-dashoffset`.
+    ///
+    /// Dashes follow the curves they are cut from, and carry on around
+    /// corners. A dash of zero length becomes a dot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any length in `pattern` is negative.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::euclid::{point2, UnknownUnit};
+    /// use fart::path::{LineCommand, Path, ToPaths, ToPathsExt};
+    ///
+    /// let line = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 0.0)),
+    ///     LineCommand::LineTo(point2(10.0, 0.0)),
+    /// ]);
+    ///
+    /// let dashes = line.dashed(&[2.0, 1.0], 0.0);
+    /// assert_eq!(dashes.to_paths().count(), 4);
+    /// ```
+    fn dashed(self, pattern: &[f64], offset: f64) -> Dashed<Self>
+    where
+        Self: Sized,
+    {
+        Dashed::new(self, pattern, offset)
+    }
 
-/// An iterator over paths produced by `Color<P>`. Created via `<Color<P> as
-/// ToPaths<_, _>>::to_paths()`.
-#[derive(Clone, Debug)]
-pub struct ColorPaths<P, T, U> {
-    inner: P,
-    color: Cow<'static, str>,
-    _phantom: PhantomData<fn() -> Path<T, U>>,
+    /// Replace this shape's paths with dots, `spacing` apart along them.
+    ///
+    /// Every dot is a path that moves to a point and draws a line to that
+    /// same point, which plots as a single touch of the pen.
+    fn dotted(self, spacing: f64) -> Dashed<Self>
+    where
+        Self: Sized,
+    {
+        Dashed::new(self, &[0.0, spacing], 0.0)
+    }
+
+    /// Clip this shape's paths against the given mask, keeping either the
+    /// parts inside or the parts outside of it.
+    ///
+    /// The paths are first flattened into straight lines that deviate at most
+    /// `tolerance` from any curves, and then clipped exactly. The mask can be
+    /// a `fart::geom::Polygon`, `fart::geom::ConvexPolygon`,
+    /// `fart::geom::PolygonWithHoles`, or `fart::aabb::Aabb`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is not positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::aabb::Aabb;
+    /// use fart::euclid::{point2, UnknownUnit};
+    /// use fart::geom::Keep;
+    /// use fart::path::{LineCommand, Path, ToPaths, ToPathsExt};
+    ///
+    /// let line = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 5.0)),
+    ///     LineCommand::LineTo(point2(20.0, 5.0)),
+    /// ]);
+    /// let mask = Aabb::new(point2(0.0, 0.0), point2(10.0, 10.0));
+    ///
+    /// let clipped = line.clip(mask, Keep::Inside, 0.1);
+    /// assert_eq!(clipped.to_paths().next().unwrap().length(), 10.0);
+    /// ```
+    fn clip<M>(self, mask: M, keep: fart_2d_geom::Keep, tolerance: f64) -> Clipped<Self, M>
+    where
+        Self: Sized,
+    {
+        Clipped::new(self, mask, keep, tolerance)
+    }
 }
 
-impl<P, T, U> Iterator for ColorPaths<P, T, U>
-where
-    P: Iterator<Item = Path<T, U>>,
-{
-    type Item = Path<T, U>;
+impl<S, T, U> ToPathsExt<T, U> for S where S: ToPaths<T, U> {}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut path = self.inner.next()?;
-        path.color = self.color.clone().into();
-        Some(path)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svg::node::element::path::{Command, Data, Position};
+
+    fn svg_data(commands: Vec<LineCommand<f64, CanvasSpace>>) -> String {
+        let path = Path::with_commands(commands);
+        let element: svg::node::element::Path = (&path).into();
+        let element = element.to_string();
+        let start = element.find(" d=\"").expect("path element should have data") + 4;
+        let len = element[start..].find('"').unwrap();
+        element[start..start + len].to_string()
     }
-}
 
-impl<P, T, U> ToPaths<T, U> for Color<P>
-where
-    P: ToPaths<T, U>,
-{
-    type Paths = ColorPaths<P::Paths, T, U>;
+    fn assert_round_trips(commands: Vec<LineCommand<f64, CanvasSpace>>, expected: &str) {
+        let d = svg_data(commands);
+        assert_eq!(d, expected);
 
-    fn to_paths(&self) -> Self::Paths {
-        let inner = self.inner.to_paths();
-        let color = self.color.clone();
-        ColorPaths {
-            inner,
-            color,
-            _phantom: PhantomData,
+        // Parsing the data back in with the `svg` crate should give us the
+        // same data again.
+        let parsed = Data::parse(&d).unwrap();
+        let reparsed: svg::node::Value = parsed.into();
+        assert_eq!(reparsed.to_string(), expected);
+    }
+
+    #[test]
+    fn polygons_with_holes_draw_every_ring() {
+        let square = |min: f64, max: f64| {
+            fart_2d_geom::Polygon::new(vec![
+                point2(min, min),
+                point2(max, min),
+                point2(max, max),
+                point2(min, max),
+            ])
+        };
+        let frame = fart_2d_geom::PolygonWithHoles::new(square(0.0, 3.0), vec![square(1.0, 2.0)]);
+        let paths: Vec<Path<f64, CanvasSpace>> = frame.to_paths().collect();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(
+            svg_data(paths[0].commands.clone()),
+            "M0,0 L3,0 L3,3 L0,3 z M1,1 L2,1 L2,2 L1,2 z"
+        );
+    }
+
+    #[test]
+    fn lines_and_quadratics() {
+        assert_round_trips(
+            vec![
+                LineCommand::MoveTo(point2(1.0, 2.0)),
+                LineCommand::LineTo(point2(3.0, 4.0)),
+                LineCommand::LineBy(vec2(1.0, 1.0)),
+                LineCommand::HorizontalLineTo(10.0),
+                LineCommand::VerticalLineBy(-2.5),
+                LineCommand::QuadraticBezierTo {
+                    control: point2(1.0, 1.0),
+                    end: point2(2.0, 0.0),
+                },
+                LineCommand::SmoothQuadtraticCurveBy(vec2(2.0, 0.0)),
+                LineCommand::Close,
+            ],
+            "M1,2 L3,4 l1,1 H10 v-2.5 Q1,1,2,0 t2,0 z",
+        );
+    }
+
+    #[test]
+    fn cubic_beziers() {
+        assert_round_trips(
+            vec![
+                LineCommand::MoveTo(point2(0.0, 0.0)),
+                LineCommand::CubicBezierTo {
+                    control_1: point2(1.0, 2.0),
+                    control_2: point2(3.0, 4.0),
+                    end: point2(5.0, 6.0),
+                },
+                LineCommand::CubicBezierBy {
+                    control_1: vec2(-1.0, 0.5),
+                    control_2: vec2(-2.0, 1.5),
+                    end: vec2(-3.0, 0.0),
+                },
+                LineCommand::SmoothCubicBezierTo {
+                    control: point2(7.0, 8.0),
+                    end: point2(9.0, 10.0),
+                },
+            ],
+            "M0,0 C1,2,3,4,5,6 c-1,0.5,-2,1.5,-3,0 S7,8,9,10",
+        );
+    }
+
+    #[test]
+    fn arcs() {
+        assert_round_trips(
+            vec![
+                LineCommand::MoveTo(point2(10.0, 10.0)),
+                LineCommand::ArcTo {
+                    x_radius: 5.0,
+                    y_radius: 2.5,
+                    x_axis_rotation: euclid::Angle::degrees(90.0),
+                    large_arc_flag: true,
+                    sweep_flag: false,
+                    end: point2(20.0, 10.0),
+                },
+                LineCommand::ArcBy {
+                    x_radius: 4.0,
+                    y_radius: 4.0,
+                    x_axis_rotation: 45.0,
+                    large_arc_flag: false,
+                    sweep_flag: true,
+                    end: vec2(0.0, 8.0),
+                },
+            ],
+            "M10,10 A5,2.5,90,1,0,20,10 a4,4,45,0,1,0,8",
+        );
+    }
+
+    #[test]
+    fn parsed_arc_parameters() {
+        let d = svg_data(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::ArcTo {
+                x_radius: 3.0,
+                y_radius: 2.0,
+                x_axis_rotation: euclid::Angle::radians(std::f64::consts::PI),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(6.0, 0.0),
+            },
+        ]);
+        let parsed = Data::parse(&d).unwrap();
+        match &parsed[1] {
+            Command::EllipticalArc(Position::Absolute, params) => {
+                assert_eq!(&params[..], &[3.0, 2.0, 180.0, 0.0, 1.0, 6.0, 0.0]);
+            }
+            otherwise => panic!("expected an absolute arc, found {:?}", otherwise),
         }
     }
-}
 
-/// An extension trait for shapes to add various helper utilities.
-pub trait ToPathsExt<T, U>: ToPaths<T, U> {
-    /// Force this shape's paths to be of the given color.
-    fn color<C>(self, color: C) -> Color<Self>
-    where
-        C: Into<Cow<'static, str>>,
-        Self: Sized,
-    {
-        Color {
-            inner: self,
-            color: color.into(),
+    #[test]
+    fn arc_radii_ignore_translation() {
+        let arc = LineCommand::<f64, CanvasSpace>::ArcTo {
+            x_radius: 5.0,
+            y_radius: 3.0,
+            x_axis_rotation: euclid::Angle::zero(),
+            large_arc_flag: false,
+            sweep_flag: false,
+            end: point2(10.0, 0.0),
+        };
+        let translation = euclid::Transform2D::<_, _, CanvasSpace>::translation(100.0, 50.0);
+        match arc.transform(&translation) {
+            LineCommand::ArcTo {
+                x_radius,
+                y_radius,
+                end,
+                ..
+            } => {
+                assert_eq!((x_radius, y_radius), (5.0, 3.0));
+                assert_eq!(end, point2(110.0, 50.0));
+            }
+            otherwise => panic!("expected an arc, found {:?}", otherwise),
         }
     }
-}
 
-impl<S, T, U> ToPathsExt<T, U> for S where S: ToPaths<T, U> {}
-*/
+    #[test]
+    fn arcs_turn_and_mirror_with_the_transformation() {
+        use euclid::{Angle, Transform2D};
+
+        // Half of an ellipse that is wider than it is tall.
+        let arc = Path::<f64, CanvasSpace>::with_commands(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::ArcTo {
+                x_radius: 2.0,
+                y_radius: 1.0,
+                x_axis_rotation: Angle::zero(),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(4.0, 0.0),
+            },
+        ]);
+        let transformations: [Transform2D<f64, CanvasSpace, CanvasSpace>; 3] = [
+            Transform2D::rotation(Angle::degrees(90.0)),
+            Transform2D::scale(1.0, -1.0),
+            Transform2D::scale(2.0, 2.0)
+                .then_rotate(Angle::degrees(30.0))
+                .then_translate(vec2(5.0, 5.0)),
+        ];
+        for transformation in &transformations {
+            let transformed = arc.transform(transformation);
+            for i in 0..=10 {
+                let fraction = i as f64 / 10.0;
+                let expected = arc.point_at(fraction * arc.length()).unwrap();
+                let expected = transformation.transform_point(expected);
+                let actual = transformed
+                    .point_at(fraction * transformed.length())
+                    .unwrap();
+                assert!(
+                    (expected - actual).length() < 1e-3,
+                    "expected {:?}, found {:?}",
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+}
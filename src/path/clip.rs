@@ -0,0 +1,137 @@
+//! Clipping paths against masks.
+
+use super::{Path, ToPaths};
+use fart_2d_geom::{ClipMask, Keep};
+
+/// A `ToPaths` wrapper that clips all of the inner paths against a mask.
+/// Created using `ToPathsExt::clip`.
+#[derive(Clone, Debug)]
+pub struct Clipped<P, M> {
+    inner: P,
+    mask: M,
+    keep: Keep,
+    tolerance: f64,
+}
+
+impl<P, M> Clipped<P, M> {
+    pub(crate) fn new(inner: P, mask: M, keep: Keep, tolerance: f64) -> Clipped<P, M> {
+        assert!(tolerance > 0.0, "flattening tolerance must be positive");
+        Clipped {
+            inner,
+            mask,
+            keep,
+            tolerance,
+        }
+    }
+}
+
+impl<P, M, U> ToPaths<f64, U> for Clipped<P, M>
+where
+    P: ToPaths<f64, U>,
+    M: ClipMask<U>,
+{
+    type Paths = std::vec::IntoIter<Path<f64, U>>;
+
+    fn to_paths(&self) -> Self::Paths {
+        let mut clipped = vec![];
+        for path in self.inner.to_paths() {
+            for polyline in path.flatten(self.tolerance) {
+                for piece in polyline.clip(&self.mask, self.keep) {
+                    clipped.extend(piece.to_paths());
+                }
+            }
+        }
+        clipped.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{LineCommand, Path, ToPaths, ToPathsExt};
+    use euclid::{point2, Angle, UnknownUnit};
+    use fart_2d_geom::{Keep, Polygon, PolygonWithHoles};
+    use fart_aabb::Aabb;
+
+    fn path(commands: Vec<LineCommand<f64, UnknownUnit>>) -> Path<f64, UnknownUnit> {
+        Path::with_commands(commands)
+    }
+
+    #[test]
+    fn lines_are_trimmed_to_the_mask() {
+        let line = path(vec![
+            LineCommand::MoveTo(point2(-5.0, 1.0)),
+            LineCommand::LineTo(point2(5.0, 1.0)),
+        ]);
+        let mask = Aabb::new(point2(-2.0, 0.0), point2(2.0, 2.0));
+
+        let inside: Vec<_> = (&line).clip(&mask, Keep::Inside, 0.1).to_paths().collect();
+        assert_eq!(inside.len(), 1);
+        let vertices = inside[0].flatten(0.1).remove(0).vertices;
+        assert_eq!(vertices.len(), 2);
+        assert!((vertices[0] - point2(-2.0, 1.0)).length() < 1e-9);
+        assert!((vertices[1] - point2(2.0, 1.0)).length() < 1e-9);
+
+        let outside: Vec<_> = line.clip(&mask, Keep::Outside, 0.1).to_paths().collect();
+        assert_eq!(outside.len(), 2);
+        assert!((outside[0].length() - 3.0).abs() < 1e-9);
+        assert!((outside[1].length() - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn holes_in_the_mask_are_respected() {
+        let square = |min: f64, max: f64| {
+            Polygon::new(vec![
+                point2(min, min),
+                point2(max, min),
+                point2(max, max),
+                point2(min, max),
+            ])
+        };
+        let frame = PolygonWithHoles::new(square(-4.0, 4.0), vec![square(-2.0, 2.0)]);
+        let line = path(vec![
+            LineCommand::MoveTo(point2(-10.0, 0.0)),
+            LineCommand::LineTo(point2(10.0, 0.0)),
+        ]);
+
+        let pieces: Vec<_> = line.clip(&frame, Keep::Inside, 0.1).to_paths().collect();
+        assert_eq!(pieces.len(), 2);
+        for piece in &pieces {
+            assert!((piece.length() - 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn closed_curves_stay_joined_across_their_start() {
+        // A circle starting at its right-most point. Keeping what is outside
+        // of a band down its middle leaves its left and right sides, and the
+        // right side runs across the circle's start.
+        let circle = path(vec![
+            LineCommand::MoveTo(point2(5.0, 0.0)),
+            LineCommand::ArcTo {
+                x_radius: 5.0,
+                y_radius: 5.0,
+                x_axis_rotation: Angle::zero(),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(-5.0, 0.0),
+            },
+            LineCommand::ArcTo {
+                x_radius: 5.0,
+                y_radius: 5.0,
+                x_axis_rotation: Angle::zero(),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(5.0, 0.0),
+            },
+        ]);
+        let mask = Aabb::new(point2(-3.0, -10.0), point2(3.0, 10.0));
+
+        let outside: Vec<_> = circle.clip(&mask, Keep::Outside, 0.01).to_paths().collect();
+        assert_eq!(outside.len(), 2);
+        for piece in &outside {
+            for v in piece.flatten(0.01)[0].vertices() {
+                assert!(v.x.abs() >= 3.0 - 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! Splitting paths into dashes.
+//!
+//! Pen plotters ignore `stroke-dasharray`, so dashes have to be real geometry.
+
+use super::segment::{Segment, SubPath};
+use super::{LineCommand, Path, ToPaths};
+
+/// A `ToPaths` wrapper that splits all of the inner paths into dashes. Created
+/// using `ToPathsExt::dashed` or `ToPathsExt::dotted`.
+#[derive(Clone, Debug)]
+pub struct Dashed<P> {
+    inner: P,
+    pattern: Vec<f64>,
+    offset: f64,
+}
+
+impl<P> Dashed<P> {
+    pub(crate) fn new(inner: P, pattern: &[f64], offset: f64) -> Dashed<P> {
+        assert!(
+            pattern.iter().all(|&l| l >= 0.0),
+            "dash pattern lengths must not be negative: {:?}",
+            pattern
+        );
+
+        // Like SVG's `stroke-dasharray`, an odd number of lengths is repeated
+        // to get an even number of lengths.
+        let mut pattern = pattern.to_vec();
+        if pattern.len() % 2 == 1 {
+            pattern.extend_from_slice(&pattern.clone());
+        }
+
+        Dashed {
+            inner,
+            pattern,
+            offset,
+        }
+    }
+}
+
+impl<P, U> ToPaths<f64, U> for Dashed<P>
+where
+    P: ToPaths<f64, U>,
+{
+    type Paths = std::vec::IntoIter<Path<f64, U>>;
+
+    fn to_paths(&self) -> Self::Paths {
+        let mut dashes = vec![];
+        for path in self.inner.to_paths() {
+            if self.pattern.iter().sum::<f64>() <= 0.0 {
+                dashes.push(path);
+                continue;
+            }
+            for subpath in path.subpaths() {
+                self.dash_subpath(&subpath, &mut dashes);
+            }
+        }
+        dashes.into_iter()
+    }
+}
+
+impl<P> Dashed<P> {
+    fn dash_subpath<U>(&self, subpath: &SubPath<U>, dashes: &mut Vec<Path<f64, U>>) {
+        let first_dash = dashes.len();
+
+        // Find where in the pattern the offset puts us.
+        let total: f64 = self.pattern.iter().sum();
+        let mut index = 0;
+        let mut remaining = self.pattern[0];
+        let mut skip = self.offset.rem_euclid(total);
+        while skip > 0.0 && skip >= remaining {
+            skip -= remaining;
+            index = (index + 1) % self.pattern.len();
+            remaining = self.pattern[index];
+        }
+        remaining -= skip;
+        let starts_on = index % 2 == 0;
+
+        let mut current: Option<Path<f64, U>> = None;
+        // Whether the last dash is a dot at the very end of the sub-path.
+        let mut ends_with_dot = false;
+        for (i, segment) in subpath.segments.iter().enumerate() {
+            let length = segment.length();
+            let mut position = 0.0;
+            loop {
+                let on = index % 2 == 0;
+                if on && current.is_none() {
+                    // Start a new dash here. A zero length dash is a dot.
+                    let at = segment.point(segment.t_at_length(position));
+                    let mut dash = Path::with_commands(vec![LineCommand::MoveTo(at)]);
+                    if remaining == 0.0 {
+                        dash.commands.push(LineCommand::LineTo(at));
+                        ends_with_dot = i + 1 == subpath.segments.len() && position >= length;
+                    }
+                    current = Some(dash);
+                }
+
+                let step = remaining.min(length - position);
+                if on && step > 0.0 {
+                    let t0 = segment.t_at_length(position);
+                    let t1 = segment.t_at_length(position + step);
+                    let piece: Segment<U> = segment.slice(t0, t1);
+                    current.as_mut().unwrap().commands.push(piece.to_command());
+                }
+                position += step;
+                remaining -= step;
+
+                if remaining > 0.0 {
+                    // This pattern element continues into the next segment.
+                    break;
+                }
+                if let Some(dash) = current.take() {
+                    dashes.push(dash);
+                }
+                index = (index + 1) % self.pattern.len();
+                remaining = self.pattern[index];
+                if position >= length && remaining > 0.0 {
+                    break;
+                }
+            }
+        }
+
+        let ends_on = current.is_some();
+        if let Some(dash) = current {
+            dashes.push(dash);
+        }
+
+        // A dot at the very end of a closed sub-path is back where it
+        // started, which already has a dot or dash of its own.
+        if subpath.closed && starts_on && ends_with_dot && dashes.len() - first_dash >= 2 {
+            dashes.pop();
+        }
+
+        // When a closed sub-path both starts and ends in the middle of a dash,
+        // those two halves are really the same dash running across the point
+        // where the sub-path closes.
+        if subpath.closed && starts_on && ends_on && dashes.len() - first_dash >= 2 {
+            let first = dashes.remove(first_dash);
+            let last = dashes.last_mut().unwrap();
+            last.commands.extend(first.commands.into_iter().skip(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{LineCommand, Path, ToPaths, ToPathsExt};
+    use euclid::{point2, vec2, Point2D, UnknownUnit};
+
+    fn path(commands: Vec<LineCommand<f64, UnknownUnit>>) -> Path<f64, UnknownUnit> {
+        Path::with_commands(commands)
+    }
+
+    fn end_points(
+        paths: &[Path<f64, UnknownUnit>],
+    ) -> Vec<(Point2D<f64, UnknownUnit>, Point2D<f64, UnknownUnit>)> {
+        paths
+            .iter()
+            .map(|p| {
+                let vertices = p.flatten(0.001).remove(0).vertices;
+                (vertices[0], *vertices.last().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dashes_follow_the_pattern() {
+        let line = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(10.0, 0.0)),
+        ]);
+        let dashes: Vec<_> = (&line).dashed(&[2.0, 1.0], 0.0).to_paths().collect();
+        assert_eq!(
+            end_points(&dashes),
+            vec![
+                (point2(0.0, 0.0), point2(2.0, 0.0)),
+                (point2(3.0, 0.0), point2(5.0, 0.0)),
+                (point2(6.0, 0.0), point2(8.0, 0.0)),
+                (point2(9.0, 0.0), point2(10.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_shift_the_pattern() {
+        let line = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(6.0, 0.0)),
+        ]);
+        let dashes: Vec<_> = (&line).dashed(&[2.0, 2.0], 3.0).to_paths().collect();
+        assert_eq!(
+            end_points(&dashes),
+            vec![
+                (point2(1.0, 0.0), point2(3.0, 0.0)),
+                (point2(5.0, 0.0), point2(6.0, 0.0)),
+            ]
+        );
+
+        // Negative offsets shift the other way.
+        let dashes: Vec<_> = (&line).dashed(&[2.0, 2.0], -1.0).to_paths().collect();
+        assert_eq!(
+            end_points(&dashes),
+            vec![
+                (point2(1.0, 0.0), point2(3.0, 0.0)),
+                (point2(5.0, 0.0), point2(6.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn dashes_carry_over_corners() {
+        let corner = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineBy(vec2(3.0, 0.0)),
+            LineCommand::LineBy(vec2(0.0, 3.0)),
+        ]);
+        let dashes: Vec<_> = corner.dashed(&[4.0, 1.0], 0.0).to_paths().collect();
+        assert_eq!(dashes.len(), 2);
+        assert_eq!(
+            dashes[0].flatten(0.001)[0].vertices(),
+            &[point2(0.0, 0.0), point2(3.0, 0.0), point2(3.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn dashes_stay_continuous_across_close() {
+        let square = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 4.0)),
+            LineCommand::LineTo(point2(0.0, 4.0)),
+            LineCommand::Close,
+        ]);
+        // 16 units around, so the last dash runs from 15 to 16 and then
+        // continues with the first dash from 0 to 1.
+        let dashes: Vec<_> = square.dashed(&[2.0, 2.0], 1.0).to_paths().collect();
+        assert_eq!(dashes.len(), 4);
+        assert_eq!(
+            dashes.last().unwrap().flatten(0.001)[0].vertices(),
+            &[point2(0.0, 1.0), point2(0.0, 0.0), point2(1.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn dashed_curves_stay_curves() {
+        let curve = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::CubicBezierTo {
+                control_1: point2(0.0, 10.0),
+                control_2: point2(10.0, 10.0),
+                end: point2(10.0, 0.0),
+            },
+        ]);
+        let length = curve.length();
+        let dashes: Vec<_> = (&curve).dashed(&[1.0], 0.0).to_paths().collect();
+        for (i, dash) in dashes.iter().enumerate() {
+            match dash.commands[1] {
+                LineCommand::CubicBezierTo { .. } => {}
+                ref otherwise => panic!("expected a cubic, found {:?}", otherwise),
+            }
+            let start = curve.point_at(2.0 * i as f64).unwrap();
+            let dash_start = dash.point_at(0.0).unwrap();
+            assert!((start - dash_start).length() < 1e-6);
+            assert!(dash.length() <= 1.0 + 1e-6);
+        }
+        assert_eq!(dashes.len(), (length / 2.0).ceil() as usize);
+    }
+
+    #[test]
+    fn dots_are_zero_length_dashes() {
+        let line = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 0.0)),
+        ]);
+        let dots: Vec<_> = line.dotted(2.0).to_paths().collect();
+        assert_eq!(dots.len(), 3);
+        for (i, dot) in dots.iter().enumerate() {
+            let p = point2(2.0 * i as f64, 0.0);
+            match dot.commands[..] {
+                [LineCommand::MoveTo(a), LineCommand::LineTo(b)] => {
+                    assert_eq!((a, b), (p, p));
+                }
+                ref otherwise => panic!("expected a dot, found {:?}", otherwise),
+            }
+        }
+    }
+
+    #[test]
+    fn closed_paths_are_not_dotted_twice_where_they_close() {
+        let square = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 4.0)),
+            LineCommand::LineTo(point2(0.0, 4.0)),
+            LineCommand::Close,
+        ]);
+        let dots: Vec<_> = square.dotted(2.0).to_paths().collect();
+        assert_eq!(dots.len(), 8);
+        let starts: Vec<_> = dots.iter().map(|d| d.start().unwrap()).collect();
+        assert_eq!(starts[0], point2(0.0, 0.0));
+        assert_eq!(starts[7], point2(0.0, 2.0));
+    }
+}
@@ -0,0 +1,315 @@
+//! Flattening paths into polylines.
+
+use super::segment::{Arc, Segment};
+use super::Path;
+use euclid::Point2D;
+use fart_2d_geom::Polyline;
+
+impl<U> Path<f64, U> {
+    /// Flatten this path into polylines made only of straight line segments.
+    ///
+    /// Curves and arcs are approximated such that the polylines never deviate
+    /// from the real curve by more than `tolerance`.
+    ///
+    /// Every sub-path (started by a move command) becomes its own polyline.
+    /// Closed sub-paths end with their first vertex repeated. Sub-paths
+    /// without any length, like the dots of a dotted path, become a polyline
+    /// with their single point repeated, so that they aren't lost. A lone move
+    /// draws nothing, and is skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is not positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::euclid::{point2, UnknownUnit};
+    /// use fart::path::{LineCommand, Path};
+    ///
+    /// let path = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 0.0)),
+    ///     LineCommand::QuadraticBezierTo {
+    ///         control: point2(5.0, 10.0),
+    ///         end: point2(10.0, 0.0),
+    ///     },
+    /// ]);
+    ///
+    /// let polylines = path.flatten(0.1);
+    /// assert_eq!(polylines.len(), 1);
+    /// assert!(polylines[0].len() > 2);
+    /// ```
+    pub fn flatten(&self, tolerance: f64) -> Vec<Polyline<f64, U>> {
+        assert!(tolerance > 0.0, "flattening tolerance must be positive");
+
+        let mut polylines = vec![];
+        for subpath in self.subpaths() {
+            let mut vertices = vec![subpath.start];
+            for segment in &subpath.segments {
+                segment.flatten_into(tolerance, &mut vertices);
+            }
+            if vertices.len() == 1 {
+                vertices.push(subpath.start);
+            }
+            polylines.push(Polyline::new(vertices));
+        }
+        polylines
+    }
+}
+
+impl<U> Segment<U> {
+    /// Push this segment's flattened vertices, except for its start point,
+    /// onto `vertices`.
+    pub(crate) fn flatten_into(&self, tolerance: f64, vertices: &mut Vec<Point2D<f64, U>>) {
+        let n = self.flattening_steps(tolerance);
+        for i in 1..n {
+            push_distinct(vertices, self.point(i as f64 / n as f64));
+        }
+        push_distinct(vertices, self.to());
+    }
+
+    /// How many straight pieces this segment should be split into so that they
+    /// deviate at most `tolerance` from it.
+    ///
+    /// For the bezier curves, linear interpolation over `n` uniform steps
+    /// deviates at most `M / (8 n^2)` where `M` bounds the length of the
+    /// curve's second derivative.
+    fn flattening_steps(&self, tolerance: f64) -> usize {
+        let steps = match *self {
+            Segment::Line { .. } => 1.0,
+            Segment::Quadratic { from, control, to } => {
+                let m = 2.0 * (from - control * 2.0 + to.to_vector()).length();
+                (m / (8.0 * tolerance)).sqrt()
+            }
+            Segment::Cubic {
+                from,
+                control_1,
+                control_2,
+                to,
+            } => {
+                let a = (from - control_1 * 2.0 + control_2.to_vector()).length();
+                let b = (control_1 - control_2 * 2.0 + to.to_vector()).length();
+                let m = 6.0 * a.max(b);
+                (m / (8.0 * tolerance)).sqrt()
+            }
+            Segment::Arc(ref arc) => arc_steps(arc, tolerance),
+        };
+        (steps.ceil() as usize).max(1)
+    }
+}
+
+/// An arc of radius `r` split into steps of angle `a` deviates by the sagitta
+/// `r * (1 - cos(a / 2))` from the real arc.
+fn arc_steps<U>(arc: &Arc<U>, tolerance: f64) -> f64 {
+    let radius = arc.x_radius.max(arc.y_radius);
+    if tolerance >= radius {
+        return (arc.sweep_angle.abs() / std::f64::consts::FRAC_PI_2).ceil();
+    }
+    let step = 2.0 * (1.0 - tolerance / radius).acos();
+    arc.sweep_angle.abs() / step
+}
+
+/// Whether the given polyline, flattened from a path, is a dot: a sub-path
+/// without any length.
+pub(crate) fn is_dot<U>(polyline: &Polyline<f64, U>) -> bool {
+    polyline.vertices().windows(2).all(|w| w[0] == w[1])
+}
+
+fn push_distinct<U>(vertices: &mut Vec<Point2D<f64, U>>, p: Point2D<f64, U>) {
+    if vertices.last() != Some(&p) {
+        vertices.push(p);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{LineCommand, Path};
+    use euclid::{point2, vec2, Angle, Point2D, UnknownUnit};
+
+    fn path(commands: Vec<LineCommand<f64, UnknownUnit>>) -> Path<f64, UnknownUnit> {
+        Path::with_commands(commands)
+    }
+
+    fn distance_to_polyline(
+        p: Point2D<f64, UnknownUnit>,
+        vertices: &[Point2D<f64, UnknownUnit>],
+    ) -> f64 {
+        vertices
+            .windows(2)
+            .map(|w| {
+                let d = w[1] - w[0];
+                let t = ((p - w[0]).dot(d) / d.square_length()).clamp(0.0, 1.0);
+                (w[0] + d * t - p).length()
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    #[test]
+    fn straight_lines_are_kept_exactly() {
+        let polylines = path(vec![
+            LineCommand::MoveTo(point2(1.0, 1.0)),
+            LineCommand::LineBy(vec2(2.0, 0.0)),
+            LineCommand::VerticalLineTo(5.0),
+            LineCommand::HorizontalLineBy(-2.0),
+            LineCommand::Close,
+        ])
+        .flatten(0.1);
+
+        assert_eq!(polylines.len(), 1);
+        assert_eq!(
+            polylines[0].vertices(),
+            &[
+                point2(1.0, 1.0),
+                point2(3.0, 1.0),
+                point2(3.0, 5.0),
+                point2(1.0, 5.0),
+                point2(1.0, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_sub_path_is_its_own_polyline() {
+        let polylines = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 0.0)),
+            LineCommand::MoveBy(vec2(0.0, 1.0)),
+            LineCommand::LineBy(vec2(-1.0, 0.0)),
+            // A lone move has no length.
+            LineCommand::MoveTo(point2(5.0, 5.0)),
+        ])
+        .flatten(0.1);
+
+        assert_eq!(polylines.len(), 2);
+        assert_eq!(
+            polylines[1].vertices(),
+            &[point2(1.0, 1.0), point2(0.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn sub_paths_without_length_are_kept_as_dots() {
+        let polylines = path(vec![
+            LineCommand::MoveTo(point2(2.0, 3.0)),
+            LineCommand::LineTo(point2(2.0, 3.0)),
+        ])
+        .flatten(0.1);
+
+        assert_eq!(polylines.len(), 1);
+        assert_eq!(
+            polylines[0].vertices(),
+            &[point2(2.0, 3.0), point2(2.0, 3.0)]
+        );
+    }
+
+    #[test]
+    fn drawing_after_close_starts_at_the_closed_sub_path_start() {
+        let polylines = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 1.0)),
+            LineCommand::Close,
+            LineCommand::LineBy(vec2(0.0, -1.0)),
+        ])
+        .flatten(0.1);
+
+        assert_eq!(polylines.len(), 2);
+        assert_eq!(
+            polylines[1].vertices(),
+            &[point2(0.0, 0.0), point2(0.0, -1.0)]
+        );
+    }
+
+    #[test]
+    fn cubics_stay_within_tolerance() {
+        let tolerance = 0.01;
+        let polylines = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::CubicBezierTo {
+                control_1: point2(0.0, 10.0),
+                control_2: point2(10.0, 10.0),
+                end: point2(10.0, 0.0),
+            },
+        ])
+        .flatten(tolerance);
+        let vertices = polylines[0].vertices();
+
+        for i in 0..=100 {
+            let t = i as f64 / 100.0;
+            let mt = 1.0 - t;
+            let p = point2(
+                3.0 * mt * t * t * 10.0 + t * t * t * 10.0,
+                3.0 * mt * mt * t * 10.0 + 3.0 * mt * t * t * 10.0,
+            );
+            assert!(distance_to_polyline(p, vertices) <= tolerance);
+        }
+        assert_eq!(vertices.last(), Some(&point2(10.0, 0.0)));
+    }
+
+    #[test]
+    fn smooth_curves_reflect_the_previous_control_point() {
+        let smooth = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::QuadraticBezierTo {
+                control: point2(5.0, 5.0),
+                end: point2(10.0, 0.0),
+            },
+            LineCommand::SmoothQuadtraticCurveBy(vec2(10.0, 0.0)),
+        ])
+        .flatten(0.01);
+        let explicit = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::QuadraticBezierTo {
+                control: point2(5.0, 5.0),
+                end: point2(10.0, 0.0),
+            },
+            LineCommand::QuadraticBezierTo {
+                control: point2(15.0, -5.0),
+                end: point2(20.0, 0.0),
+            },
+        ])
+        .flatten(0.01);
+
+        assert_eq!(smooth[0].vertices(), explicit[0].vertices());
+    }
+
+    #[test]
+    fn arcs_stay_on_the_ellipse() {
+        let tolerance = 0.001;
+        let polylines = path(vec![
+            LineCommand::MoveTo(point2(-5.0, 0.0)),
+            LineCommand::ArcTo {
+                x_radius: 5.0,
+                y_radius: 5.0,
+                x_axis_rotation: Angle::zero(),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(5.0, 0.0),
+            },
+            // A rotated ellipse, drawn relatively, back to the start.
+            LineCommand::ArcBy {
+                x_radius: 10.0,
+                y_radius: 2.0,
+                x_axis_rotation: 90.0,
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: vec2(-10.0, 0.0),
+            },
+        ])
+        .flatten(tolerance);
+        let vertices = polylines[0].vertices();
+
+        let circle_end = vertices
+            .iter()
+            .position(|v| *v == point2(5.0, 0.0))
+            .unwrap();
+        for v in &vertices[..=circle_end] {
+            assert!((v.to_vector().length() - 5.0).abs() < 1e-9);
+            // A positive sweep goes through increasing angles, which passes
+            // through negative y here.
+            assert!(v.y <= 1e-9);
+        }
+        assert!(circle_end > 10);
+        assert_eq!(vertices.last(), Some(&point2(-5.0, 0.0)));
+    }
+}
@@ -0,0 +1,136 @@
+//! Joining paths end to end.
+
+use super::{LineCommand, Path};
+
+impl<U> Path<f64, U> {
+    /// Continue this path with everything that `other` draws, without lifting
+    /// the pen in between.
+    ///
+    /// If `other` doesn't start exactly where this path ends, a straight line
+    /// is drawn to bridge the gap. Any later sub-paths of `other` are still
+    /// started with a move. The appended commands are all absolute.
+    ///
+    /// A closed sub-path of `other` that continues this path is drawn all the
+    /// way around back to its start, but isn't closed again, since closing
+    /// would draw a line back to where the joined sub-path started.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::euclid::{point2, UnknownUnit};
+    /// use fart::path::{LineCommand, Path};
+    ///
+    /// let mut path = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 0.0)),
+    /// ]);
+    /// path.connect(&Path::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(4.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 4.0)),
+    /// ]));
+    ///
+    /// assert_eq!(path.commands.len(), 3);
+    /// assert_eq!(path.length(), 8.0);
+    /// ```
+    pub fn connect(&mut self, other: &Path<f64, U>) {
+        let mut end = self.end();
+        for subpath in other.subpaths() {
+            let continues = match end.take() {
+                Some(end) if end == subpath.start => true,
+                Some(_) => {
+                    self.commands.push(LineCommand::LineTo(subpath.start));
+                    true
+                }
+                None => {
+                    self.commands.push(LineCommand::MoveTo(subpath.start));
+                    false
+                }
+            };
+            self.commands
+                .extend(subpath.segments.iter().map(|s| s.to_command()));
+            if subpath.closed && !continues {
+                self.commands.push(LineCommand::Close);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{LineCommand, Path};
+    use euclid::{point2, vec2, UnknownUnit};
+
+    #[test]
+    fn gaps_are_bridged_and_later_sub_paths_moved_to() {
+        let mut path = Path::<f64, UnknownUnit>::with_commands(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineBy(vec2(1.0, 0.0)),
+        ]);
+        path.connect(&Path::with_commands(vec![
+            LineCommand::MoveTo(point2(1.0, 1.0)),
+            LineCommand::LineBy(vec2(1.0, 0.0)),
+            LineCommand::MoveTo(point2(5.0, 5.0)),
+            LineCommand::LineBy(vec2(0.0, 1.0)),
+        ]));
+
+        let polylines = path.flatten(0.1);
+        assert_eq!(polylines.len(), 2);
+        assert_eq!(
+            polylines[0].vertices(),
+            &[
+                point2(0.0, 0.0),
+                point2(1.0, 0.0),
+                point2(1.0, 1.0),
+                point2(2.0, 1.0)
+            ]
+        );
+        assert_eq!(
+            polylines[1].vertices(),
+            &[point2(5.0, 5.0), point2(5.0, 6.0)]
+        );
+    }
+
+    #[test]
+    fn closed_sub_paths_that_continue_a_path_are_not_closed_again() {
+        let mut path = Path::<f64, UnknownUnit>::with_commands(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 0.0)),
+        ]);
+        path.connect(&Path::with_commands(vec![
+            LineCommand::MoveTo(point2(1.0, 0.0)),
+            LineCommand::LineTo(point2(2.0, 0.0)),
+            LineCommand::LineTo(point2(2.0, 1.0)),
+            LineCommand::LineTo(point2(1.0, 1.0)),
+            LineCommand::Close,
+        ]));
+
+        assert_eq!(path.length(), 5.0);
+        let polylines = path.flatten(0.1);
+        assert_eq!(polylines.len(), 1);
+        assert_eq!(polylines[0].vertices().last(), Some(&point2(1.0, 0.0)));
+    }
+
+    #[test]
+    fn closed_sub_paths_that_start_a_path_stay_closed() {
+        let mut path = Path::<f64, UnknownUnit>::new();
+        path.connect(&Path::with_commands(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 1.0)),
+            LineCommand::Close,
+        ]));
+        assert!(matches!(path.commands.last(), Some(LineCommand::Close)));
+        assert_eq!(path.length(), 2.0 + 2.0f64.sqrt());
+    }
+
+    #[test]
+    fn connecting_to_an_empty_path_moves_to_the_start() {
+        let mut path = Path::<f64, UnknownUnit>::new();
+        path.connect(&Path::with_commands(vec![
+            LineCommand::MoveTo(point2(3.0, 3.0)),
+            LineCommand::LineTo(point2(4.0, 3.0)),
+        ]));
+        assert_eq!(path.start(), Some(point2(3.0, 3.0)));
+        assert_eq!(path.length(), 1.0);
+    }
+}
@@ -0,0 +1,420 @@
+//! Measuring lengths along paths, and finding points and tangents at given
+//! distances along them.
+
+use super::segment::{Arc, Segment};
+use super::Path;
+use euclid::{vec2, Point2D, Vector2D};
+
+impl<U> Path<f64, U> {
+    /// Get the total length of all of this path's lines, curves, and arcs.
+    ///
+    /// Moves don't draw anything, so they don't count towards the length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::euclid::{point2, vec2, UnknownUnit};
+    /// use fart::path::{LineCommand, Path};
+    ///
+    /// let square = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 0.0)),
+    ///     LineCommand::LineBy(vec2(2.0, 0.0)),
+    ///     LineCommand::LineBy(vec2(0.0, 2.0)),
+    ///     LineCommand::LineBy(vec2(-2.0, 0.0)),
+    ///     LineCommand::Close,
+    /// ]);
+    ///
+    /// assert_eq!(square.length(), 8.0);
+    /// ```
+    pub fn length(&self) -> f64 {
+        self.subpaths()
+            .iter()
+            .flat_map(|s| s.segments.iter())
+            .map(|s| s.length())
+            .sum()
+    }
+
+    /// Get the point that is `distance` along this path.
+    ///
+    /// The distance is clamped to the range `0.0..=self.length()`. Returns
+    /// `None` if this path doesn't draw anything.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::euclid::{point2, UnknownUnit};
+    /// use fart::path::{LineCommand, Path};
+    ///
+    /// let path = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 4.0)),
+    /// ]);
+    ///
+    /// assert_eq!(path.point_at(6.0), Some(point2(4.0, 2.0)));
+    /// ```
+    pub fn point_at(&self, distance: f64) -> Option<Point2D<f64, U>> {
+        self.locate(distance).map(|(segment, t)| segment.point(t))
+    }
+
+    /// Get the unit tangent vector, pointing in the path's direction, at the
+    /// point that is `distance` along this path.
+    ///
+    /// The distance is clamped to the range `0.0..=self.length()`. Returns
+    /// `None` if this path doesn't draw anything, or only draws dots, which
+    /// have no direction.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::euclid::{point2, vec2, UnknownUnit};
+    /// use fart::path::{LineCommand, Path};
+    ///
+    /// let path = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 4.0)),
+    /// ]);
+    ///
+    /// assert_eq!(path.tangent_at(6.0), Some(vec2(0.0, 1.0)));
+    /// ```
+    pub fn tangent_at(&self, distance: f64) -> Option<Vector2D<f64, U>> {
+        self.locate(distance)
+            .filter(|(segment, _)| segment.length() > 0.0)
+            .map(|(segment, t)| segment.tangent(t))
+    }
+
+    /// Like `point_at`, but takes a normalized `t` in `0.0..=1.0` where `0.0`
+    /// is the start of this path and `1.0` its end.
+    pub fn point_at_t(&self, t: f64) -> Option<Point2D<f64, U>> {
+        self.point_at(t * self.length())
+    }
+
+    /// Like `tangent_at`, but takes a normalized `t` in `0.0..=1.0` where
+    /// `0.0` is the start of this path and `1.0` its end.
+    pub fn tangent_at_t(&self, t: f64) -> Option<Vector2D<f64, U>> {
+        self.tangent_at(t * self.length())
+    }
+
+    /// Find the segment that is `distance` along this path, and the parameter
+    /// within that segment.
+    ///
+    /// Segments of zero length are skipped, since they have no direction,
+    /// unless the path has nothing else.
+    fn locate(&self, distance: f64) -> Option<(Segment<U>, f64)> {
+        let mut remaining = distance.max(0.0);
+        let mut last = None;
+        for segment in self.subpaths().into_iter().flat_map(|s| s.segments) {
+            let length = segment.length();
+            if length == 0.0 {
+                last = last.or(Some(segment));
+                continue;
+            }
+            if remaining <= length {
+                let t = segment.t_at_length(remaining);
+                return Some((segment, t));
+            }
+            remaining -= length;
+            last = Some(segment);
+        }
+        last.map(|segment| (segment, 1.0))
+    }
+}
+
+/// How precisely lengths are computed, relative to the segment's length.
+const RELATIVE_PRECISION: f64 = 1e-9;
+
+impl<U> Segment<U> {
+    /// The arc length of this segment.
+    pub(crate) fn length(&self) -> f64 {
+        self.length_to(1.0)
+    }
+
+    /// The arc length of this segment from its start up to parameter `t`.
+    pub(crate) fn length_to(&self, t: f64) -> f64 {
+        match *self {
+            Segment::Line { from, to } => (to - from).length() * t,
+            Segment::Arc(Arc {
+                x_radius,
+                y_radius,
+                sweep_angle,
+                ..
+            }) if x_radius == y_radius => x_radius * sweep_angle.abs() * t,
+            _ => {
+                let speed = |t| self.derivative(t).length();
+                let estimate = gauss_legendre(&speed, 0.0, t);
+                adaptive_gauss_legendre(&speed, 0.0, t, estimate, 16)
+            }
+        }
+    }
+
+    /// Find the parameter `t` at which this segment's arc length from its
+    /// start is `length`.
+    pub(crate) fn t_at_length(&self, length: f64) -> f64 {
+        let total = self.length();
+        if total == 0.0 || length <= 0.0 {
+            return 0.0;
+        }
+        if length >= total {
+            return 1.0;
+        }
+        if let Segment::Line { .. } = *self {
+            return length / total;
+        }
+
+        // Newton's method, falling back to bisection whenever a step would
+        // leave the bracket around the solution.
+        let (mut low, mut high) = (0.0, 1.0);
+        let mut t = length / total;
+        for _ in 0..64 {
+            let error = self.length_to(t) - length;
+            if error.abs() <= total * RELATIVE_PRECISION {
+                break;
+            }
+            if error > 0.0 {
+                high = t;
+            } else {
+                low = t;
+            }
+            let speed = self.derivative(t).length();
+            let next = t - error / speed;
+            t = if speed > 0.0 && low < next && next < high {
+                next
+            } else {
+                (low + high) / 2.0
+            };
+        }
+        t
+    }
+
+    /// The derivative of this segment with respect to `t` at parameter `t`.
+    pub(crate) fn derivative(&self, t: f64) -> Vector2D<f64, U> {
+        match *self {
+            Segment::Line { from, to } => to - from,
+            Segment::Quadratic { from, control, to } => {
+                (control - from) * (2.0 * (1.0 - t)) + (to - control) * (2.0 * t)
+            }
+            Segment::Cubic {
+                from,
+                control_1,
+                control_2,
+                to,
+            } => {
+                let mt = 1.0 - t;
+                (control_1 - from) * (3.0 * mt * mt)
+                    + (control_2 - control_1) * (6.0 * mt * t)
+                    + (to - control_2) * (3.0 * t * t)
+            }
+            Segment::Arc(ref arc) => {
+                let (sin_phi, cos_phi) = arc.x_axis_rotation.sin_cos();
+                let (sin, cos) = (arc.start_angle + arc.sweep_angle * t).sin_cos();
+                let x = -arc.x_radius * sin * arc.sweep_angle;
+                let y = arc.y_radius * cos * arc.sweep_angle;
+                vec2(cos_phi * x - sin_phi * y, sin_phi * x + cos_phi * y)
+            }
+        }
+    }
+
+    /// The unit tangent of this segment at parameter `t`.
+    pub(crate) fn tangent(&self, t: f64) -> Vector2D<f64, U> {
+        let d = self.derivative(t);
+        if d.square_length() > 0.0 {
+            return d.normalize();
+        }
+
+        // The derivative vanishes where a control point coincides with an end
+        // point. Look at the direction the curve is heading in just next to
+        // `t` instead.
+        const NUDGE: f64 = 1e-6;
+        let (a, b) = if t < 0.5 {
+            (self.point(t), self.point(t + NUDGE))
+        } else {
+            (self.point(t - NUDGE), self.point(t))
+        };
+        let d = b - a;
+        if d.square_length() > 0.0 {
+            d.normalize()
+        } else {
+            (self.to() - self.from()).normalize()
+        }
+    }
+}
+
+/// Integrate `f` over `a..b` with five point Gauss-Legendre quadrature.
+fn gauss_legendre(f: &dyn Fn(f64) -> f64, a: f64, b: f64) -> f64 {
+    const NODES: [(f64, f64); 5] = [
+        (0.0, 0.568_888_888_888_888_9),
+        (-0.538_469_310_105_683_1, 0.478_628_670_499_366_47),
+        (0.538_469_310_105_683_1, 0.478_628_670_499_366_47),
+        (-0.906_179_845_938_664, 0.236_926_885_056_189_08),
+        (0.906_179_845_938_664, 0.236_926_885_056_189_08),
+    ];
+    let half = (b - a) / 2.0;
+    let mid = (a + b) / 2.0;
+    half * NODES
+        .iter()
+        .map(|&(x, w)| w * f(mid + half * x))
+        .sum::<f64>()
+}
+
+/// Recursively split `a..b` in half until the halves agree with the estimate
+/// for the whole interval.
+fn adaptive_gauss_legendre(
+    f: &dyn Fn(f64) -> f64,
+    a: f64,
+    b: f64,
+    estimate: f64,
+    depth: usize,
+) -> f64 {
+    let mid = (a + b) / 2.0;
+    let left = gauss_legendre(f, a, mid);
+    let right = gauss_legendre(f, mid, b);
+    if depth == 0 || (left + right - estimate).abs() <= RELATIVE_PRECISION * estimate.abs() {
+        left + right
+    } else {
+        adaptive_gauss_legendre(f, a, mid, left, depth - 1)
+            + adaptive_gauss_legendre(f, mid, b, right, depth - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{LineCommand, Path};
+    use euclid::{point2, vec2, Angle, UnknownUnit};
+    use std::f64::consts::PI;
+
+    fn path(commands: Vec<LineCommand<f64, UnknownUnit>>) -> Path<f64, UnknownUnit> {
+        Path::with_commands(commands)
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn moves_do_not_count_towards_length() {
+        let p = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(3.0, 4.0)),
+            LineCommand::MoveBy(vec2(100.0, 0.0)),
+            LineCommand::HorizontalLineBy(-1.0),
+        ]);
+        assert_eq!(p.length(), 6.0);
+        assert_eq!(p.point_at(5.5), Some(point2(102.5, 4.0)));
+        assert_eq!(p.tangent_at(5.5), Some(vec2(-1.0, 0.0)));
+    }
+
+    #[test]
+    fn zero_length_segments_have_no_tangent_of_their_own() {
+        let p = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 0.0)),
+            LineCommand::LineTo(point2(4.0, 3.0)),
+        ]);
+        assert_eq!(p.tangent_at(0.0), Some(vec2(1.0, 0.0)));
+        assert_eq!(p.tangent_at(4.0), Some(vec2(1.0, 0.0)));
+        assert_eq!(p.tangent_at(4.5), Some(vec2(0.0, 1.0)));
+        assert_eq!(p.point_at(0.0), Some(point2(0.0, 0.0)));
+
+        let dot = path(vec![
+            LineCommand::MoveTo(point2(1.0, 1.0)),
+            LineCommand::LineTo(point2(1.0, 1.0)),
+        ]);
+        assert_eq!(dot.point_at(0.0), Some(point2(1.0, 1.0)));
+        assert_eq!(dot.tangent_at(0.0), None);
+    }
+
+    #[test]
+    fn close_adds_the_closing_line() {
+        let p = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(3.0, 0.0)),
+            LineCommand::LineTo(point2(3.0, 4.0)),
+            LineCommand::Close,
+        ]);
+        assert_eq!(p.length(), 12.0);
+        assert_eq!(p.point_at_t(1.0), Some(point2(0.0, 0.0)));
+        let tangent = p.tangent_at(10.0).unwrap();
+        assert_close(tangent.x, -0.6);
+        assert_close(tangent.y, -0.8);
+    }
+
+    #[test]
+    fn circular_arcs() {
+        let p = path(vec![
+            LineCommand::MoveTo(point2(1.0, 0.0)),
+            LineCommand::ArcTo {
+                x_radius: 1.0,
+                y_radius: 1.0,
+                x_axis_rotation: Angle::zero(),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(-1.0, 0.0),
+            },
+        ]);
+        assert_close(p.length(), PI);
+
+        let mid = p.point_at_t(0.5).unwrap();
+        assert_close(mid.x, 0.0);
+        assert_close(mid.y, 1.0);
+
+        let tangent = p.tangent_at_t(0.5).unwrap();
+        assert_close(tangent.x, -1.0);
+        assert_close(tangent.y, 0.0);
+    }
+
+    #[test]
+    fn cubic_length_matches_fine_flattening() {
+        let p = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::CubicBezierBy {
+                control_1: vec2(0.0, 10.0),
+                control_2: vec2(10.0, 10.0),
+                end: vec2(10.0, 0.0),
+            },
+            LineCommand::SmoothCubicBezierBy {
+                control: vec2(10.0, -10.0),
+                end: vec2(10.0, 0.0),
+            },
+        ]);
+
+        let flattened: f64 = p
+            .flatten(1e-7)
+            .iter()
+            .flat_map(|l| {
+                l.vertices()
+                    .windows(2)
+                    .map(|w| (w[1] - w[0]).length())
+                    .collect::<Vec<_>>()
+            })
+            .sum();
+        assert!((p.length() - flattened).abs() < 1e-5);
+
+        // By symmetry, the middle of the path is where the two curves meet.
+        let mid = p.point_at(p.length() / 2.0).unwrap();
+        assert_close(mid.x, 10.0);
+        assert_close(mid.y, 0.0);
+    }
+
+    #[test]
+    fn cusps_still_have_tangents() {
+        let p = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::QuadraticBezierTo {
+                control: point2(0.0, 0.0),
+                end: point2(0.0, 5.0),
+            },
+        ]);
+        assert_eq!(p.tangent_at(0.0), Some(vec2(0.0, 1.0)));
+    }
+
+    #[test]
+    fn empty_paths_have_no_points() {
+        let p = path(vec![LineCommand::MoveTo(point2(1.0, 1.0))]);
+        assert_eq!(p.length(), 0.0);
+        assert_eq!(p.point_at(0.0), None);
+        assert_eq!(p.tangent_at_t(0.5), None);
+    }
+}
@@ -0,0 +1,165 @@
+//! Parsing SVG path data into paths.
+
+use super::{LineCommand, Path};
+use euclid::{point2, vec2, Angle};
+use svg::node::element::path::{Command, Data, Position};
+
+impl<U> Path<f64, U> {
+    /// Parse SVG path data, the contents of a `<path>` element's `d`
+    /// attribute, into a path.
+    ///
+    /// Every SVG path command becomes the matching `LineCommand`, keeping
+    /// relative commands relative, so the path draws exactly what the data
+    /// does. Coordinates are taken as they are, without any transform.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::canvas::CanvasSpace;
+    /// use fart::path::{LineCommand, Path};
+    ///
+    /// let path = Path::<f64, CanvasSpace>::from_svg_data("M 0,0 L 10,0 v 10 z").unwrap();
+    /// assert_eq!(path.commands.len(), 4);
+    /// assert!(matches!(path.commands[2], LineCommand::VerticalLineBy(y) if y == 10.0));
+    /// ```
+    pub fn from_svg_data(data: &str) -> crate::Result<Path<f64, U>> {
+        let data =
+            Data::parse(data).map_err(|e| failure::format_err!("invalid path data: {}", e))?;
+
+        let mut commands = vec![];
+        for command in data.iter() {
+            let (position, parameters, arity) = match command {
+                Command::Move(p, params) | Command::Line(p, params) => (p, params, 2),
+                Command::HorizontalLine(p, params) | Command::VerticalLine(p, params) => {
+                    (p, params, 1)
+                }
+                Command::QuadraticCurve(p, params) | Command::SmoothCubicCurve(p, params) => {
+                    (p, params, 4)
+                }
+                Command::SmoothQuadraticCurve(p, params) => (p, params, 2),
+                Command::CubicCurve(p, params) => (p, params, 6),
+                Command::EllipticalArc(p, params) => (p, params, 7),
+                Command::Close => {
+                    commands.push(LineCommand::Close);
+                    continue;
+                }
+            };
+            let absolute = *position == Position::Absolute;
+            if parameters.is_empty() || parameters.len() % arity != 0 {
+                failure::bail!(
+                    "path command has {} parameters, which is not a multiple of {}",
+                    parameters.len(),
+                    arity
+                );
+            }
+
+            let parameters: Vec<f64> = parameters.iter().map(|&p| f64::from(p)).collect();
+            for (i, p) in parameters.chunks(arity).enumerate() {
+                commands.push(match (command, absolute) {
+                    // Coordinates after the first pair of a move are lines.
+                    (Command::Move(..), true) if i == 0 => LineCommand::MoveTo(point2(p[0], p[1])),
+                    (Command::Move(..), false) if i == 0 => LineCommand::MoveBy(vec2(p[0], p[1])),
+                    (Command::Move(..), true) | (Command::Line(..), true) => {
+                        LineCommand::LineTo(point2(p[0], p[1]))
+                    }
+                    (Command::Move(..), false) | (Command::Line(..), false) => {
+                        LineCommand::LineBy(vec2(p[0], p[1]))
+                    }
+                    (Command::HorizontalLine(..), true) => LineCommand::HorizontalLineTo(p[0]),
+                    (Command::HorizontalLine(..), false) => LineCommand::HorizontalLineBy(p[0]),
+                    (Command::VerticalLine(..), true) => LineCommand::VerticalLineTo(p[0]),
+                    (Command::VerticalLine(..), false) => LineCommand::VerticalLineBy(p[0]),
+                    (Command::QuadraticCurve(..), true) => LineCommand::QuadraticBezierTo {
+                        control: point2(p[0], p[1]),
+                        end: point2(p[2], p[3]),
+                    },
+                    (Command::QuadraticCurve(..), false) => LineCommand::QuadraticBezierBy {
+                        control: vec2(p[0], p[1]),
+                        end: vec2(p[2], p[3]),
+                    },
+                    (Command::SmoothQuadraticCurve(..), true) => {
+                        LineCommand::SmoothQuadtraticCurveTo(point2(p[0], p[1]))
+                    }
+                    (Command::SmoothQuadraticCurve(..), false) => {
+                        LineCommand::SmoothQuadtraticCurveBy(vec2(p[0], p[1]))
+                    }
+                    (Command::CubicCurve(..), true) => LineCommand::CubicBezierTo {
+                        control_1: point2(p[0], p[1]),
+                        control_2: point2(p[2], p[3]),
+                        end: point2(p[4], p[5]),
+                    },
+                    (Command::CubicCurve(..), false) => LineCommand::CubicBezierBy {
+                        control_1: vec2(p[0], p[1]),
+                        control_2: vec2(p[2], p[3]),
+                        end: vec2(p[4], p[5]),
+                    },
+                    (Command::SmoothCubicCurve(..), true) => LineCommand::SmoothCubicBezierTo {
+                        control: point2(p[0], p[1]),
+                        end: point2(p[2], p[3]),
+                    },
+                    (Command::SmoothCubicCurve(..), false) => LineCommand::SmoothCubicBezierBy {
+                        control: vec2(p[0], p[1]),
+                        end: vec2(p[2], p[3]),
+                    },
+                    (Command::EllipticalArc(..), true) => LineCommand::ArcTo {
+                        x_radius: p[0],
+                        y_radius: p[1],
+                        x_axis_rotation: Angle::degrees(p[2]),
+                        large_arc_flag: p[3] != 0.0,
+                        sweep_flag: p[4] != 0.0,
+                        end: point2(p[5], p[6]),
+                    },
+                    (Command::EllipticalArc(..), false) => LineCommand::ArcBy {
+                        x_radius: p[0],
+                        y_radius: p[1],
+                        x_axis_rotation: p[2],
+                        large_arc_flag: p[3] != 0.0,
+                        sweep_flag: p[4] != 0.0,
+                        end: vec2(p[5], p[6]),
+                    },
+                    (Command::Close, _) => unreachable!(),
+                });
+            }
+        }
+        Ok(Path::with_commands(commands))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::CanvasSpace;
+
+    fn parse(data: &str) -> Path<f64, CanvasSpace> {
+        Path::from_svg_data(data).unwrap()
+    }
+
+    #[test]
+    fn every_command_round_trips() {
+        let data = "M1,2 L3,4 l1,1 H10 h-1 V5 v-2.5 Q1,1 2,0 q1,1 2,0 T3,3 t1,1 \
+                    C1,2 3,4 5,6 c1,2 3,4 5,6 S1,2 3,4 s1,2 3,4 \
+                    A5,5 30 0 1 10,10 a5,5 30 1 0 10,10 z";
+        let path = parse(data);
+        assert_eq!(path.commands.len(), 18);
+
+        // Writing the path back out gives the same data.
+        let element: svg::node::element::Path = (&path).into();
+        let expected: svg::node::Value = Data::parse(data).unwrap().into();
+        assert!(element.to_string().contains(&format!("d=\"{}\"", expected)));
+    }
+
+    #[test]
+    fn extra_move_coordinates_are_lines() {
+        let path = parse("m1,1 2,2 3,3");
+        assert!(matches!(path.commands[0], LineCommand::MoveBy(_)));
+        assert!(matches!(path.commands[1], LineCommand::LineBy(_)));
+        assert!(matches!(path.commands[2], LineCommand::LineBy(_)));
+        assert_eq!(path.end(), Some(point2(6.0, 6.0)));
+    }
+
+    #[test]
+    fn missing_parameters_are_errors() {
+        assert!(Path::<f64, CanvasSpace>::from_svg_data("M1,2 L3").is_err());
+        assert!(Path::<f64, CanvasSpace>::from_svg_data("C1,2 3,4").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Reversing paths, and finding where they start and end.
+
+use super::segment::{Arc, Segment};
+use super::{LineCommand, Path};
+use euclid::Point2D;
+
+impl<U> Path<f64, U> {
+    /// Get the point where this path starts drawing.
+    ///
+    /// Returns `None` if this path doesn't draw anything.
+    pub fn start(&self) -> Option<Point2D<f64, U>> {
+        self.subpaths().first().map(|s| s.start)
+    }
+
+    /// Get the point where this path stops drawing.
+    ///
+    /// Returns `None` if this path doesn't draw anything.
+    pub fn end(&self) -> Option<Point2D<f64, U>> {
+        self.subpaths()
+            .last()
+            .and_then(|s| s.segments.last())
+            .map(|s| s.to())
+    }
+
+    /// Get a path that draws the same lines and curves as this one, but in the
+    /// opposite direction.
+    ///
+    /// Sub-paths are drawn in reverse order, and each is drawn from its end to
+    /// its start. Closed sub-paths stay closed. The commands of the reversed
+    /// path are all absolute.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fart::euclid::{point2, UnknownUnit};
+    /// use fart::path::{LineCommand, Path};
+    ///
+    /// let path = Path::<f64, UnknownUnit>::with_commands(vec![
+    ///     LineCommand::MoveTo(point2(0.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 0.0)),
+    ///     LineCommand::LineTo(point2(4.0, 4.0)),
+    /// ]);
+    ///
+    /// let reversed = path.reversed();
+    /// assert_eq!(reversed.start(), Some(point2(4.0, 4.0)));
+    /// assert_eq!(reversed.end(), Some(point2(0.0, 0.0)));
+    /// ```
+    pub fn reversed(&self) -> Path<f64, U> {
+        let mut commands = vec![];
+        for subpath in self.subpaths().iter().rev() {
+            let end = subpath.segments.last().unwrap().to();
+            commands.push(LineCommand::MoveTo(end));
+            commands.extend(
+                subpath
+                    .segments
+                    .iter()
+                    .rev()
+                    .map(|s| s.reversed().to_command()),
+            );
+            if subpath.closed {
+                commands.push(LineCommand::Close);
+            }
+        }
+        Path::with_commands(commands)
+    }
+}
+
+impl<U> Segment<U> {
+    /// Get this segment drawn in the opposite direction.
+    fn reversed(&self) -> Segment<U> {
+        match *self {
+            Segment::Line { from, to } => Segment::Line { from: to, to: from },
+            Segment::Quadratic { from, control, to } => Segment::Quadratic {
+                from: to,
+                control,
+                to: from,
+            },
+            Segment::Cubic {
+                from,
+                control_1,
+                control_2,
+                to,
+            } => Segment::Cubic {
+                from: to,
+                control_1: control_2,
+                control_2: control_1,
+                to: from,
+            },
+            Segment::Arc(ref arc) => Segment::Arc(Arc {
+                start_angle: arc.start_angle + arc.sweep_angle,
+                sweep_angle: -arc.sweep_angle,
+                from: arc.to,
+                to: arc.from,
+                ..*arc
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{LineCommand, Path};
+    use euclid::{point2, vec2, Angle, UnknownUnit};
+
+    fn path(commands: Vec<LineCommand<f64, UnknownUnit>>) -> Path<f64, UnknownUnit> {
+        Path::with_commands(commands)
+    }
+
+    #[test]
+    fn reversed_paths_trace_the_same_points_backwards() {
+        let original = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineBy(vec2(4.0, 0.0)),
+            LineCommand::QuadraticBezierTo {
+                control: point2(8.0, 0.0),
+                end: point2(8.0, 4.0),
+            },
+            LineCommand::CubicBezierTo {
+                control_1: point2(8.0, 8.0),
+                control_2: point2(4.0, 8.0),
+                end: point2(4.0, 6.0),
+            },
+            LineCommand::ArcTo {
+                x_radius: 2.0,
+                y_radius: 2.0,
+                x_axis_rotation: Angle::zero(),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(0.0, 6.0),
+            },
+        ]);
+        let reversed = original.reversed();
+
+        let length = original.length();
+        assert!((reversed.length() - length).abs() < 1e-9);
+        for i in 0..=20 {
+            let d = length * f64::from(i) / 20.0;
+            let a = original.point_at(d).unwrap();
+            let b = reversed.point_at(length - d).unwrap();
+            assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn reversed_sub_paths_come_in_reverse_order_and_stay_closed() {
+        let original = path(vec![
+            LineCommand::MoveTo(point2(0.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 0.0)),
+            LineCommand::LineTo(point2(1.0, 1.0)),
+            LineCommand::Close,
+            LineCommand::MoveTo(point2(5.0, 5.0)),
+            LineCommand::LineTo(point2(6.0, 5.0)),
+        ]);
+        let reversed = original.reversed();
+
+        assert_eq!(reversed.start(), Some(point2(6.0, 5.0)));
+        assert_eq!(reversed.end(), Some(point2(0.0, 0.0)));
+        let polylines = reversed.flatten(0.1);
+        assert_eq!(polylines.len(), 2);
+        assert_eq!(
+            polylines[1].vertices(),
+            &[
+                point2(0.0, 0.0),
+                point2(1.0, 1.0),
+                point2(1.0, 0.0),
+                point2(0.0, 0.0)
+            ]
+        );
+        assert!(matches!(reversed.commands.last(), Some(LineCommand::Close)));
+    }
+}
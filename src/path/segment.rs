@@ -0,0 +1,531 @@
+//! Resolving a path's line commands into absolute segments.
+//!
+//! Line commands are convenient to write, but relative commands, smooth
+//! curves, and `Close` all depend on the commands that came before them. This
+//! module walks a path once and resolves every command into a self-contained
+//! `Segment` with absolute coordinates, which is what all of the geometric
+//! operations on paths (flattening, measuring, ...) actually want.
+
+use super::{LineCommand, Path};
+use euclid::{point2, vec2, Point2D};
+use std::f64::consts::PI;
+
+/// A single piece of a path with all of its points resolved to absolute
+/// coordinates.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Segment<U> {
+    /// A straight line.
+    Line {
+        from: Point2D<f64, U>,
+        to: Point2D<f64, U>,
+    },
+    /// A quadratic bezier curve.
+    Quadratic {
+        from: Point2D<f64, U>,
+        control: Point2D<f64, U>,
+        to: Point2D<f64, U>,
+    },
+    /// A cubic bezier curve.
+    Cubic {
+        from: Point2D<f64, U>,
+        control_1: Point2D<f64, U>,
+        control_2: Point2D<f64, U>,
+        to: Point2D<f64, U>,
+    },
+    /// An elliptical arc in center parameterization.
+    Arc(Arc<U>),
+}
+
+/// An elliptical arc in center parameterization.
+///
+/// See https://www.w3.org/TR/SVG/implnote.html#ArcImplementationNotes
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Arc<U> {
+    pub center: Point2D<f64, U>,
+    pub x_radius: f64,
+    pub y_radius: f64,
+    /// The rotation of the ellipse's x axis, in radians.
+    pub x_axis_rotation: f64,
+    /// The angle at which the arc starts, in radians.
+    pub start_angle: f64,
+    /// The angle the arc sweeps through, in radians. Negative angles sweep
+    /// clockwise.
+    pub sweep_angle: f64,
+    /// The exact start and end points, so that rounding errors don't leave
+    /// gaps between this arc and its neighboring segments.
+    pub from: Point2D<f64, U>,
+    pub to: Point2D<f64, U>,
+}
+
+/// A run of connected segments, started by a move command.
+#[derive(Clone, Debug)]
+pub(crate) struct SubPath<U> {
+    pub start: Point2D<f64, U>,
+    pub segments: Vec<Segment<U>>,
+    /// Whether this sub-path was ended by a `Close` command. If so, the last
+    /// segment leads back to `start`.
+    pub closed: bool,
+}
+
+impl<U> Segment<U> {
+    /// The point where this segment starts.
+    pub fn from(&self) -> Point2D<f64, U> {
+        match *self {
+            Segment::Line { from, .. }
+            | Segment::Quadratic { from, .. }
+            | Segment::Cubic { from, .. } => from,
+            Segment::Arc(ref arc) => arc.from,
+        }
+    }
+
+    /// The point where this segment ends.
+    pub fn to(&self) -> Point2D<f64, U> {
+        match *self {
+            Segment::Line { to, .. }
+            | Segment::Quadratic { to, .. }
+            | Segment::Cubic { to, .. } => to,
+            Segment::Arc(ref arc) => arc.to,
+        }
+    }
+
+    /// The point on this segment at parameter `t` in `0.0..=1.0`.
+    pub fn point(&self, t: f64) -> Point2D<f64, U> {
+        match *self {
+            Segment::Line { from, to } => from.lerp(to, t),
+            Segment::Quadratic { from, control, to } => {
+                let mt = 1.0 - t;
+                weighted(&[(from, mt * mt), (control, 2.0 * mt * t), (to, t * t)])
+            }
+            Segment::Cubic {
+                from,
+                control_1,
+                control_2,
+                to,
+            } => {
+                let mt = 1.0 - t;
+                weighted(&[
+                    (from, mt * mt * mt),
+                    (control_1, 3.0 * mt * mt * t),
+                    (control_2, 3.0 * mt * t * t),
+                    (to, t * t * t),
+                ])
+            }
+            Segment::Arc(ref arc) => {
+                if t <= 0.0 {
+                    arc.from
+                } else if t >= 1.0 {
+                    arc.to
+                } else {
+                    arc.point(t)
+                }
+            }
+        }
+    }
+}
+
+impl<U> Segment<U> {
+    /// Get the part of this segment between parameters `t0` and `t1`.
+    pub fn slice(&self, t0: f64, t1: f64) -> Segment<U> {
+        let from = self.point(t0);
+        let to = self.point(t1);
+        match *self {
+            Segment::Line { .. } => Segment::Line { from, to },
+            Segment::Quadratic {
+                from: p0,
+                control: p1,
+                to: p2,
+            } => {
+                // The control point of the slice is the curve's blossom at
+                // `(t0, t1)`.
+                let a = p0.lerp(p1, t0);
+                let b = p1.lerp(p2, t0);
+                Segment::Quadratic {
+                    from,
+                    control: a.lerp(b, t1),
+                    to,
+                }
+            }
+            Segment::Cubic {
+                from: p0,
+                control_1: p1,
+                control_2: p2,
+                to: p3,
+            } => {
+                let blossom = |u: f64, v: f64, w: f64| {
+                    let a = [p0.lerp(p1, u), p1.lerp(p2, u), p2.lerp(p3, u)];
+                    let b = [a[0].lerp(a[1], v), a[1].lerp(a[2], v)];
+                    b[0].lerp(b[1], w)
+                };
+                Segment::Cubic {
+                    from,
+                    control_1: blossom(t0, t0, t1),
+                    control_2: blossom(t0, t1, t1),
+                    to,
+                }
+            }
+            Segment::Arc(ref arc) => Segment::Arc(Arc {
+                start_angle: arc.start_angle + arc.sweep_angle * t0,
+                sweep_angle: arc.sweep_angle * (t1 - t0),
+                from,
+                to,
+                ..*arc
+            }),
+        }
+    }
+
+    /// Get the absolute line command that draws this segment from its start
+    /// point.
+    pub fn to_command(&self) -> LineCommand<f64, U> {
+        match *self {
+            Segment::Line { to, .. } => LineCommand::LineTo(to),
+            Segment::Quadratic { control, to, .. } => {
+                LineCommand::QuadraticBezierTo { control, end: to }
+            }
+            Segment::Cubic {
+                control_1,
+                control_2,
+                to,
+                ..
+            } => LineCommand::CubicBezierTo {
+                control_1,
+                control_2,
+                end: to,
+            },
+            Segment::Arc(ref arc) => LineCommand::ArcTo {
+                x_radius: arc.x_radius,
+                y_radius: arc.y_radius,
+                x_axis_rotation: euclid::Angle::radians(arc.x_axis_rotation),
+                large_arc_flag: arc.sweep_angle.abs() > PI,
+                sweep_flag: arc.sweep_angle > 0.0,
+                end: arc.to,
+            },
+        }
+    }
+}
+
+impl<U> Arc<U> {
+    /// Convert an SVG style endpoint parameterized arc into center
+    /// parameterization.
+    ///
+    /// Returns `None` if the arc degenerates into a straight line, which
+    /// happens when a radius is zero or the end points coincide.
+    pub fn from_endpoints(
+        from: Point2D<f64, U>,
+        to: Point2D<f64, U>,
+        x_radius: f64,
+        y_radius: f64,
+        x_axis_rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+    ) -> Option<Arc<U>> {
+        let mut rx = x_radius.abs();
+        let mut ry = y_radius.abs();
+        if rx == 0.0 || ry == 0.0 || from == to {
+            return None;
+        }
+
+        let (sin_phi, cos_phi) = x_axis_rotation.sin_cos();
+
+        // Step 1: compute (x1', y1'), the start point in the ellipse's
+        // coordinate system, centered on the chord's midpoint.
+        let dx = (from.x - to.x) / 2.0;
+        let dy = (from.y - to.y) / 2.0;
+        let x1 = cos_phi * dx + sin_phi * dy;
+        let y1 = -sin_phi * dx + cos_phi * dy;
+
+        // Scale the radii up if they are too small to span the end points.
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            let s = lambda.sqrt();
+            rx *= s;
+            ry *= s;
+        }
+
+        // Step 2: compute (cx', cy').
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cx1 = coefficient * rx * y1 / ry;
+        let cy1 = -coefficient * ry * x1 / rx;
+
+        // Step 3: compute (cx, cy) from (cx', cy').
+        let center = point2(
+            cos_phi * cx1 - sin_phi * cy1 + (from.x + to.x) / 2.0,
+            sin_phi * cx1 + cos_phi * cy1 + (from.y + to.y) / 2.0,
+        );
+
+        // Step 4: compute the start angle and the sweep angle.
+        let angle =
+            |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        let ux = (x1 - cx1) / rx;
+        let uy = (y1 - cy1) / ry;
+        let vx = (-x1 - cx1) / rx;
+        let vy = (-y1 - cy1) / ry;
+        let start_angle = angle(1.0, 0.0, ux, uy);
+        let mut sweep_angle = angle(ux, uy, vx, vy);
+        if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        } else if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        }
+
+        Some(Arc {
+            center,
+            x_radius: rx,
+            y_radius: ry,
+            x_axis_rotation,
+            start_angle,
+            sweep_angle,
+            from,
+            to,
+        })
+    }
+
+    /// The point on this arc at parameter `t` in `0.0..=1.0`.
+    pub fn point(&self, t: f64) -> Point2D<f64, U> {
+        let (sin_phi, cos_phi) = self.x_axis_rotation.sin_cos();
+        let (sin, cos) = (self.start_angle + self.sweep_angle * t).sin_cos();
+        let x = self.x_radius * cos;
+        let y = self.y_radius * sin;
+        point2(
+            self.center.x + cos_phi * x - sin_phi * y,
+            self.center.y + sin_phi * x + cos_phi * y,
+        )
+    }
+}
+
+fn weighted<U>(points: &[(Point2D<f64, U>, f64)]) -> Point2D<f64, U> {
+    let (x, y) = points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), &(p, w)| (x + p.x * w, y + p.y * w));
+    point2(x, y)
+}
+
+impl<U> Path<f64, U> {
+    /// Resolve this path's line commands into sub-paths of absolute segments.
+    ///
+    /// Sub-paths that consist of nothing but a move are not included.
+    pub(crate) fn subpaths(&self) -> Vec<SubPath<U>> {
+        let mut subpaths = vec![];
+        let mut current: Option<SubPath<U>> = None;
+        let mut position = point2(0.0, 0.0);
+
+        // The control point to reflect for a following smooth cubic or
+        // quadratic curve, if the previous command was of the matching kind.
+        let mut last_cubic_control = None;
+        let mut last_quadratic_control = None;
+
+        let mut finish = |current: &mut Option<SubPath<U>>| {
+            if let Some(subpath) = current.take() {
+                if !subpath.segments.is_empty() {
+                    subpaths.push(subpath);
+                }
+            }
+        };
+
+        for command in &self.commands {
+            let mut cubic_control = None;
+            let mut quadratic_control = None;
+
+            let segment = match *command {
+                LineCommand::MoveTo(p) => {
+                    finish(&mut current);
+                    position = p;
+                    None
+                }
+                LineCommand::MoveBy(v) => {
+                    finish(&mut current);
+                    position += v;
+                    None
+                }
+                LineCommand::LineTo(p) => Some(line(position, p)),
+                LineCommand::LineBy(v) => Some(line(position, position + v)),
+                LineCommand::HorizontalLineTo(x) => Some(line(position, point2(x, position.y))),
+                LineCommand::HorizontalLineBy(dx) => Some(line(position, position + vec2(dx, 0.0))),
+                LineCommand::VerticalLineTo(y) => Some(line(position, point2(position.x, y))),
+                LineCommand::VerticalLineBy(dy) => Some(line(position, position + vec2(0.0, dy))),
+                LineCommand::Close => {
+                    if let Some(mut subpath) = current.take() {
+                        let start = subpath.start;
+                        if position != start {
+                            subpath.segments.push(line(position, start));
+                        }
+                        subpath.closed = true;
+                        position = start;
+                        current = Some(subpath);
+                        finish(&mut current);
+                    }
+                    None
+                }
+                LineCommand::CubicBezierTo {
+                    control_1,
+                    control_2,
+                    end,
+                } => {
+                    cubic_control = Some(control_2);
+                    Some(cubic(position, control_1, control_2, end))
+                }
+                LineCommand::CubicBezierBy {
+                    control_1,
+                    control_2,
+                    end,
+                } => {
+                    cubic_control = Some(position + control_2);
+                    Some(cubic(
+                        position,
+                        position + control_1,
+                        position + control_2,
+                        position + end,
+                    ))
+                }
+                LineCommand::SmoothCubicBezierTo { control, end } => {
+                    let control_1 = reflect(last_cubic_control, position);
+                    cubic_control = Some(control);
+                    Some(cubic(position, control_1, control, end))
+                }
+                LineCommand::SmoothCubicBezierBy { control, end } => {
+                    let control_1 = reflect(last_cubic_control, position);
+                    cubic_control = Some(position + control);
+                    Some(cubic(
+                        position,
+                        control_1,
+                        position + control,
+                        position + end,
+                    ))
+                }
+                LineCommand::QuadraticBezierTo { control, end } => {
+                    quadratic_control = Some(control);
+                    Some(quadratic(position, control, end))
+                }
+                LineCommand::QuadraticBezierBy { control, end } => {
+                    quadratic_control = Some(position + control);
+                    Some(quadratic(position, position + control, position + end))
+                }
+                LineCommand::SmoothQuadtraticCurveTo(end) => {
+                    let control = reflect(last_quadratic_control, position);
+                    quadratic_control = Some(control);
+                    Some(quadratic(position, control, end))
+                }
+                LineCommand::SmoothQuadtraticCurveBy(end) => {
+                    let control = reflect(last_quadratic_control, position);
+                    quadratic_control = Some(control);
+                    Some(quadratic(position, control, position + end))
+                }
+                LineCommand::ArcTo {
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation,
+                    large_arc_flag,
+                    sweep_flag,
+                    end,
+                } => Some(arc(
+                    position,
+                    end,
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation.radians,
+                    large_arc_flag,
+                    sweep_flag,
+                )),
+                LineCommand::ArcBy {
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation,
+                    large_arc_flag,
+                    sweep_flag,
+                    end,
+                } => Some(arc(
+                    position,
+                    position + end,
+                    x_radius,
+                    y_radius,
+                    x_axis_rotation.to_radians(),
+                    large_arc_flag,
+                    sweep_flag,
+                )),
+            };
+
+            last_cubic_control = cubic_control;
+            last_quadratic_control = quadratic_control;
+
+            // Drawing after a `Close` without a move starts a new sub-path at
+            // the closed sub-path's start, which is where `position` is now.
+            if let Some(segment) = segment {
+                position = segment.to();
+                current
+                    .get_or_insert_with(|| SubPath {
+                        start: segment.from(),
+                        segments: vec![],
+                        closed: false,
+                    })
+                    .segments
+                    .push(segment);
+            }
+        }
+
+        finish(&mut current);
+        return subpaths;
+
+        fn line<U>(from: Point2D<f64, U>, to: Point2D<f64, U>) -> Segment<U> {
+            Segment::Line { from, to }
+        }
+
+        fn quadratic<U>(
+            from: Point2D<f64, U>,
+            control: Point2D<f64, U>,
+            to: Point2D<f64, U>,
+        ) -> Segment<U> {
+            Segment::Quadratic { from, control, to }
+        }
+
+        fn cubic<U>(
+            from: Point2D<f64, U>,
+            control_1: Point2D<f64, U>,
+            control_2: Point2D<f64, U>,
+            to: Point2D<f64, U>,
+        ) -> Segment<U> {
+            Segment::Cubic {
+                from,
+                control_1,
+                control_2,
+                to,
+            }
+        }
+
+        fn arc<U>(
+            from: Point2D<f64, U>,
+            to: Point2D<f64, U>,
+            x_radius: f64,
+            y_radius: f64,
+            x_axis_rotation: f64,
+            large_arc: bool,
+            sweep: bool,
+        ) -> Segment<U> {
+            match Arc::from_endpoints(
+                from,
+                to,
+                x_radius,
+                y_radius,
+                x_axis_rotation,
+                large_arc,
+                sweep,
+            ) {
+                Some(arc) => Segment::Arc(arc),
+                None => Segment::Line { from, to },
+            }
+        }
+
+        fn reflect<U>(
+            control: Option<Point2D<f64, U>>,
+            position: Point2D<f64, U>,
+        ) -> Point2D<f64, U> {
+            match control {
+                Some(c) => position + (position - c),
+                None => position,
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Transforming paths exactly under any affine transformation.
+
+use super::segment::Segment;
+use super::{LineCommand, Path};
+use euclid::{vec2, Angle, Transform2D};
+use std::f64::consts::PI;
+
+impl<U> Path<f64, U> {
+    /// Transform this path with the given affine transformation.
+    ///
+    /// Unlike `Path::transform`, which maps every command on its own, this
+    /// resolves the path into absolute segments first, so that relative
+    /// commands, horizontal and vertical lines, and arcs all come out right
+    /// under rotations, skews, and non-uniform scales.
+    pub(crate) fn transform_resolved<V>(
+        &self,
+        transformation: &Transform2D<f64, U, V>,
+    ) -> Path<f64, V> {
+        let mut commands = vec![];
+        for subpath in self.subpaths() {
+            commands.push(LineCommand::MoveTo(
+                transformation.transform_point(subpath.start),
+            ));
+            for segment in &subpath.segments {
+                commands.push(match *segment {
+                    Segment::Line { to, .. } => {
+                        LineCommand::LineTo(transformation.transform_point(to))
+                    }
+                    Segment::Quadratic { control, to, .. } => LineCommand::QuadraticBezierTo {
+                        control: transformation.transform_point(control),
+                        end: transformation.transform_point(to),
+                    },
+                    Segment::Cubic {
+                        control_1,
+                        control_2,
+                        to,
+                        ..
+                    } => LineCommand::CubicBezierTo {
+                        control_1: transformation.transform_point(control_1),
+                        control_2: transformation.transform_point(control_2),
+                        end: transformation.transform_point(to),
+                    },
+                    Segment::Arc(ref arc) => {
+                        // The ellipse is the unit circle mapped through the
+                        // transformation, the ellipse's rotation, and its
+                        // radii. The singular value decomposition of that
+                        // mapping gives the new ellipse's rotation and radii.
+                        let (sin, cos) = arc.x_axis_rotation.sin_cos();
+                        let x_axis = transformation
+                            .transform_vector(vec2(arc.x_radius * cos, arc.x_radius * sin));
+                        let y_axis = transformation
+                            .transform_vector(vec2(-arc.y_radius * sin, arc.y_radius * cos));
+                        let e = (x_axis.x + y_axis.y) / 2.0;
+                        let f = (x_axis.x - y_axis.y) / 2.0;
+                        let g = (x_axis.y + y_axis.x) / 2.0;
+                        let h = (x_axis.y - y_axis.x) / 2.0;
+                        let q = e.hypot(h);
+                        let r = f.hypot(g);
+                        let rotation = (g.atan2(f) + h.atan2(e)) / 2.0;
+
+                        // Mirroring turns clockwise arcs counter-clockwise.
+                        let mirrored = transformation.determinant() < 0.0;
+                        LineCommand::ArcTo {
+                            x_radius: q + r,
+                            y_radius: (q - r).abs(),
+                            x_axis_rotation: Angle::radians(rotation),
+                            large_arc_flag: arc.sweep_angle.abs() > PI,
+                            sweep_flag: (arc.sweep_angle > 0.0) != mirrored,
+                            end: transformation.transform_point(arc.to),
+                        }
+                    }
+                });
+            }
+            if subpath.closed {
+                commands.push(LineCommand::Close);
+            }
+        }
+        Path::with_commands(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{LineCommand, Path};
+    use euclid::{point2, vec2, Angle, Transform2D, UnknownUnit};
+
+    fn assert_same_shape(a: &Path<f64, UnknownUnit>, b: &Path<f64, UnknownUnit>) {
+        assert!((a.length() - b.length()).abs() < 1e-6);
+        for i in 0..=20 {
+            let distance = a.length() * f64::from(i) / 20.0;
+            let (p, q) = (a.point_at(distance).unwrap(), b.point_at(distance).unwrap());
+            assert!((p - q).length() < 1e-6, "{:?} != {:?}", p, q);
+        }
+    }
+
+    #[test]
+    fn relative_and_axis_aligned_commands_survive_rotation() {
+        let path = Path::<f64, UnknownUnit>::with_commands(vec![
+            LineCommand::MoveTo(point2(1.0, 1.0)),
+            LineCommand::HorizontalLineBy(2.0),
+            LineCommand::VerticalLineTo(3.0),
+            LineCommand::LineBy(vec2(-2.0, 0.0)),
+            LineCommand::Close,
+        ]);
+        let rotation = Transform2D::rotation(Angle::degrees(90.0));
+        let rotated: Path<f64, UnknownUnit> = path.transform_resolved(&rotation);
+        let expected = Path::with_commands(vec![
+            LineCommand::MoveTo(point2(-1.0, 1.0)),
+            LineCommand::LineTo(point2(-1.0, 3.0)),
+            LineCommand::LineTo(point2(-3.0, 3.0)),
+            LineCommand::LineTo(point2(-3.0, 1.0)),
+            LineCommand::Close,
+        ]);
+        assert_same_shape(&rotated, &expected);
+    }
+
+    #[test]
+    fn arcs_survive_non_uniform_scales_and_mirroring() {
+        // A half circle, which becomes half of an ellipse.
+        let path = Path::<f64, UnknownUnit>::with_commands(vec![
+            LineCommand::MoveTo(point2(1.0, 0.0)),
+            LineCommand::ArcTo {
+                x_radius: 1.0,
+                y_radius: 1.0,
+                x_axis_rotation: Angle::zero(),
+                large_arc_flag: false,
+                sweep_flag: true,
+                end: point2(-1.0, 0.0),
+            },
+        ]);
+        let scale = Transform2D::scale(2.0, -3.0);
+        let scaled: Path<f64, UnknownUnit> = path.transform_resolved(&scale);
+        let expected = Path::with_commands(
+            (0..=1000)
+                .map(|i| {
+                    let angle = std::f64::consts::PI * f64::from(i) / 1000.0;
+                    point2(2.0 * angle.cos(), -3.0 * angle.sin())
+                })
+                .enumerate()
+                .map(|(i, p)| {
+                    if i == 0 {
+                        LineCommand::MoveTo(p)
+                    } else {
+                        LineCommand::LineTo(p)
+                    }
+                }),
+        );
+        for i in 0..=20 {
+            let t = f64::from(i) / 20.0;
+            let p = scaled.point_at(scaled.length() * t).unwrap();
+            let q = expected.point_at(expected.length() * t).unwrap();
+            assert!((p - q).length() < 1e-3, "{:?} != {:?}", p, q);
+        }
+    }
+}
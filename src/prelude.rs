@@ -2,7 +2,8 @@
 
 pub use crate::{
     canvas::{Canvas, CanvasSpace},
-    path::{LineCommand, Path, ToPaths},
+    hatch::Hatch,
+    path::{LineCommand, Path, ToPaths, ToPathsExt},
     process::Process,
     units::{Inches, Millis, Paper},
     user_const, Config,
@@ -0,0 +1,182 @@
+//! Simplifying paths by removing vertices.
+//!
+//! Generative geometry often has far more vertices than a plotter can resolve,
+//! which only makes for huge files and slow plots.
+
+use crate::path::{is_dot, Path, ToPaths};
+
+/// The algorithm to simplify with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    /// The Ramer-Douglas-Peucker algorithm, which never moves the path by
+    /// more than the tolerance. See `fart_2d_geom::Polyline::simplify`.
+    RamerDouglasPeucker,
+
+    /// The Visvalingam-Whyatt algorithm, which tends to keep the overall shape
+    /// better. See `fart_2d_geom::Polyline::simplify_visvalingam`.
+    Visvalingam,
+}
+
+/// A simplification of paths: an algorithm and a tolerance.
+///
+/// # Example
+///
+/// ```
+/// use fart::euclid::{point2, UnknownUnit};
+/// use fart::path::{LineCommand, Path};
+/// use fart::simplify::{Method, Simplify};
+///
+/// let path = Path::<f64, UnknownUnit>::with_commands(
+///     Some(LineCommand::MoveTo(point2(0.0, 0.0)))
+///         .into_iter()
+///         .chain((1..=100).map(|i| LineCommand::LineTo(point2(f64::from(i), 0.0)))),
+/// );
+///
+/// let simplify = Simplify::new(Method::RamerDouglasPeucker).tolerance(0.1);
+/// assert_eq!(simplify.path(&path).commands.len(), 2);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Simplify {
+    method: Method,
+    tolerance: Option<f64>,
+}
+
+impl Simplify {
+    /// Construct a new simplification with the given algorithm.
+    ///
+    /// It has no tolerance yet.
+    pub fn new(method: Method) -> Simplify {
+        Simplify {
+            method,
+            tolerance: None,
+        }
+    }
+
+    /// Set how far the simplified paths may stray from the original paths.
+    ///
+    /// For Visvalingam-Whyatt, vertices are removed while the triangles they
+    /// form with their neighbours have less area than a right triangle whose
+    /// legs are the tolerance.
+    ///
+    /// When simplifying with `Canvas::simplify`, this defaults to half the
+    /// nib size of the layer's pen, which is too small to see on paper.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tolerance` is negative.
+    pub fn tolerance(mut self, tolerance: f64) -> Simplify {
+        assert!(
+            tolerance >= 0.0,
+            "simplification tolerance must not be negative"
+        );
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// Use the given tolerance if this simplification doesn't have one yet.
+    pub(crate) fn or_tolerance(mut self, tolerance: f64) -> Simplify {
+        self.tolerance = self.tolerance.or(Some(tolerance));
+        self
+    }
+
+    /// Simplify the given path.
+    ///
+    /// The path is flattened into straight lines first, with half of the
+    /// tolerance, and those lines are then simplified with the other half.
+    /// Sub-paths that don't draw anything are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this simplification doesn't have a tolerance.
+    pub fn path<U>(&self, path: &Path<f64, U>) -> Path<f64, U> {
+        self.path_with_counts(path).0
+    }
+
+    /// Simplify the given path, and also get how many vertices it had when
+    /// flattened, and how many it has after simplifying.
+    pub(crate) fn path_with_counts<U>(&self, path: &Path<f64, U>) -> (Path<f64, U>, usize, usize) {
+        let tolerance = self.tolerance.expect("simplification needs a tolerance");
+
+        let mut before = 0;
+        let mut after = 0;
+        let mut commands = vec![];
+        // Flattening needs a positive tolerance, and it doesn't hurt to
+        // flatten finely when asked to keep every vertex.
+        for polyline in path.flatten((tolerance / 2.0).max(1e-6)) {
+            // Dots have nothing to remove, and count as a single vertex.
+            if is_dot(&polyline) {
+                before += 1;
+                after += 1;
+                commands.extend(polyline.to_paths().flat_map(|p| p.commands));
+                continue;
+            }
+            let simplified = match self.method {
+                Method::RamerDouglasPeucker => polyline.simplify(tolerance / 2.0),
+                Method::Visvalingam => polyline.simplify_visvalingam(tolerance * tolerance / 2.0),
+            };
+            before += polyline.len();
+            after += simplified.len();
+            commands.extend(simplified.to_paths().flat_map(|p| p.commands));
+        }
+        (Path::with_commands(commands), before, after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::LineCommand;
+    use euclid::{point2, UnknownUnit};
+
+    fn wiggle() -> Path<f64, UnknownUnit> {
+        // A sine wave with a tiny bit of noise on top.
+        Path::with_commands(
+            (0..=1000)
+                .map(|i| {
+                    let x = f64::from(i) / 100.0;
+                    let noise = if i % 2 == 0 { 0.001 } else { -0.001 };
+                    point2(x, x.sin() + noise)
+                })
+                .enumerate()
+                .map(|(i, p)| {
+                    if i == 0 {
+                        LineCommand::MoveTo(p)
+                    } else {
+                        LineCommand::LineTo(p)
+                    }
+                }),
+        )
+    }
+
+    #[test]
+    fn both_methods_remove_most_vertices_and_keep_the_shape() {
+        let path = wiggle();
+        for &method in &[Method::RamerDouglasPeucker, Method::Visvalingam] {
+            let (simplified, before, after) = Simplify::new(method)
+                .tolerance(0.05)
+                .path_with_counts(&path);
+            assert_eq!(before, 1001);
+            assert!(after < 100, "{:?} kept {} vertices", method, after);
+            assert_eq!(simplified.commands.len(), after);
+            for i in 0..=100 {
+                let distance = simplified.length() * f64::from(i) / 100.0;
+                let p = simplified.point_at(distance).unwrap();
+                assert!(
+                    (p.y - p.x.sin()).abs() < 0.1,
+                    "{:?} strayed at {:?}",
+                    method,
+                    p
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn zero_tolerance_keeps_every_corner() {
+        let path = wiggle();
+        let simplified = Simplify::new(Method::RamerDouglasPeucker)
+            .tolerance(0.0)
+            .path(&path);
+        assert_eq!(simplified.commands.len(), 1001);
+    }
+}
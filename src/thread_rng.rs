@@ -5,6 +5,11 @@ crate::user_const! {
     const RNG_SEED: u64 = 69_420;
 }
 
+/// Get the seed that every thread's `FartThreadRng` is seeded with.
+pub(crate) fn seed() -> u64 {
+    *RNG_SEED
+}
+
 thread_local! {
     static THREAD_RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(*RNG_SEED));
 }
@@ -46,6 +46,11 @@ macro_rules! user_const {
                     stringify!($ty),
                     value
                 );
+                $crate::__record_user_const(
+                    stringify!($name),
+                    stringify!($ty),
+                    env::var(env_var_name).unwrap_or_else(|_| format!("{:?}", value)),
+                );
 
                 value
             };
@@ -0,0 +1,63 @@
+//! Escaping the text that fart writes into SVGs and reads back out of them.
+//!
+//! The `svg` crate escapes `&`, `<`, and `>` in attribute values and text
+//! when it writes them, but its parser leaves them escaped, so everything
+//! read from an SVG is unescaped here. It doesn't escape double quotes in
+//! attribute values at all, so attribute values are fully escaped before the
+//! `svg` crate escapes them again, and are unescaped twice when read back.
+
+/// Escape the given text for the `svg` crate to write as an attribute value.
+/// Read it back with `attribute_value`.
+pub(crate) fn attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Get the value of an attribute written with `attribute`, undoing both its
+/// escaping and the `svg` crate's.
+pub(crate) fn attribute_value(value: &str) -> String {
+    unescape(&unescape(value))
+}
+
+/// Undo the escaping of XML special characters in text read from an SVG.
+pub(crate) fn unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svg::node::element::Element;
+    use svg::node::Text;
+    use svg::parser::{Event, Parser};
+    use svg::Node;
+
+    #[test]
+    fn special_characters_round_trip() {
+        let value = r#"Faber & Castell "Pitt" <1.5mm> &amp; &quot;"#;
+        let mut element = Element::new("fart:test");
+        element.assign("value", attribute(value));
+        element.append(Text::new(value));
+        let svg = element.to_string();
+
+        let mut events = Parser::new(&svg);
+        match events.next() {
+            Some(Event::Tag("fart:test", _, attributes)) => {
+                assert_eq!(attribute_value(&attributes["value"]), value)
+            }
+            event => panic!("expected <fart:test>, found {:?}", event),
+        }
+        match events.next() {
+            Some(Event::Text(text)) => assert_eq!(unescape(text), value),
+            event => panic!("expected text, found {:?}", event),
+        }
+    }
+}